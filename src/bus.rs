@@ -1,6 +1,6 @@
 use crate::{
     cartridge::{Cartridge, PrgRom},
-    ppu::Ppu2C02,
+    ppu::{Ppu2C02, PpuEventKind},
 };
 use crate::{cpu::Cpu6502, pad::Pad};
 
@@ -46,6 +46,10 @@ pub struct Bus {
     pub pad1: Pad,
     pub pad2: Pad,
     pub ram: [u8; 2048],
+    // Último valor que passou pelo barramento de dados. Endereços sem nenhum
+    // dispositivo ("open bus") devolvem esse valor, porque nada muda as linhas
+    // de dados depois do último acesso
+    pub open_bus: u8,
     // A count of how many clocks have passed
     pub system_clock_counter: u32,
 
@@ -79,6 +83,7 @@ impl Bus {
             ppu: Ppu2C02::new(chr_rom),
             prg_rom,
             ram: [0; 2048],
+            open_bus: 0,
             pad1: Pad::new(),
             pad2: Pad::new(),
             system_clock_counter: 0,
@@ -91,40 +96,55 @@ impl Bus {
     }
 
     pub fn read(&mut self, addres: u16, read_only: bool) -> u8 {
-        // println!("bus->read({:#06x})", addres);
-        // println!("bus->read: {}", self.version);
+        let data = self.read_data(addres, read_only);
 
+        // Leituras de depuração não passam pelo barramento de verdade
+        if !read_only {
+            self.open_bus = data;
+        }
+
+        data
+    }
+
+    fn read_data(&mut self, addres: u16, read_only: bool) -> u8 {
         let (read, data) = self.prg_rom.read(addres);
-        // println!("bus->read({:#06x}) - cart is true: {}", addres, read);
         if read {
             return data;
         }
 
         // Ram
         if addres <= 0x1FFF {
-            // println!("bus->read({:#06x}) - ram", addres);
             return self.ram[addres as usize & 0x07FF];
         }
 
         if addres >= 0x2000 && addres <= 0x3FFF {
-            // println!("bus->read({:#06x}) - ppu", addres);
             // PPU Address range, mirrored every 8
-            return self.ppu.cpu_read(addres & 0x0007, read_only);
+            let data = self.ppu.cpu_read(addres & 0x0007, read_only);
+            if !read_only {
+                self.ppu.log_event(PpuEventKind::RegisterRead, addres, data);
+            }
+            return data;
         }
 
-        // Pads
+        // Pads, só os bits 0-4 são ligados ao controle, os 3 bits
+        // de cima continuam com o valor do barramento (normalmente
+        // o byte alto do endereço, 0x40)
         if addres >= 0x4016 && addres <= 0x4017 {
-            return match addres {
+            let data = match addres {
+                0x4016 if read_only => self.pad1.peek(),
+                0x4017 if read_only => self.pad2.peek(),
                 0x4016 => self.pad1.read(),
-                0x4017 => self.pad2.read(),
-                _ => 0,
+                _ => self.pad2.read(),
             };
+            return (self.open_bus & 0xE0) | (data & 0x1F);
         }
 
-        0
+        self.open_bus
     }
 
     pub fn write(&mut self, addres: u16, data: u8) {
+        self.open_bus = data;
+
         if self.prg_rom.write(addres, data) {
             return;
         }
@@ -139,12 +159,16 @@ impl Bus {
             // and these are repeated throughout this range. We can
             // use bitwise AND operation to mask the bottom 3 bits,
             // which is the equivalent of addr % 8.
+            self.ppu
+                .log_event(PpuEventKind::RegisterWrite, addres, data);
             self.ppu.cpu_write(addres & 0x0007, data);
             return;
         }
 
         if addres == 0x4014 {
             // A write to this address initiates a DMA transfer
+            self.ppu
+                .log_event(PpuEventKind::RegisterWrite, addres, data);
             self.dma_page = data;
             self.dma_addr = 0x00;
             self.dma_transfer = true;
@@ -43,6 +43,30 @@ impl Cartridge {
 
         cart
     }
+
+    /** Cartucho com 64KB de memória plana cobrindo todo o barramento da CPU,
+    o conteúdo de `memory` é copiado a partir do endereço `addres` */
+    pub fn flat(addres: u16, memory: &[u8]) -> Cartridge {
+        let mut cart = Cartridge {
+            image_valid: true,
+            mirror: Mirror::Horizontal,
+            mapper_id: 0,
+            prg_banks: 0,
+            chr_banks: 0,
+            prg_memory: vec![],
+            chr_memory: vec![],
+            mapper: Mapper::create_mapper_flat(),
+        };
+
+        cart.prg_memory.resize(65536, 0);
+        cart.chr_memory.resize(8192, 0);
+
+        let start = addres as usize;
+        let end = (start + memory.len()).min(65536);
+        cart.prg_memory[start..end].copy_from_slice(&memory[..end - start]);
+
+        cart
+    }
 }
 
 /**
@@ -9,7 +9,9 @@ use super::Cpu6502;
 // O processador 6502 pode acessar entre o endereço 0x0000 e 0xFFFF, onde o Byte alto é referente a página na memória
 // e assim temos 256 páginas com 256 bytes cada página.
 
-// Alguns modos de endereçamento precisam de mais ciclos e para isso retornamos os ciclos adicionais necessários para cada modo
+// Cada modo de endereçamento é executado ciclo a ciclo, cada chamada faz exatamente um acesso ao barramento
+// (com exceção do Implied e Immediate que não gastam ciclos aqui) e retorna true quando o endereço efetivo
+// (addr_abs) está pronto. O "step" é o ciclo da instrução, o ciclo 0 é a leitura do opcode.
 
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub enum AddressMode {
@@ -42,28 +44,27 @@ pub enum AddressMode {
 }
 
 impl Cpu6502 {
-    pub fn addres_mode(&mut self, addr_mode: AddressMode) -> u8 {
+    pub fn addres_mode(&mut self, addr_mode: AddressMode, step: u8) -> bool {
         match addr_mode {
             AddressMode::IMP => self.imp(),
             AddressMode::IMM => self.imm(),
             AddressMode::ZP0 => self.zp0(),
-            AddressMode::ZPX => self.zpx(),
-            AddressMode::ZPY => self.zpy(),
+            AddressMode::ZPX => self.zpx(step),
+            AddressMode::ZPY => self.zpy(step),
             AddressMode::REL => self.rel(),
-            AddressMode::ABS => self.abs(),
-            AddressMode::ABX => self.abx(),
-            AddressMode::ABY => self.aby(),
-            AddressMode::IND => self.ind(),
-            AddressMode::IZX => self.izx(),
-            AddressMode::IZY => self.izy(),
+            AddressMode::ABS => self.abs(step),
+            AddressMode::ABX => self.abx(step),
+            AddressMode::ABY => self.aby(step),
+            AddressMode::IND => self.ind(step),
+            AddressMode::IZX => self.izx(step),
+            AddressMode::IZY => self.izy(step),
             AddressMode::ACC => self.acc(),
         }
     }
 
-    fn acc(&mut self) -> u8 {
-        self.fetched = 0;
-        println!("ACCUMULATOR ADDRESS");
-        0
+    fn acc(&mut self) -> bool {
+        self.fetched = self.a;
+        true
     }
 
     /** Address Mode: Implied.
@@ -71,20 +72,20 @@ impl Cpu6502 {
         Nesse modo a instrução não recebe nenhum argumento ou utiliza o acumulador como argumento, por isso
     vamos apenas ler o acumulador
          */
-    fn imp(&mut self) -> u8 {
+    fn imp(&mut self) -> bool {
         self.fetched = self.a;
-        0
+        true
     }
 
     /** Address Mode: Immediate.
 
         Como o próprio nome diz, a instrução é imediata e utiliza o próximo byte como argumento, vamos preparar para ler o
-    endereço do próximo byte
+    endereço do próximo byte. A leitura acontece no mesmo ciclo, junto com o acesso ao operando.
          */
-    fn imm(&mut self) -> u8 {
+    fn imm(&mut self) -> bool {
         self.addr_abs = self.pc;
         self.pc_next();
-        0
+        true
     }
 
     /** Address Mode: Zero Page.
@@ -93,33 +94,43 @@ impl Cpu6502 {
     para montar o endereço da página zero que será utilizado.
     Como vamos ler da página zero precisamos somente de 1 byte para saber o endereço
     */
-    fn zp0(&mut self) -> u8 {
+    fn zp0(&mut self) -> bool {
         self.addr_abs = self.read(self.pc) as u16;
         self.pc_next();
-        self.addr_abs &= 0x00FF;
-        0
+        true
     }
 
     /** Address Mode:  Zero Page with X Offset.
 
-    Mesmo funcionamento do Zero Page, porém vamos utilizar o registrador x para incrementar o offset
+    Mesmo funcionamento do Zero Page, porém vamos utilizar o registrador x para incrementar o offset.
+    No segundo ciclo a CPU lê o endereço ainda sem o offset (leitura "dummy") enquanto faz a soma.
     */
-    fn zpx(&mut self) -> u8 {
-        self.addr_abs = self.read(self.pc) as u16 + self.x as u16;
-        self.pc_next();
-        self.addr_abs &= 0x00FF;
-        0
+    fn zpx(&mut self, step: u8) -> bool {
+        if step == 1 {
+            self.addr_abs = self.read(self.pc) as u16;
+            self.pc_next();
+            return false;
+        }
+
+        self.read(self.addr_abs);
+        self.addr_abs = (self.addr_abs + self.x as u16) & 0x00FF;
+        true
     }
 
     /** Address Mode: Zero Page with Y Offset.
 
     Mesma coisa do Zero Page with X Offset, porém utilizando o registrador y.
     */
-    fn zpy(&mut self) -> u8 {
-        self.addr_abs = self.read(self.pc) as u16 + self.y as u16;
-        self.pc_next();
-        self.addr_abs &= 0x00FF;
-        0
+    fn zpy(&mut self, step: u8) -> bool {
+        if step == 1 {
+            self.addr_abs = self.read(self.pc) as u16;
+            self.pc_next();
+            return false;
+        }
+
+        self.read(self.addr_abs);
+        self.addr_abs = (self.addr_abs + self.y as u16) & 0x00FF;
+        true
     }
 
     /** Address Mode: Relative.
@@ -128,7 +139,7 @@ impl Cpu6502 {
     entre -128 e +127 relativo ao endereço atual. Vamos ler o valor do prómio byte para
     montar o endereço relativo.
     */
-    fn rel(&mut self) -> u8 {
+    fn rel(&mut self) -> bool {
         self.addr_rel = self.read(self.pc) as u16;
         self.pc_next();
 
@@ -137,17 +148,32 @@ impl Cpu6502 {
             self.addr_rel |= 0xFF00;
         }
 
-        0
+        true
     }
 
     /** Address Mode: Absolute.
 
        Um endereço completo de 16 bits será utilizado como argumento, vamos ler os próximos 2 bytes
     */
-    fn abs(&mut self) -> u8 {
-        self.addr_abs = self.read_next_16b();
+    fn abs(&mut self, step: u8) -> bool {
+        if step == 1 {
+            self.addr_abs = self.read(self.pc) as u16;
+            self.pc_next();
+            return false;
+        }
+
+        self.addr_abs |= (self.read(self.pc) as u16) << 8;
+        self.pc_next();
+        true
+    }
 
-        0
+    // Soma o indice apenas no byte baixo do endereço, assim como o hardware faz. Caso mude de página
+    // o byte alto é corrigido depois de uma leitura "dummy" no endereço errado (ver micro_step.rs)
+    fn indexed(&mut self, hi: u8, index: u8) {
+        let lo = (self.addr_abs & 0x00FF) + index as u16;
+        self.page_crossed = lo > 0x00FF;
+        self.fix_up = true;
+        self.addr_abs = ((hi as u16) << 8) | (lo & 0x00FF);
     }
 
     /** Address Mode:  Absolute with X Offset.
@@ -155,30 +181,34 @@ impl Cpu6502 {
     Mesma coisa do Absolute, porém utilizando o registrador x como offset.
     Caso o endereço resultado mudar de página um ciclo adicional é necessário.
     */
-    fn abx(&mut self) -> u8 {
-        let addr_abs = self.read_next_16b();
-        self.addr_abs = addr_abs + self.x as u16;
-
-        if (self.addr_abs & 0xFF00) != (addr_abs & 0xFF00) {
-            return 1;
+    fn abx(&mut self, step: u8) -> bool {
+        if step == 1 {
+            self.addr_abs = self.read(self.pc) as u16;
+            self.pc_next();
+            return false;
         }
 
-        0
+        let hi = self.read(self.pc);
+        self.pc_next();
+        self.indexed(hi, self.x);
+        true
     }
 
     /** Address Mode:  Absolute with Y Offset.
 
         Mesma funcionalidade do ABX porém utilizando o registrador y como offset.
     */
-    fn aby(&mut self) -> u8 {
-        let addr_abs = self.read_next_16b();
-        self.addr_abs = addr_abs + self.y as u16;
-
-        if (self.addr_abs & 0xFF00) != (addr_abs & 0xFF00) {
-            return 1;
+    fn aby(&mut self, step: u8) -> bool {
+        if step == 1 {
+            self.addr_abs = self.read(self.pc) as u16;
+            self.pc_next();
+            return false;
         }
 
-        0
+        let hi = self.read(self.pc);
+        self.pc_next();
+        self.indexed(hi, self.y);
+        true
     }
 
     /** Address Mode: Indirect.
@@ -188,16 +218,28 @@ impl Cpu6502 {
     tem um bug no hardware, pois quando o byte inferior (lsb) for 0xFF em vez do próximo endereço ser
     da próxima pagina a instrução lê o byte do começo da página, vamos emular esse bug também.
     */
-    fn ind(&mut self) -> u8 {
-        let ptr = self.read_next_16b();
-
-        if (ptr & 0x00FF) == 0x00FF {
-            self.addr_abs = ((self.read(ptr & 0xFF00) as u16) << 8) | self.read(ptr) as u16;
-        } else {
-            self.addr_abs = ((self.read(ptr + 1) as u16) << 8) | self.read(ptr) as u16;
+    fn ind(&mut self, step: u8) -> bool {
+        match step {
+            1 => {
+                self.temp = self.read(self.pc) as u16;
+                self.pc_next();
+                false
+            }
+            2 => {
+                self.temp |= (self.read(self.pc) as u16) << 8;
+                self.pc_next();
+                false
+            }
+            3 => {
+                self.addr_abs = self.read(self.temp) as u16;
+                false
+            }
+            _ => {
+                let ptr = (self.temp & 0xFF00) | (self.temp.wrapping_add(1) & 0x00FF);
+                self.addr_abs |= (self.read(ptr) as u16) << 8;
+                true
+            }
         }
-
-        0
     }
 
     /** Address Mode: Indirect X.
@@ -206,17 +248,27 @@ impl Cpu6502 {
     a location in page 0x00. The actual 16-bit address is read
     from this location
         */
-    fn izx(&mut self) -> u8 {
-        let t = self.read(self.pc) as u16;
-        self.pc_next();
-
-        let addr = (t + self.x as u16) & 0x00FF;
-        let lo = self.read(addr & 0x00FF) as u16;
-        let hi = self.read((addr + 1) & 0x00FF) as u16;
-
-        self.addr_abs = (hi << 8) | lo;
-
-        0
+    fn izx(&mut self, step: u8) -> bool {
+        match step {
+            1 => {
+                self.temp = self.read(self.pc) as u16;
+                self.pc_next();
+                false
+            }
+            2 => {
+                self.read(self.temp);
+                self.temp = (self.temp + self.x as u16) & 0x00FF;
+                false
+            }
+            3 => {
+                self.addr_abs = self.read(self.temp) as u16;
+                false
+            }
+            _ => {
+                self.addr_abs |= (self.read((self.temp + 1) & 0x00FF) as u16) << 8;
+                true
+            }
+        }
     }
 
     /** Address Mode: Indirect Y.
@@ -226,19 +278,22 @@ impl Cpu6502 {
     Y Register is added to it to offset it. If the offset causes a
     change in page then an additional clock cycle is required.
     */
-    fn izy(&mut self) -> u8 {
-        let t = self.read(self.pc) as u16;
-        self.pc_next();
-        let lo = self.read(t & 0x00FF) as u16;
-        let hi = self.read((t + 1) & 0x00FF) as u16;
-
-        self.addr_abs = (hi << 8) | lo;
-        self.addr_abs = self.addr_abs + self.y as u16;
-
-        if (self.addr_abs & 0xFF00) != (hi << 8) {
-            return 1;
+    fn izy(&mut self, step: u8) -> bool {
+        match step {
+            1 => {
+                self.temp = self.read(self.pc) as u16;
+                self.pc_next();
+                false
+            }
+            2 => {
+                self.addr_abs = self.read(self.temp) as u16;
+                false
+            }
+            _ => {
+                let hi = self.read((self.temp + 1) & 0x00FF);
+                self.indexed(hi, self.y);
+                true
+            }
         }
-
-        0
     }
 }
@@ -1,7 +1,8 @@
 use crate::bus::Bus;
+use crate::debugger::{CodeDataLog, Symbols};
 
 // O registrador de status armazena 8 flags, para facilitar o acesso foi criado um enum para cada flag
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Flags6502 {
     /** Carry Bit */
     C = 1 << 0,
@@ -21,6 +22,21 @@ pub enum Flags6502 {
     N = 1 << 7,
 }
 
+// Tipo de acesso feito pela CPU no barramento
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BusOperation {
+    Read,
+    Write,
+}
+
+// Um acesso ao barramento, usado para conferir ciclo a ciclo o que a CPU leu e escreveu
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BusActivity {
+    pub addres: u16,
+    pub data: u8,
+    pub operation: BusOperation,
+}
+
 pub struct Cpu6502 {
     // CPU registradores
     /** Registro Acumulador */
@@ -47,10 +63,36 @@ pub struct Cpu6502 {
     pub addr_rel: u16,
     /** Is the instruction byte */
     pub opcode: u8,
-    /** Counts how many cycles the instruction has remaining */
-    pub cycles: u8,
+    /** Cycle of the current instruction that will run on the next clock (0 = fetch the next opcode) */
+    pub step: u8,
+    /** The effective address is ready, the next cycles access the operand */
+    pub address_ready: bool,
+    /** Indexed address crossed a page, the high byte must be fixed after a dummy read */
+    pub page_crossed: bool,
+    /** Indexed address always spends one cycle on a dummy read before writing (write and read-modify-write) */
+    pub fix_up: bool,
+    /** Cycle of the operand access (read, dummy write and write) */
+    pub operand_step: u8,
+    /** The branch condition was true */
+    pub branch_taken: bool,
+    /** The instruction being executed is an IRQ/NMI sequence instead of an opcode */
+    pub hardware_interrupt: bool,
+    /** NMI edge detected, will be serviced at the end of the current instruction */
+    pub nmi_pending: bool,
+    /** IRQ requested, will be serviced at the end of an instruction while the I flag is clear */
+    pub irq_pending: bool,
+    /** Interrupt polling result, sampled before the last cycle of each instruction */
+    pub interrupt_poll: bool,
+    /** A JAM opcode locked the CPU, only a reset brings it back */
+    pub halted: bool,
     /** A global accumulation of the number of clocks */
     pub clock_count: u32,
+    /** Registro dos acessos ao barramento, só é preenchido quando habilitado */
+    pub bus_log: Option<Vec<BusActivity>>,
+    /** Nomes dos endereços, usados na disassembly e no debugger */
+    pub symbols: Symbols,
+    /** Code/Data Logger da PRG, só existe enquanto está gravando */
+    pub cdl: Option<CodeDataLog>,
     pub bus: Bus,
 }
 
@@ -71,8 +113,21 @@ impl Cpu6502 {
             addr_abs: 0,
             addr_rel: 0,
             opcode: 0,
-            cycles: 0,
+            step: 0,
+            address_ready: false,
+            page_crossed: false,
+            fix_up: false,
+            operand_step: 0,
+            branch_taken: false,
+            hardware_interrupt: false,
+            nmi_pending: false,
+            irq_pending: false,
+            interrupt_poll: false,
+            halted: false,
             clock_count: 0,
+            bus_log: None,
+            symbols: Symbols::new(),
+            cdl: None,
             bus,
         }
     }
@@ -81,10 +136,13 @@ impl Cpu6502 {
 // Conectividade com a Bus
 impl Cpu6502 {
     pub fn read(&mut self, addres: u16) -> u8 {
-        return self.bus.read(addres, false);
+        let data = self.bus.read(addres, false);
+        self.log_bus_activity(addres, data, BusOperation::Read);
+        data
     }
 
     pub fn write(&mut self, addres: u16, data: u8) {
+        self.log_bus_activity(addres, data, BusOperation::Write);
         self.bus.write(addres, data);
     }
 
@@ -93,6 +151,29 @@ impl Cpu6502 {
     }
 }
 
+// Registro dos acessos ao barramento
+impl Cpu6502 {
+    fn log_bus_activity(&mut self, addres: u16, data: u8, operation: BusOperation) {
+        if let Some(log) = &mut self.bus_log {
+            log.push(BusActivity {
+                addres,
+                data,
+                operation,
+            });
+        }
+    }
+
+    /** Começa a registrar todos os acessos feitos pela CPU no barramento */
+    pub fn start_bus_log(&mut self) {
+        self.bus_log = Some(vec![]);
+    }
+
+    /** Para de registrar e retorna os acessos registrados até agora */
+    pub fn stop_bus_log(&mut self) -> Vec<BusActivity> {
+        self.bus_log.take().unwrap_or_default()
+    }
+}
+
 // Funções para manipular flags
 impl Cpu6502 {
     pub fn get_flag(&mut self, flag: Flags6502) -> u8 {
@@ -121,45 +202,29 @@ impl Cpu6502 {
 // Funções auxiliares
 impl Cpu6502 {
     pub fn pc_next(&mut self) -> u16 {
-        self.pc += 1;
-        self.pc
-    }
-
-    pub fn pc_back(&mut self) -> u16 {
-        self.pc -= 1;
+        self.pc = self.pc.wrapping_add(1);
         self.pc
     }
 
     pub fn stkp_push(&mut self, value: u8) {
         self.write(0x0100 + self.stkp as u16, value);
-        self.stkp -= 1;
-
-        // if self.stkp == 0 {
-        //     self.stkp = 255;
-        // } else {
-        //     self.stkp -= 1;
-        // }
+        self.stkp = self.stkp.wrapping_sub(1);
     }
 
     pub fn stkp_pop(&mut self) -> u8 {
-        self.stkp += 1;
-        // if self.stkp == 255 {
-        //     self.stkp = 0;
-        // } else {
-        //     self.stkp += 1;
-        // }
+        self.stkp = self.stkp.wrapping_add(1);
         self.read(0x0100 + self.stkp as u16)
     }
 
-    pub fn pc_branch(&mut self) {
-        self.cycles += 1;
-        self.addr_abs = self.pc + self.addr_rel;
-
-        if (self.addr_abs & 0xFF00) != (self.pc & 0xFF00) {
-            self.cycles += 1;
-        }
+    /** Leitura da stack sem alterar o stack pointer (usada nos ciclos "dummy") */
+    pub fn stkp_peek(&mut self) -> u8 {
+        self.read(0x0100 + self.stkp as u16)
+    }
 
-        self.pc = self.addr_abs;
+    // O desvio só é calculado aqui, o PC é atualizado nos próximos ciclos da instrução
+    pub fn pc_branch(&mut self) {
+        self.addr_abs = self.pc.wrapping_add(self.addr_rel);
+        self.branch_taken = true;
     }
 
     pub fn read_16b(&mut self, addres: u16) -> u16 {
@@ -176,15 +241,6 @@ impl Cpu6502 {
         self.write(addres + 1, hi);
     }
 
-    pub fn read_next_16b(&mut self) -> u16 {
-        let lo = self.read(self.pc) as u16;
-        self.pc_next();
-        let hi = self.read(self.pc) as u16;
-        self.pc_next();
-
-        (hi << 8) | lo
-    }
-
     pub fn load(&mut self, addres: u16, data: Vec<u8>) {
         let addres = addres as usize;
         self.bus.ram[addres..(addres + data.len())].copy_from_slice(&data[..]);
@@ -27,46 +27,19 @@ impl Cpu6502 {
         self.addr_abs = 0;
         self.fetched = 0;
 
-        self.cycles = 8;
+        // a próxima chamada de cpu_clock() já lê o primeiro opcode
+        self.clear_micro_state();
     }
 
-    /** Interrupt requests are a complex operation and only happen if the
-    "disable interrupt" flag is 0. IRQs can happen at any time, but
-    you dont want them to be destructive to the operation of the running
-    program. Therefore the current instruction is allowed to finish
-    (which I facilitate by doing the whole thing when cycles == 0) and
-    then the current program counter is stored on the stack. Then the
-    current status register is stored on the stack. When the routine
-    that services the interrupt has finished, the status register
-    and program counter can be restored to how they where before it
-    occurred. This is impemented by the "RTI" instruction. Once the IRQ
-    has happened, in a similar way to a reset, a programmable address
-    is read form hard coded location 0xFFFE, which is subsequently
-    set to the program counter.
+    /** Interrupt requests only happen if the "disable interrupt" flag is 0.
+    The request is only registered here, the CPU polls the interrupt lines
+    before the last cycle of every instruction and, if the IRQ is still
+    pending and the I flag is clear, runs the same 7 cycle sequence of the
+    BRK instruction (see micro_step.rs) reading the new program counter
+    from the hard coded location 0xFFFE.
     */
     pub fn irq(&mut self) {
-        if self.get_flag(Flags6502::I) == 0 {
-            // Vamos adicionar o pc (program counter) a stack, como o pc é 16 bits vamos ter que dividir em 2 e adicionar as duas partes
-            self.write(0x0100 + self.stkp as u16, ((self.pc >> 8) & 0x00FF) as u8);
-            self.stkp -= 1;
-            self.write(0x0100 + self.stkp as u16, (self.pc & 0x00FF) as u8);
-            self.stkp -= 1;
-
-            // Adicionando o status na stack
-            self.set_flag(Flags6502::B, false);
-            self.set_flag(Flags6502::U, true);
-            self.set_flag(Flags6502::I, true);
-            self.write(0x0100 + self.stkp as u16, self.status);
-            self.stkp -= 1;
-
-            // Ler novo pc (program counter) a partir de um endereço fixo
-            self.addr_abs = 0xFFFE;
-            let lo = self.read(self.addr_abs + 0);
-            let hi = self.read(self.addr_abs + 1);
-            self.pc = ((hi << 8) | lo) as u16;
-
-            self.cycles = 7;
-        }
+        self.irq_pending = true;
     }
 
     /** A Non-Maskable Interrupt cannot be ignored. It behaves in exactly the
@@ -74,81 +47,48 @@ impl Cpu6502 {
     form location 0xFFFA.
         */
     pub fn nmi(&mut self) {
-        self.write(0x0100 + self.stkp as u16, ((self.pc >> 8) & 0x00FF) as u8);
-        self.stkp -= 1;
-        self.write(0x0100 + self.stkp as u16, (self.pc & 0x00FF) as u8);
-        self.stkp -= 1;
-
-        // Adicionando o status na stack
-        self.set_flag(Flags6502::B, false);
-        self.set_flag(Flags6502::U, true);
-        self.set_flag(Flags6502::I, true);
-        self.write(0x0100 + self.stkp as u16, self.status);
-        self.stkp -= 1;
-
-        // Ler novo pc (program counter) a partir de um endereço fixo
-        self.addr_abs = 0xFFFA;
-        let lo = self.read(self.addr_abs + 0);
-        let hi = self.read(self.addr_abs + 1);
-        self.pc = ((hi as u16) << 8) | lo as u16;
-
-        self.cycles = 8;
+        self.nmi_pending = true;
     }
 
     /** Perform one clock cycles worth of emulation */
     pub fn cpu_clock(&mut self) {
-        // println!("cycles: {}", self.cycles);
-        // Each instruction requires a variable number of clock cycles to execute.
-        // In my emulation, I only care about the final result and so I perform
-        // the entire computation in one hit. In hardware, each clock cycle would
-        // perform "microcode" style transformations of the CPUs state.
-        //
-        // To remain compliant with connected devices, it's important that the
-        // emulation also takes "time" in order to execute instructions, so I
-        // implement that delay by simply counting down the cycles required by
-        // the instruction. When it reaches 0, the instruction is complete, and
-        // the next one is ready to be executed.
-        if self.cycles == 0 {
+        // Depois de um JAM a CPU não acessa mais o barramento e ignora
+        // interrupções, o resto do sistema (PPU) continua rodando
+        if self.halted {
+            self.clock_count += 1;
+            return;
+        }
+
+        // Cada chamada executa um único ciclo da instrução, com um acesso ao
+        // barramento por ciclo (ver micro_step.rs). "step" guarda qual ciclo
+        // da instrução atual será executado, 0 é a leitura do próximo opcode
+        if self.step == 0 {
             // Ler o próximo byte de instrução, o valor desse Byte é para achar
             // qual é a operação e addresmode na tabela de tradução
-            // println!("pc: {:#06x}", self.pc);
-
-            self.opcode = self.read(self.pc);
-
-            // println!("opcode: {:#06x}", self.opcode);
-            // Sempre setar a flag unused para 1 (true)
-            self.set_flag(Flags6502::U, true);
-
-            // Incrimentar o program counter
-            self.pc_next();
-
-            let instruction = self.get_instruction();
-
-            // numero inicial de ciclos
-            self.cycles = instruction.cycles;
-
-            // aplicar o addres mode e guardar os ciclos adicionais
-            let aditional_cycles1 = self.addres_mode(instruction.addres_mode);
-
-            // executar o opcode e guardar os ciclos adicionais
-            let aditional_cycles2 = self.opcode(instruction.opcode);
-
-            // println!(
-            //     "instruction({:?},{:?}) - finished",
-            //     instruction.opcode, instruction.addres_mode
-            // );
-
-            // adicionar ciclos
-            self.cycles += aditional_cycles1 & aditional_cycles2;
+            self.fetch_opcode();
 
             // Sempre setar a flag unused para 1 (true)
             self.set_flag(Flags6502::U, true);
+            self.step = 1;
+        } else {
+            // As linhas de interrupção são verificadas antes do último ciclo da
+            // instrução, por isso uma instrução que altera a flag I só tem efeito
+            // na interrupção depois da próxima instrução (ex: CLI, SEI, PLP)
+            let poll = self.nmi_pending
+                || (self.irq_pending && self.get_flag(Flags6502::I) == 0);
+
+            if self.micro_step() {
+                self.step = 0;
+                self.interrupt_poll = poll;
+
+                // Sempre setar a flag unused para 1 (true)
+                self.set_flag(Flags6502::U, true);
+            } else {
+                self.step += 1;
+            }
         }
 
         self.clock_count += 1;
-
-        // decrementando o numero de ciclos
-        self.cycles -= 1;
     }
 
     pub fn clock(&mut self) {
@@ -193,7 +133,7 @@ impl Cpu6502 {
                         // On odd clock cycles, write to PPU OAM
                         self.bus.ppu.oam_write(self.bus.dma_addr, self.bus.dma_data);
                         // Increment the lo byte of the address
-                        self.bus.dma_addr += 1;
+                        self.bus.dma_addr = self.bus.dma_addr.wrapping_add(1);
                         // If this wraps around, we know that 256
                         // bytes have been written, so end the DMA
                         // transfer, and proceed as normal
@@ -28,7 +28,29 @@ fn to_hex(value: u32, hex_size: u8) -> String {
 
 impl Cpu6502 {
     pub fn complete(&self) -> bool {
-        self.cycles == 0
+        self.step == 0
+    }
+
+    /** Estado dos registradores em uma linha, usado quando a CPU trava em um JAM */
+    pub fn registers_dump(&self) -> String {
+        format!(
+            "PC:${} A:${} X:${} Y:${} P:${} SP:${} CYC:{}",
+            to_hex(self.pc as u32, 4),
+            to_hex(self.a as u32, 2),
+            to_hex(self.x as u32, 2),
+            to_hex(self.y as u32, 2),
+            to_hex(self.status as u32, 2),
+            to_hex(self.stkp as u32, 2),
+            self.clock_count
+        )
+    }
+
+    // Endereço de um operando, com o nome dele quando existe nos símbolos carregados
+    fn operand(&mut self, addres: u16, hex_size: u8) -> String {
+        match self.symbol(addres) {
+            Some(name) => name,
+            None => format!("${}", to_hex(addres as u32, hex_size)),
+        }
     }
 
     pub fn disassemble_instruction(&mut self) -> String {
@@ -38,7 +60,10 @@ impl Cpu6502 {
         let mut hi: u8 = 0;
 
         let mut instruction_line = format!("${}: ", to_hex(addr, 4));
-        let opcode = self.read(addr as u16);
+        if let Some(label) = self.symbol(addr as u16) {
+            instruction_line += &format!("{}: ", label);
+        }
+        let opcode = self.bus_read(addr as u16, true);
         addr += 1;
         let instruction = Instruction::from(opcode);
         instruction_line += &format!("{} ", instruction.name);
@@ -53,59 +78,68 @@ impl Cpu6502 {
             }
             AddressMode::ZP0 => {
                 lo = self.bus_read(addr as u16, true);
-                instruction_line += &format!("${} {{ZP0}}", to_hex(lo as u32, 2));
+                instruction_line += &format!("{} {{ZP0}}", self.operand(lo as u16, 2));
             }
             AddressMode::ZPX => {
                 lo = self.bus_read(addr as u16, true);
-                instruction_line += &format!("${}, X {{ZPX}}", to_hex(lo as u32, 2));
+                instruction_line += &format!("{}, X {{ZPX}}", self.operand(lo as u16, 2));
             }
             AddressMode::ZPY => {
                 lo = self.bus_read(addr as u16, true);
-                instruction_line += &format!("${}, Y {{ZPY}}", to_hex(lo as u32, 2));
+                instruction_line += &format!("{}, Y {{ZPY}}", self.operand(lo as u16, 2));
             }
             AddressMode::IZX => {
                 lo = self.bus_read(addr as u16, true);
-                instruction_line += &format!("${}, X {{IZX}}", to_hex(lo as u32, 2));
+                instruction_line += &format!("{}, X {{IZX}}", self.operand(lo as u16, 2));
             }
             AddressMode::IZY => {
                 lo = self.bus_read(addr as u16, true);
-                instruction_line += &format!("${}, Y {{IZY}}", to_hex(lo as u32, 2));
+                instruction_line += &format!("{}, Y {{IZY}}", self.operand(lo as u16, 2));
             }
             AddressMode::ABS => {
                 lo = self.bus_read(addr as u16, true);
                 addr += 1;
                 hi = self.bus_read(addr as u16, true);
-                instruction_line +=
-                    &format!("${} {{ABS}}", to_hex(((hi as u32) << 8) | lo as u32, 4));
+                instruction_line += &format!(
+                    "{} {{ABS}}",
+                    self.operand(((hi as u16) << 8) | lo as u16, 4)
+                );
             }
             AddressMode::ABX => {
                 lo = self.bus_read(addr as u16, true);
                 addr += 1;
                 hi = self.bus_read(addr as u16, true);
-                instruction_line +=
-                    &format!("${}, X {{ABX}}", to_hex(((hi as u32) << 8) | lo as u32, 2));
+                instruction_line += &format!(
+                    "{}, X {{ABX}}",
+                    self.operand(((hi as u16) << 8) | lo as u16, 4)
+                );
             }
             AddressMode::ABY => {
                 lo = self.bus_read(addr as u16, true);
                 addr += 1;
                 hi = self.bus_read(addr as u16, true);
-                instruction_line +=
-                    &format!("${}, Y {{ABY}}", to_hex(((hi as u32) << 8) | lo as u32, 2));
+                instruction_line += &format!(
+                    "{}, Y {{ABY}}",
+                    self.operand(((hi as u16) << 8) | lo as u16, 4)
+                );
             }
             AddressMode::IND => {
                 lo = self.bus_read(addr as u16, true);
                 addr += 1;
                 hi = self.bus_read(addr as u16, true);
-                instruction_line +=
-                    &format!("(${}) {{IND}}", to_hex(((hi as u32) << 8) | lo as u32, 2));
+                instruction_line += &format!(
+                    "({}) {{IND}}",
+                    self.operand(((hi as u16) << 8) | lo as u16, 4)
+                );
             }
             AddressMode::REL => {
                 value = self.bus_read(addr as u16, true);
                 addr += 1;
+                // O deslocamento tem sinal, os branches também voltam
                 instruction_line += &format!(
-                    "${} [${}] {{REL}}",
+                    "${} [{}] {{REL}}",
                     to_hex(value as u32, 2),
-                    to_hex(addr + value as u32, 4)
+                    self.operand((addr as u16).wrapping_add(value as i8 as u16), 4)
                 );
             }
             _ => {}
@@ -132,8 +166,11 @@ impl Cpu6502 {
             line_addr = addr as u16;
 
             let mut s_inst = format!("${}: ", to_hex(addr, 4));
+            if let Some(label) = self.symbol(addr as u16) {
+                s_inst += &format!("{}: ", label);
+            }
 
-            let opcode = self.read(addr as u16);
+            let opcode = self.bus_read(addr as u16, true);
             addr += 1;
             let instruction = Instruction::from(opcode);
             s_inst += &format!("{} ", instruction.name);
@@ -151,67 +188,79 @@ impl Cpu6502 {
                     lo = self.bus_read(addr as u16, true);
                     addr += 1;
                     hi = 0x00;
-                    s_inst += &format!("${} {{ZP0}}", to_hex(lo as u32, 2));
+                    s_inst += &format!("{} {{ZP0}}", self.operand(lo as u16, 2));
                 }
                 AddressMode::ZPX => {
                     lo = self.bus_read(addr as u16, true);
                     addr += 1;
                     hi = 0x00;
-                    s_inst += &format!("${}, X {{ZPX}}", to_hex(lo as u32, 2));
+                    s_inst += &format!("{}, X {{ZPX}}", self.operand(lo as u16, 2));
                 }
                 AddressMode::ZPY => {
                     lo = self.bus_read(addr as u16, true);
                     addr += 1;
                     hi = 0x00;
-                    s_inst += &format!("${}, Y {{ZPY}}", to_hex(lo as u32, 2));
+                    s_inst += &format!("{}, Y {{ZPY}}", self.operand(lo as u16, 2));
                 }
                 AddressMode::IZX => {
                     lo = self.bus_read(addr as u16, true);
                     addr += 1;
                     hi = 0x00;
-                    s_inst += &format!("${}, X {{IZX}}", to_hex(lo as u32, 2));
+                    s_inst += &format!("{}, X {{IZX}}", self.operand(lo as u16, 2));
                 }
                 AddressMode::IZY => {
                     lo = self.bus_read(addr as u16, true);
                     addr += 1;
                     hi = 0x00;
-                    s_inst += &format!("${}, Y {{IZY}}", to_hex(lo as u32, 2));
+                    s_inst += &format!("{}, Y {{IZY}}", self.operand(lo as u16, 2));
                 }
                 AddressMode::ABS => {
                     lo = self.bus_read(addr as u16, true);
                     addr += 1;
                     hi = self.bus_read(addr as u16, true);
                     addr += 1;
-                    s_inst += &format!("${} {{ABS}}", to_hex(((hi as u32) << 8) | lo as u32, 2));
+                    s_inst += &format!(
+                        "{} {{ABS}}",
+                        self.operand(((hi as u16) << 8) | lo as u16, 4)
+                    );
                 }
                 AddressMode::ABX => {
                     lo = self.bus_read(addr as u16, true);
                     addr += 1;
                     hi = self.bus_read(addr as u16, true);
                     addr += 1;
-                    s_inst += &format!("${}, X {{ABX}}", to_hex(((hi as u32) << 8) | lo as u32, 2));
+                    s_inst += &format!(
+                        "{}, X {{ABX}}",
+                        self.operand(((hi as u16) << 8) | lo as u16, 4)
+                    );
                 }
                 AddressMode::ABY => {
                     lo = self.bus_read(addr as u16, true);
                     addr += 1;
                     hi = self.bus_read(addr as u16, true);
                     addr += 1;
-                    s_inst += &format!("${}, Y {{ABY}}", to_hex(((hi as u32) << 8) | lo as u32, 2));
+                    s_inst += &format!(
+                        "{}, Y {{ABY}}",
+                        self.operand(((hi as u16) << 8) | lo as u16, 4)
+                    );
                 }
                 AddressMode::IND => {
                     lo = self.bus_read(addr as u16, true);
                     addr += 1;
                     hi = self.bus_read(addr as u16, true);
                     addr += 1;
-                    s_inst += &format!("(${}) {{IND}}", to_hex(((hi as u32) << 8) | lo as u32, 2));
+                    s_inst += &format!(
+                        "({}) {{IND}}",
+                        self.operand(((hi as u16) << 8) | lo as u16, 4)
+                    );
                 }
                 AddressMode::REL => {
                     value = self.bus_read(addr as u16, true);
                     addr += 1;
                     s_inst += &format!(
-                        "${} [${}] {{REL}}",
+                        "${} [{}] {{REL}}",
                         to_hex(value as u32, 2),
-                        to_hex(addr + value as u32, 4)
+                        self.operand((addr as u16).wrapping_add(value as i8 as u16), 4)
                     );
                 }
                 _ => {}
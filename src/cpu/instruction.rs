@@ -9,7 +9,6 @@ pub struct Instruct(&'static str, Opcode, AddressMode, u8);
 pub struct Instruction {
     pub opcode: Opcode,
     pub addres_mode: AddressMode,
-    pub cycles: u8,
     pub name: &'static str,
 }
 
@@ -18,7 +17,6 @@ impl Instruction {
         Instruction {
             name,
             addres_mode,
-            cycles: 4,
             opcode,
         }
     }
@@ -311,6 +309,8 @@ impl Instruction {
             0xd4 => Instruction::new("*IGN", Opcode::IGN, AddressMode::ZPX),
             0xf4 => Instruction::new("*IGN", Opcode::IGN, AddressMode::ZPX),
 
+            0x2b => Instruction::new("*ANC", Opcode::ANC, AddressMode::IMM),
+
             /* *************** unofficial2 ***************  */
             0xeb => Instruction::new("SBC", Opcode::SBC, AddressMode::IMM),
 
@@ -321,6 +321,21 @@ impl Instruction {
             0xda => Instruction::new("NOP", Opcode::NOP, AddressMode::IMP),
             0xfa => Instruction::new("NOP", Opcode::NOP, AddressMode::IMP),
 
+            /* *************** unstable ***************  */
+            0x93 => Instruction::new("*SHA", Opcode::SHA, AddressMode::IZY),
+            0x9f => Instruction::new("*SHA", Opcode::SHA, AddressMode::ABY),
+            0x9e => Instruction::new("*SHX", Opcode::SHX, AddressMode::ABY),
+            0x9c => Instruction::new("*SHY", Opcode::SHY, AddressMode::ABX),
+            0x9b => Instruction::new("*TAS", Opcode::TAS, AddressMode::ABY),
+            0xbb => Instruction::new("*LAS", Opcode::LAS, AddressMode::ABY),
+            0x8b => Instruction::new("*XAA", Opcode::XAA, AddressMode::IMM),
+            0xab => Instruction::new("*LXA", Opcode::LXA, AddressMode::IMM),
+
+            /* *************** jam ***************  */
+            0x02 | 0x12 | 0x22 | 0x32 | 0x42 | 0x52 | 0x62 | 0x72 | 0x92 | 0xb2 | 0xd2 | 0xf2 => {
+                Instruction::new("*JAM", Opcode::JAM, AddressMode::IMP)
+            }
+
             _ => Instruction::new("XXX", Opcode::XXX, AddressMode::IMP),
         }
     }
@@ -337,16 +352,14 @@ impl Cpu6502 {
         fetch data as the source is implied by the instruction. For example
         "INX" increments the X register. There is no additional data
         required. For all other addressing modes, the data resides at
-        the location held within addr_abs, so it is read from there.
-        Immediate adress mode exploits this slightly, as that has
-        set addr_abs = pc + 1, so it fetches the data from the
-        next byte for example "LDA $FF" just loads the accumulator with
-        256, i.e. no far reaching memory fetch is required. "fetched"
-        is a variable global to the CPU, and is set by cal
+        the location held within addr_abs and it has already been read
+        into "fetched" on its own bus cycle (see micro_step.rs), so no
+        memory access happens here. For the implied mode the accumulator
+        is the operand, for example "ASL A".
     */
     pub fn fetch(&mut self) -> u8 {
-        if AddressMode::IMP != self.get_instruction().addres_mode {
-            self.fetched = self.read(self.addr_abs);
+        if AddressMode::IMP == self.get_instruction().addres_mode {
+            self.fetched = self.a;
         }
 
         self.fetched
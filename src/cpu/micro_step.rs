@@ -0,0 +1,604 @@
+use super::addres_mode::AddressMode;
+use super::opcode::Opcode;
+use super::{Cpu6502, Flags6502};
+
+// Execução ciclo a ciclo ======================================
+// Cada chamada de cpu_clock() executa exatamente um ciclo da CPU
+// e cada ciclo faz exatamente um acesso ao barramento (leitura ou
+// escrita), inclusive as leituras e escritas "dummy" que o 6502
+// faz enquanto calcula endereços. Assim um registrador da PPU lido
+// no meio da instrução é lido no ciclo correto.
+//
+// O ciclo 0 de toda instrução é a leitura do opcode. Os ciclos
+// seguintes dependem do tipo da instrução:
+//
+// Read:              endereço -> leitura do operando -> operação
+// Write:             endereço -> escrita
+// Read-Modify-Write: endereço -> leitura -> escrita do valor antigo -> escrita do resultado
+// Implied:           leitura dummy do próximo byte -> operação
+//
+// Referência: https://www.nesdev.org/6502_cpu.txt
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum OperationKind {
+    Read,
+    Write,
+    ReadModifyWrite,
+    Implied,
+    Branch,
+    Jump,
+    Push,
+    Pull,
+    Break,
+    JumpSubRoutine,
+    ReturnFromInterrupt,
+    ReturnFromSubRoutine,
+}
+
+impl OperationKind {
+    pub fn from(opcode: Opcode, addres_mode: AddressMode) -> OperationKind {
+        match opcode {
+            Opcode::BCC
+            | Opcode::BCS
+            | Opcode::BEQ
+            | Opcode::BMI
+            | Opcode::BNE
+            | Opcode::BPL
+            | Opcode::BVC
+            | Opcode::BVS => OperationKind::Branch,
+            Opcode::JMP => OperationKind::Jump,
+            Opcode::PHA | Opcode::PHP => OperationKind::Push,
+            Opcode::PLA | Opcode::PLP => OperationKind::Pull,
+            Opcode::BRK => OperationKind::Break,
+            Opcode::JSR => OperationKind::JumpSubRoutine,
+            Opcode::RTI => OperationKind::ReturnFromInterrupt,
+            Opcode::RTS => OperationKind::ReturnFromSubRoutine,
+            _ if addres_mode == AddressMode::IMP => OperationKind::Implied,
+            Opcode::STA
+            | Opcode::STX
+            | Opcode::STY
+            | Opcode::SAX
+            | Opcode::SHA
+            | Opcode::SHX
+            | Opcode::SHY
+            | Opcode::TAS => OperationKind::Write,
+            Opcode::ASL
+            | Opcode::LSR
+            | Opcode::ROL
+            | Opcode::ROR
+            | Opcode::INC
+            | Opcode::DEC
+            | Opcode::SLO
+            | Opcode::SRE
+            | Opcode::RLA
+            | Opcode::RRA
+            | Opcode::ISC
+            | Opcode::DCP => OperationKind::ReadModifyWrite,
+            _ => OperationKind::Read,
+        }
+    }
+}
+
+impl Cpu6502 {
+    /** Ciclo 0: lê o opcode ou, se uma interrupção foi detectada no fim da instrução
+    anterior, faz uma leitura dummy e começa a sequência de interrupção (um BRK "forçado") */
+    pub fn fetch_opcode(&mut self) {
+        if self.interrupt_poll {
+            self.read(self.pc);
+            self.opcode = 0x00;
+            self.hardware_interrupt = true;
+        } else {
+            self.opcode = self.read(self.pc);
+            self.log_code(self.pc);
+            self.pc_next();
+            self.hardware_interrupt = false;
+        }
+
+        self.address_ready = false;
+        self.page_crossed = false;
+        self.fix_up = false;
+        self.operand_step = 0;
+        self.branch_taken = false;
+    }
+
+    /** Volta para o começo de uma instrução, sem interrupção pendente e sem o travamento do JAM */
+    pub fn clear_micro_state(&mut self) {
+        self.step = 0;
+        self.address_ready = false;
+        self.page_crossed = false;
+        self.fix_up = false;
+        self.operand_step = 0;
+        self.branch_taken = false;
+        self.hardware_interrupt = false;
+        self.nmi_pending = false;
+        self.irq_pending = false;
+        self.interrupt_poll = false;
+        self.halted = false;
+    }
+
+    /** Executa o ciclo "step" da instrução atual, retorna true quando a instrução terminou */
+    pub fn micro_step(&mut self) -> bool {
+        let instruction = self.get_instruction();
+        let step = self.step;
+
+        let kind = OperationKind::from(instruction.opcode, instruction.addres_mode);
+        match kind {
+            OperationKind::Implied => {
+                self.read(self.pc);
+                self.opcode(instruction.opcode);
+                true
+            }
+            OperationKind::Read | OperationKind::Write | OperationKind::ReadModifyWrite => {
+                if !self.address_ready {
+                    self.address_ready = self.addres_mode(instruction.addres_mode, step);
+
+                    // Só o modo Immediate lê o operando no mesmo ciclo em que calcula o endereço
+                    if !self.address_ready || instruction.addres_mode != AddressMode::IMM {
+                        return false;
+                    }
+                }
+
+                let done = self.operand_step(instruction.opcode);
+                if done
+                    && kind != OperationKind::Write
+                    && instruction.addres_mode != AddressMode::IMM
+                {
+                    self.log_data(self.addr_abs, instruction.addres_mode);
+                }
+                done
+            }
+            OperationKind::Jump => {
+                if self.addres_mode(instruction.addres_mode, step) {
+                    if instruction.addres_mode == AddressMode::IND {
+                        self.log_indirect_jump();
+                    }
+                    self.opcode(instruction.opcode);
+                    return true;
+                }
+                false
+            }
+            OperationKind::Branch => self.branch_step(instruction.opcode, step),
+            OperationKind::Push => {
+                if step == 1 {
+                    self.read(self.pc);
+                    return false;
+                }
+                self.opcode(instruction.opcode);
+                true
+            }
+            OperationKind::Pull => match step {
+                1 => {
+                    self.read(self.pc);
+                    false
+                }
+                2 => {
+                    self.stkp_peek();
+                    false
+                }
+                _ => {
+                    self.opcode(instruction.opcode);
+                    true
+                }
+            },
+            OperationKind::Break => self.brk_step(step),
+            OperationKind::JumpSubRoutine => self.jsr_step(step),
+            OperationKind::ReturnFromInterrupt => self.rti_step(step),
+            OperationKind::ReturnFromSubRoutine => self.rts_step(step),
+        }
+    }
+
+    // Acesso ao operando depois que o endereço efetivo está pronto
+    fn operand_step(&mut self, opcode: Opcode) -> bool {
+        let kind = OperationKind::from(opcode, AddressMode::ABS);
+
+        // Endereço indexado: leitura dummy no endereço com o byte alto ainda errado. Nas
+        // instruções de leitura, se não mudou de página, essa já é a leitura correta
+        if self.fix_up {
+            let data = self.read(self.addr_abs);
+            if self.page_crossed {
+                self.addr_abs = self.addr_abs.wrapping_add(0x0100);
+            }
+            self.fix_up = false;
+
+            if kind == OperationKind::Read {
+                if !self.page_crossed {
+                    self.fetched = data;
+                    self.opcode(opcode);
+                    return true;
+                }
+                self.page_crossed = false;
+            }
+            return false;
+        }
+
+        match kind {
+            OperationKind::Read => {
+                self.fetched = self.read(self.addr_abs);
+                self.opcode(opcode);
+                true
+            }
+            OperationKind::Write => {
+                self.opcode(opcode);
+                true
+            }
+            _ => {
+                self.operand_step += 1;
+                match self.operand_step {
+                    1 => {
+                        self.fetched = self.read(self.addr_abs);
+                        false
+                    }
+                    2 => {
+                        // O 6502 escreve o valor original de volta enquanto calcula o resultado
+                        self.write(self.addr_abs, self.fetched);
+                        false
+                    }
+                    _ => {
+                        self.opcode(opcode);
+                        true
+                    }
+                }
+            }
+        }
+    }
+
+    // Instruction: Branch
+    // Ciclo 1: lê o offset e avalia a condição
+    // Ciclo 2: (desvio) leitura dummy, soma o offset no byte baixo do PC
+    // Ciclo 3: (mudou de página) leitura dummy, corrige o byte alto do PC
+    fn branch_step(&mut self, opcode: Opcode, step: u8) -> bool {
+        match step {
+            1 => {
+                self.addres_mode(AddressMode::REL, step);
+                self.opcode(opcode);
+                !self.branch_taken
+            }
+            2 => {
+                self.read(self.pc);
+                if (self.addr_abs & 0xFF00) == (self.pc & 0xFF00) {
+                    self.pc = self.addr_abs;
+                    return true;
+                }
+                self.pc = (self.pc & 0xFF00) | (self.addr_abs & 0x00FF);
+                false
+            }
+            _ => {
+                self.read(self.pc);
+                self.pc = self.addr_abs;
+                true
+            }
+        }
+    }
+
+    // Instruction: Break / IRQ / NMI
+    // Function:    Program Sourced Interrupt
+    // As interrupções de hardware usam a mesma sequência, porém o PC não é
+    // incrementado e a flag B é guardada como 0. Se um NMI acontecer durante
+    // a sequência ele "sequestra" o vetor, mesmo em um BRK ou IRQ.
+    fn brk_step(&mut self, step: u8) -> bool {
+        match step {
+            1 => {
+                self.read(self.pc);
+                if !self.hardware_interrupt {
+                    self.pc_next();
+                }
+                false
+            }
+            2 => {
+                self.stkp_push(((self.pc >> 8) & 0x00FF) as u8);
+                false
+            }
+            3 => {
+                self.stkp_push((self.pc & 0x00FF) as u8);
+                false
+            }
+            4 => {
+                let status = if self.hardware_interrupt {
+                    (self.status & !(Flags6502::B as u8)) | Flags6502::U as u8
+                } else {
+                    self.status | Flags6502::B as u8 | Flags6502::U as u8
+                };
+                self.stkp_push(status);
+
+                if self.nmi_pending {
+                    self.nmi_pending = false;
+                    self.temp = 0xFFFA;
+                } else {
+                    if self.hardware_interrupt {
+                        self.irq_pending = false;
+                    }
+                    self.temp = 0xFFFE;
+                }
+                self.set_flag(Flags6502::I, true);
+                false
+            }
+            5 => {
+                self.addr_abs = self.read(self.temp) as u16;
+                false
+            }
+            _ => {
+                self.addr_abs |= (self.read(self.temp + 1) as u16) << 8;
+                self.pc = self.addr_abs;
+                true
+            }
+        }
+    }
+
+    // Instruction: Jump To Sub-Routine
+    // Function:    Push current pc to stack, pc = address
+    // O endereço guardado é o do último byte da instrução (o RTS soma 1)
+    fn jsr_step(&mut self, step: u8) -> bool {
+        match step {
+            1 => {
+                self.addr_abs = self.read(self.pc) as u16;
+                self.pc_next();
+                false
+            }
+            2 => {
+                self.stkp_peek();
+                false
+            }
+            3 => {
+                self.stkp_push(((self.pc >> 8) & 0x00FF) as u8);
+                false
+            }
+            4 => {
+                self.stkp_push((self.pc & 0x00FF) as u8);
+                false
+            }
+            _ => {
+                self.addr_abs |= (self.read(self.pc) as u16) << 8;
+                self.pc = self.addr_abs;
+                true
+            }
+        }
+    }
+
+    // Instruction: Return from Interrupt
+    // Function:    Status <- stack, pc <- stack
+    fn rti_step(&mut self, step: u8) -> bool {
+        match step {
+            1 => {
+                self.read(self.pc);
+                false
+            }
+            2 => {
+                self.stkp_peek();
+                false
+            }
+            3 => {
+                self.status = self.stkp_pop();
+                self.set_flag(Flags6502::B, false);
+                self.set_flag(Flags6502::U, true);
+                false
+            }
+            4 => {
+                self.addr_abs = self.stkp_pop() as u16;
+                false
+            }
+            _ => {
+                self.addr_abs |= (self.stkp_pop() as u16) << 8;
+                self.pc = self.addr_abs;
+                true
+            }
+        }
+    }
+
+    // Instruction: Return from Sub-Routine
+    // Function:    pc <- stack + 1
+    fn rts_step(&mut self, step: u8) -> bool {
+        match step {
+            1 => {
+                self.read(self.pc);
+                false
+            }
+            2 => {
+                self.stkp_peek();
+                false
+            }
+            3 => {
+                self.addr_abs = self.stkp_pop() as u16;
+                false
+            }
+            4 => {
+                self.addr_abs |= (self.stkp_pop() as u16) << 8;
+                self.pc = self.addr_abs;
+                false
+            }
+            _ => {
+                self.read(self.pc);
+                self.pc_next();
+                true
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::Bus;
+    use crate::cartridge::Cartridge;
+    use crate::cpu::BusOperation;
+
+    fn r(addres: u16, data: u8) -> (u16, u8, BusOperation) {
+        (addres, data, BusOperation::Read)
+    }
+
+    fn w(addres: u16, data: u8) -> (u16, u8, BusOperation) {
+        (addres, data, BusOperation::Write)
+    }
+
+    // CPU com 64KB de memória plana, o programa é gravado em "pc" com a flag I
+    // ligada e o vetor de IRQ apontando para $0400
+    fn flat_cpu(pc: u16, program: &[u8]) -> Cpu6502 {
+        let mut cpu = Cpu6502::new_with_bus(Bus::new(Cartridge::flat(0x0000, &[])));
+        for (i, byte) in program.iter().enumerate() {
+            cpu.bus.write(pc + i as u16, *byte);
+        }
+        cpu.bus.write(0xFFFE, 0x00);
+        cpu.bus.write(0xFFFF, 0x04);
+        cpu.pc = pc;
+        cpu.stkp = 0xFD;
+        cpu.status = 0x24;
+        cpu.clear_micro_state();
+        cpu
+    }
+
+    // Executa "cycles" ciclos e retorna cada acesso feito ao barramento
+    fn bus_log(cpu: &mut Cpu6502, cycles: usize) -> Vec<(u16, u8, BusOperation)> {
+        cpu.start_bus_log();
+        for _ in 0..cycles {
+            cpu.cpu_clock();
+        }
+        cpu.stop_bus_log()
+            .iter()
+            .map(|activity| (activity.addres, activity.data, activity.operation))
+            .collect()
+    }
+
+    #[test]
+    fn indexed_read_page_cross() {
+        // LDA $10FF,X com X = 1: leitura dummy em $1000 antes de corrigir o byte alto
+        let mut cpu = flat_cpu(0x0200, &[0xBD, 0xFF, 0x10]);
+        cpu.bus.write(0x1000, 0x11);
+        cpu.bus.write(0x1100, 0x22);
+        cpu.x = 0x01;
+        let log = bus_log(&mut cpu, 5);
+        assert_eq!(
+            log,
+            [
+                r(0x0200, 0xBD),
+                r(0x0201, 0xFF),
+                r(0x0202, 0x10),
+                r(0x1000, 0x11),
+                r(0x1100, 0x22)
+            ]
+        );
+        assert!(cpu.complete());
+        assert_eq!(cpu.a, 0x22);
+
+        // sem mudar de página a primeira leitura já é a certa e a instrução tem 4 ciclos
+        let mut cpu = flat_cpu(0x0200, &[0xBD, 0x00, 0x10]);
+        cpu.bus.write(0x1001, 0x33);
+        cpu.x = 0x01;
+        let log = bus_log(&mut cpu, 4);
+        assert_eq!(
+            log,
+            [
+                r(0x0200, 0xBD),
+                r(0x0201, 0x00),
+                r(0x0202, 0x10),
+                r(0x1001, 0x33)
+            ]
+        );
+        assert!(cpu.complete());
+    }
+
+    #[test]
+    fn read_modify_write_double_write() {
+        // INC $10: escreve o valor antigo e depois o resultado
+        let mut cpu = flat_cpu(0x0200, &[0xE6, 0x10]);
+        cpu.bus.write(0x0010, 0x05);
+        let log = bus_log(&mut cpu, 5);
+        assert_eq!(
+            log,
+            [
+                r(0x0200, 0xE6),
+                r(0x0201, 0x10),
+                r(0x0010, 0x05),
+                w(0x0010, 0x05),
+                w(0x0010, 0x06)
+            ]
+        );
+        assert!(cpu.complete());
+
+        // INC $1000,X sempre faz a leitura dummy, mesmo sem mudar de página
+        let mut cpu = flat_cpu(0x0200, &[0xFE, 0x00, 0x10]);
+        cpu.bus.write(0x1001, 0x7F);
+        cpu.x = 0x01;
+        let log = bus_log(&mut cpu, 7);
+        assert_eq!(
+            log,
+            [
+                r(0x0200, 0xFE),
+                r(0x0201, 0x00),
+                r(0x0202, 0x10),
+                r(0x1001, 0x7F),
+                r(0x1001, 0x7F),
+                w(0x1001, 0x7F),
+                w(0x1001, 0x80)
+            ]
+        );
+        assert!(cpu.complete());
+    }
+
+    #[test]
+    fn branch_taken_page_cross() {
+        // BNE +$10 em $02FD: lê o próximo opcode, depois $020F com o byte alto
+        // ainda errado e só então vai para $030F
+        let mut cpu = flat_cpu(0x02FD, &[0xD0, 0x10, 0xEA]);
+        cpu.bus.write(0x020F, 0x44);
+        let log = bus_log(&mut cpu, 4);
+        assert_eq!(
+            log,
+            [
+                r(0x02FD, 0xD0),
+                r(0x02FE, 0x10),
+                r(0x02FF, 0xEA),
+                r(0x020F, 0x44)
+            ]
+        );
+        assert!(cpu.complete());
+        assert_eq!(cpu.pc, 0x030F);
+    }
+
+    #[test]
+    fn irq_polled_before_last_cycle() {
+        // CLI; NOP com IRQ pendente: a linha é verificada antes do último ciclo do
+        // CLI, quando a flag I ainda está ligada, então o NOP roda antes da IRQ
+        let mut cpu = flat_cpu(0x0200, &[0x58, 0xEA, 0xEA]);
+        cpu.irq_pending = true;
+        let log = bus_log(&mut cpu, 11);
+        assert_eq!(
+            log,
+            [
+                r(0x0200, 0x58),
+                r(0x0201, 0xEA),
+                r(0x0201, 0xEA),
+                r(0x0202, 0xEA),
+                r(0x0202, 0xEA),
+                r(0x0202, 0xEA),
+                w(0x01FD, 0x02),
+                w(0x01FC, 0x02),
+                w(0x01FB, 0x20),
+                r(0xFFFE, 0x00),
+                r(0xFFFF, 0x04)
+            ]
+        );
+        assert!(cpu.complete());
+        assert_eq!(cpu.pc, 0x0400);
+
+        // SEI com IRQ pendente: a verificação acontece antes do SEI ligar a flag I,
+        // então a IRQ entra logo depois dele
+        let mut cpu = flat_cpu(0x0200, &[0x78, 0xEA]);
+        cpu.status = 0x20;
+        cpu.irq_pending = true;
+        let log = bus_log(&mut cpu, 9);
+        assert_eq!(
+            log,
+            [
+                r(0x0200, 0x78),
+                r(0x0201, 0xEA),
+                r(0x0201, 0xEA),
+                r(0x0201, 0xEA),
+                w(0x01FD, 0x02),
+                w(0x01FC, 0x01),
+                w(0x01FB, 0x24),
+                r(0xFFFE, 0x00),
+                r(0xFFFF, 0x04)
+            ]
+        );
+        assert_eq!(cpu.pc, 0x0400);
+    }
+}
@@ -3,6 +3,7 @@ mod cpu6502;
 mod external_inputs;
 mod helpers;
 mod instruction;
+mod micro_step;
 mod opcode;
 
 pub use addres_mode::AddressMode;
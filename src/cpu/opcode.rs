@@ -1,9 +1,14 @@
 use super::addres_mode::AddressMode;
 use super::{Cpu6502, Flags6502};
 
+// Constante das instruções instáveis XAA e LXA
+const UNSTABLE_MAGIC: u8 = 0xEE;
+
 // Opcodes ======================================================
-// There are 56 "legitimate" opcodes provided by the 6502 CPU. I
-// have not modelled "unofficial" opcodes. As each opcode is
+// There are 56 "legitimate" opcodes provided by the 6502 CPU. The
+// "unofficial" ones are modelled after the 2A03 behaviour documented
+// on the nesdev wiki, including the unstable ones (SHA, SHX, SHY, TAS,
+// XAA, LXA) and the JAM opcodes that lock the CPU. As each opcode is
 // defined by 1 byte, there are potentially 256 possible codes.
 // Codes are not used in a "switch case" style on a processor,
 // instead they are repsonisble for switching individual parts of
@@ -14,10 +19,12 @@ use super::{Cpu6502, Flags6502};
 // interesting ways, and can be exploited to gain additional
 // functionality!
 //
-// These functions return 0 normally, but some are capable of
-// requiring more clock cycles when executed under certain
-// conditions combined with certain addressing modes. If that is
-// the case, they return 1.
+// Each function performs the work of the last cycle of the
+// instruction: the operand has already been read into "fetched"
+// by the addressing mode (see micro_step.rs) and any write to
+// memory here is the single bus access of that cycle. The control
+// instructions (BRK, JSR, RTI and RTS) need several bus accesses
+// and are stepped cycle by cycle in micro_step.rs instead.
 //
 // I have included detailed explanations of each function in
 // the class implementation file. Note they are listed in
@@ -98,11 +105,22 @@ pub enum Opcode {
     SKB,
     IGN,
 
+    // unofficial2 (instáveis)
+    // https://www.nesdev.org/wiki/CPU_unofficial_opcodes
+    SHA,
+    SHX,
+    SHY,
+    TAS,
+    LAS,
+    XAA,
+    LXA,
+    JAM,
+
     XXX,
 }
 
 impl Cpu6502 {
-    pub fn opcode(&mut self, code: Opcode) -> u8 {
+    pub fn opcode(&mut self, code: Opcode) {
         match code {
             Opcode::ADC => self.adc(),
             Opcode::AND => self.and(),
@@ -114,7 +132,6 @@ impl Cpu6502 {
             Opcode::BMI => self.bmi(),
             Opcode::BNE => self.bne(),
             Opcode::BPL => self.bpl(),
-            Opcode::BRK => self.brk(),
             Opcode::BVC => self.bvc(),
             Opcode::BVS => self.bvs(),
             Opcode::CLC => self.clc(),
@@ -132,7 +149,6 @@ impl Cpu6502 {
             Opcode::INX => self.inx(),
             Opcode::INY => self.iny(),
             Opcode::JMP => self.jmp(),
-            Opcode::JSR => self.jsr(),
             Opcode::LDA => self.lda(),
             Opcode::LDX => self.ldx(),
             Opcode::LDY => self.ldy(),
@@ -145,8 +161,6 @@ impl Cpu6502 {
             Opcode::PLP => self.plp(),
             Opcode::ROL => self.rol(),
             Opcode::ROR => self.ror(),
-            Opcode::RTI => self.rti(),
-            Opcode::RTS => self.rts(),
             Opcode::SBC => self.sbc(),
             Opcode::SEC => self.sec(),
             Opcode::SED => self.sed(),
@@ -175,7 +189,19 @@ impl Cpu6502 {
             Opcode::SRE => self.sre(),
             Opcode::SKB => self.skb(),
             Opcode::IGN => self.ign(),
+
+            Opcode::SHA => self.sha(),
+            Opcode::SHX => self.shx(),
+            Opcode::SHY => self.shy(),
+            Opcode::TAS => self.tas(),
+            Opcode::LAS => self.las(),
+            Opcode::XAA => self.xaa(),
+            Opcode::LXA => self.lxa(),
+            Opcode::JAM => self.jam(),
             Opcode::XXX => self.xxx(),
+
+            // executadas ciclo a ciclo em micro_step.rs
+            Opcode::BRK | Opcode::JSR | Opcode::RTI | Opcode::RTS => {}
         }
     }
 
@@ -202,7 +228,7 @@ impl Cpu6502 {
     // 1  0  1 | 0 |  0  |  1  |   0   |
     // 1  1  0 | 1 |  1  |  0  |   1   |
     // 1  1  1 | 0 |  0  |  0  |   1   |
-    fn adc(&mut self) -> u8 {
+    fn adc(&mut self) {
         // guardando valor que será adicionado no acumulador
         self.fetch();
 
@@ -224,8 +250,6 @@ impl Cpu6502 {
 
         // Salvar o resultado no acumulador
         self.a = (self.temp & 0x00FF) as u8;
-
-        1
     }
 
     // Instruction: Subtraction with Borrow In
@@ -236,7 +260,7 @@ impl Cpu6502 {
     // Given the explanation for ADC above, we can reorganise our data
     // to use the same computation for addition, for subtraction by multiplying
     // the data by -1, i.e. make it negative
-    fn sbc(&mut self) -> u8 {
+    fn sbc(&mut self) {
         // guardando valor que será adicionado no acumulador
         self.fetch();
 
@@ -261,27 +285,23 @@ impl Cpu6502 {
 
         // Salvar o resultado no acumulador
         self.a = (self.temp & 0x00FF) as u8;
-
-        1
     }
 
     // Instruction: Bitwise Logic AND
     // Function:    A = A & M
     // Flags Out:   N, Z
-    fn and(&mut self) -> u8 {
+    fn and(&mut self) {
         self.fetch();
 
         self.a = self.a & self.fetched;
         self.set_flag(Flags6502::Z, self.a == 0);
         self.set_flag(Flags6502::N, (self.a & 0x80) > 0);
-
-        1
     }
 
     // Instruction: Arithmetic Shift Left
     // Function:    A = C <- (A << 1) <- 0
     // Flags Out:   N, Z, C
-    fn asl(&mut self) -> u8 {
+    fn asl(&mut self) {
         self.fetch();
 
         self.temp = (self.fetched as u16) << 1;
@@ -295,339 +315,257 @@ impl Cpu6502 {
         } else {
             self.write(self.addr_abs, (self.temp & 0x00FF) as u8);
         }
-
-        1
     }
 
     // Instruction: Branch if Carry Clear
     // Function:    if(C == 0) pc = address
-    fn bcc(&mut self) -> u8 {
+    fn bcc(&mut self) {
         if self.get_flag(Flags6502::C) == 0 {
             self.pc_branch();
         }
-
-        0
     }
 
     // Instruction: Branch if Carry Set
     // Function:    if(C == 1) pc = address
-    fn bcs(&mut self) -> u8 {
+    fn bcs(&mut self) {
         if self.get_flag(Flags6502::C) == 1 {
             self.pc_branch();
         }
-
-        0
     }
 
     // Instruction: Branch if Equal
     // Function:    if(Z == 1) pc = address
-    fn beq(&mut self) -> u8 {
+    fn beq(&mut self) {
         if self.get_flag(Flags6502::Z) == 1 {
             self.pc_branch();
         }
-
-        0
     }
 
-    fn bit(&mut self) -> u8 {
+    fn bit(&mut self) {
         self.fetch();
         self.temp = (self.a & self.fetched) as u16;
         self.set_flag(Flags6502::Z, (self.temp & 0x00FF) == 0);
         self.set_flag(Flags6502::N, (self.fetched & (1 << 7)) > 0);
         self.set_flag(Flags6502::V, (self.fetched & (1 << 6)) > 0);
-
-        0
     }
 
     // Instruction: Branch if Negative
     // Function:    if(N == 1) pc = address
-    fn bmi(&mut self) -> u8 {
+    fn bmi(&mut self) {
         if self.get_flag(Flags6502::N) == 1 {
             self.pc_branch();
         }
-
-        0
     }
 
     // Instruction: Branch if Not Equal
     // Function:    if(Z == 0) pc = address
-    fn bne(&mut self) -> u8 {
+    fn bne(&mut self) {
         if self.get_flag(Flags6502::Z) == 0 {
             self.pc_branch();
         }
-
-        0
     }
 
     // Instruction: Branch if Positive
     // Function:    if(N == 0) pc = address
-    fn bpl(&mut self) -> u8 {
+    fn bpl(&mut self) {
         if self.get_flag(Flags6502::N) == 0 {
             self.pc_branch();
         }
-
-        0
-    }
-
-    // Instruction: Break
-    // Function: Program Sourced Interrupt
-    fn brk(&mut self) -> u8 {
-        self.pc_next();
-
-        self.set_flag(Flags6502::I, true);
-        self.stkp_push(((self.pc >> 8) & 0x00FF) as u8);
-        self.stkp_push((self.pc & 0x00FF) as u8);
-
-        self.set_flag(Flags6502::B, true);
-        self.stkp_push(self.status);
-        self.set_flag(Flags6502::B, false);
-
-        self.pc = self.read_16b(0xFFFE);
-
-        0
     }
 
     // Instruction: Branch if Overflow Clear
     // Function:    if(V == 0) pc = address
-    fn bvc(&mut self) -> u8 {
+    fn bvc(&mut self) {
         if self.get_flag(Flags6502::V) == 0 {
             self.pc_branch();
         }
-
-        0
     }
 
     // Instruction: Branch if Overflow Set
     // Function:    if(V == 1) pc = address
-    fn bvs(&mut self) -> u8 {
+    fn bvs(&mut self) {
         if self.get_flag(Flags6502::V) == 1 {
             self.pc_branch();
         }
-
-        0
     }
 
     // Instruction: Clear Carry Flag
     // Function:    C = 0
-    fn clc(&mut self) -> u8 {
+    fn clc(&mut self) {
         self.set_flag(Flags6502::C, false);
-        0
     }
 
     // Instruction: Clear Decimal Flag
     // Function:    D = 0
-    fn cld(&mut self) -> u8 {
+    fn cld(&mut self) {
         self.set_flag(Flags6502::D, false);
-        0
     }
 
     // Instruction: Disable Interrupts / Clear Interrupt Flag
     // Function:    I = 0
-    fn cli(&mut self) -> u8 {
+    fn cli(&mut self) {
         self.set_flag(Flags6502::I, false);
-        0
     }
 
     // Instruction: Clear Overflow Flag
     // Function:    V = 0
-    fn clv(&mut self) -> u8 {
+    fn clv(&mut self) {
         self.set_flag(Flags6502::V, false);
-        0
     }
 
     // Instruction: Compare Accumulator
     // Function:    C <- A >= M      Z <- (A - M) == 0
     // Flags Out:   N, C, Z
-    fn cmp(&mut self) -> u8 {
+    fn cmp(&mut self) {
         self.fetch();
 
-        self.temp = (self.a - self.fetched) as u16;
+        self.temp = self.a.wrapping_sub(self.fetched) as u16;
         self.set_flag(Flags6502::C, self.a >= self.fetched);
         self.set_flag(Flags6502::Z, (self.temp & 0x00FF) == 0);
         self.set_flag(Flags6502::N, (self.temp & 0x0080) > 0);
-
-        1
     }
 
     // Instruction: Compare X Register
     // Function:    C <- X >= M      Z <- (X - M) == 0
     // Flags Out:   N, C, Z
-    fn cpx(&mut self) -> u8 {
+    fn cpx(&mut self) {
         self.fetch();
 
-        self.temp = (self.x - self.fetched) as u16;
+        self.temp = self.x.wrapping_sub(self.fetched) as u16;
         self.set_flag(Flags6502::C, self.x >= self.fetched);
         self.set_flag(Flags6502::Z, (self.temp & 0x00FF) == 0);
         self.set_flag(Flags6502::N, (self.temp & 0x0080) > 0);
-
-        0
     }
 
     // Instruction: Compare Y Register
     // Function:    C <- Y >= M      Z <- (Y - M) == 0
     // Flags Out:   N, C, Z
-    fn cpy(&mut self) -> u8 {
+    fn cpy(&mut self) {
         self.fetch();
 
-        self.temp = (self.y - self.fetched) as u16;
+        self.temp = self.y.wrapping_sub(self.fetched) as u16;
         self.set_flag(Flags6502::C, self.y >= self.fetched);
         self.set_flag(Flags6502::Z, (self.temp & 0x00FF) == 0);
         self.set_flag(Flags6502::N, (self.temp & 0x0080) > 0);
-
-        0
     }
 
     // Instruction: Decrement Value at Memory Location
     // Function:    M = M - 1
     // Flags Out:   N, Z
-    fn dec(&mut self) -> u8 {
+    fn dec(&mut self) {
         self.fetch();
 
-        self.temp = (self.fetched - 1) as u16;
+        self.temp = self.fetched.wrapping_sub(1) as u16;
         self.write(self.addr_abs, (self.temp & 0x00FF) as u8);
         self.set_flag(Flags6502::Z, (self.temp & 0x00FF) == 0);
         self.set_flag(Flags6502::N, (self.temp & 0x0080) > 0);
-
-        0
     }
 
     // Instruction: Decrement X Register
     // Function:    X = X - 1
     // Flags Out:   N, Z
-    fn dex(&mut self) -> u8 {
-        self.x -= 1;
+    fn dex(&mut self) {
+        self.x = self.x.wrapping_sub(1);
 
         self.set_flag(Flags6502::Z, self.x == 0);
         self.set_flag(Flags6502::N, (self.x & 0x80) > 0);
-
-        0
     }
 
     // Instruction: Decrement Y Register
     // Function:    Y = Y - 1
     // Flags Out:   N, Z
-    fn dey(&mut self) -> u8 {
-        self.y -= 1;
+    fn dey(&mut self) {
+        self.y = self.y.wrapping_sub(1);
 
         self.set_flag(Flags6502::Z, self.y == 0);
         self.set_flag(Flags6502::N, (self.y & 0x80) > 0);
-
-        0
     }
 
     // Instruction: Bitwise Logic XOR
     // Function:    A = A xor M
     // Flags Out:   N, Z
-    fn eor(&mut self) -> u8 {
+    fn eor(&mut self) {
         self.fetch();
 
         self.a = self.a ^ self.fetched;
 
         self.set_flag(Flags6502::Z, self.a == 0);
         self.set_flag(Flags6502::N, (self.a & 0x80) > 0);
-
-        1
     }
 
     // Instruction: Increment Value at Memory Location
     // Function:    M = M + 1
     // Flags Out:   N, Z
-    fn inc(&mut self) -> u8 {
+    fn inc(&mut self) {
         self.fetch();
 
-        self.temp = (self.fetched + 1) as u16;
+        self.temp = self.fetched.wrapping_add(1) as u16;
         self.write(self.addr_abs, (self.temp & 0x00FF) as u8);
 
         self.set_flag(Flags6502::Z, (self.temp & 0x00FF) == 0);
         self.set_flag(Flags6502::N, (self.temp & 0x0080) > 0);
-
-        0
     }
 
     // Instruction: Increment X Register
     // Function:    X = X + 1
     // Flags Out:   N, Z
-    fn inx(&mut self) -> u8 {
-        self.x += 1;
+    fn inx(&mut self) {
+        self.x = self.x.wrapping_add(1);
 
         self.set_flag(Flags6502::Z, self.x == 0);
         self.set_flag(Flags6502::N, (self.x & 0x80) > 0);
-
-        0
     }
 
     // Instruction: Increment Y Register
     // Function:    Y = Y + 1
     // Flags Out:   N, Z
-    fn iny(&mut self) -> u8 {
-        self.y += 1;
+    fn iny(&mut self) {
+        self.y = self.y.wrapping_add(1);
 
         self.set_flag(Flags6502::Z, self.y == 0);
         self.set_flag(Flags6502::N, (self.y & 0x80) > 0);
-
-        0
     }
 
     // Instruction: Jump To Location
     // Function:    pc = address
-    fn jmp(&mut self) -> u8 {
+    fn jmp(&mut self) {
         self.pc = self.addr_abs;
-        0
-    }
-
-    // Instruction: Jump To Sub-Routine
-    // Function:    Push current pc to stack, pc = address
-    fn jsr(&mut self) -> u8 {
-        self.pc -= 1;
-
-        self.stkp_push(((self.pc >> 8) & 0x00FF) as u8);
-        self.stkp_push((self.pc & 0x00FF) as u8);
-
-        self.pc = self.addr_abs;
-
-        0
     }
 
     // Instruction: Load The Accumulator
     // Function:    A = M
     // Flags Out:   N, Z
-    fn lda(&mut self) -> u8 {
+    fn lda(&mut self) {
         self.fetch();
         self.a = self.fetched;
 
         self.set_flag(Flags6502::Z, self.a == 0);
         self.set_flag(Flags6502::N, (self.a & 0x80) > 0);
-
-        1
     }
 
     // Instruction: Load The X Register
     // Function:    X = M
     // Flags Out:   N, Z
-    fn ldx(&mut self) -> u8 {
+    fn ldx(&mut self) {
         self.fetch();
         self.x = self.fetched;
 
         self.set_flag(Flags6502::Z, self.x == 0);
         self.set_flag(Flags6502::N, (self.x & 0x80) > 0);
-
-        1
     }
 
     // Instruction: Load The Y Register
     // Function:    Y = M
     // Flags Out:   N, Z
-    fn ldy(&mut self) -> u8 {
+    fn ldy(&mut self) {
         self.fetch();
         self.y = self.fetched;
 
         self.set_flag(Flags6502::Z, self.y == 0);
         self.set_flag(Flags6502::N, (self.y & 0x80) > 0);
-
-        1
     }
 
-    fn lsr(&mut self) -> u8 {
+    fn lsr(&mut self) {
         self.fetch();
 
         self.set_flag(Flags6502::C, (self.fetched & 0x0001) > 0);
@@ -641,73 +579,57 @@ impl Cpu6502 {
         } else {
             self.write(self.addr_abs, (self.temp & 0x00FF) as u8);
         }
-
-        0
     }
 
-    fn nop(&mut self) -> u8 {
-        match self.opcode {
-            0x1C | 0x3C | 0x5C | 0x7C | 0xDC | 0xFC => 1,
-            _ => 0,
-        }
-    }
+    fn nop(&mut self) {}
 
     // Instruction: Bitwise Logic OR
     // Function:    A = A | M
     // Flags Out:   N, Z
-    fn ora(&mut self) -> u8 {
+    fn ora(&mut self) {
         self.fetch();
 
         self.a = self.a | self.fetched;
 
         self.set_flag(Flags6502::Z, self.a == 0);
         self.set_flag(Flags6502::N, (self.a & 0x80) > 0);
-
-        1
     }
 
     // Instruction: Push Accumulator to Stack
     // Function:    A -> stack
-    fn pha(&mut self) -> u8 {
-        self.write(0x0100 + self.stkp as u16, self.a);
+    fn pha(&mut self) {
         self.stkp_push(self.a);
-
-        0
     }
 
     // Instruction: Push Status Register to Stack
     // Function:    status -> stack
     // Note:        Break flag is set to 1 before push
-    fn php(&mut self) -> u8 {
+    fn php(&mut self) {
         self.stkp_push(self.status | Flags6502::B as u8 | Flags6502::U as u8);
         self.set_flag(Flags6502::B, false);
         self.set_flag(Flags6502::U, false);
-
-        0
     }
 
     // Instruction: Pop Accumulator off Stack
     // Function:    A <- stack
     // Flags Out:   N, Z
-    fn pla(&mut self) -> u8 {
+    fn pla(&mut self) {
         self.a = self.stkp_pop();
 
         self.set_flag(Flags6502::Z, self.a == 0);
         self.set_flag(Flags6502::N, (self.a & 0x80) > 0);
-
-        0
     }
 
     // Instruction: Pop Status Register off Stack
     // Function:    Status <- stack
-    fn plp(&mut self) -> u8 {
+    fn plp(&mut self) {
+        // a flag B não existe no registrador, ela só aparece no valor guardado na stack
         self.status = self.stkp_pop();
+        self.set_flag(Flags6502::B, false);
         self.set_flag(Flags6502::U, true);
-
-        0
     }
 
-    fn rol(&mut self) -> u8 {
+    fn rol(&mut self) {
         self.fetch();
 
         self.temp = (self.fetched as u16) << 1 | self.get_flag(Flags6502::C) as u16;
@@ -721,11 +643,9 @@ impl Cpu6502 {
         } else {
             self.write(self.addr_abs, (self.temp & 0x00FF) as u8);
         }
-
-        0
     }
 
-    fn ror(&mut self) -> u8 {
+    fn ror(&mut self) {
         self.fetch();
 
         self.temp = (self.fetched >> 1) as u16 | ((self.get_flag(Flags6502::C) as u16) << 7);
@@ -739,147 +659,102 @@ impl Cpu6502 {
         } else {
             self.write(self.addr_abs, (self.temp & 0x00FF) as u8);
         }
-
-        0
-    }
-
-    fn rti(&mut self) -> u8 {
-        self.status = self.stkp_pop();
-        self.status &= !(Flags6502::B as u8);
-        self.status &= !(Flags6502::U as u8);
-
-        self.pc = self.stkp_pop() as u16;
-        self.pc |= (self.stkp_pop() as u16) << 8;
-
-        0
-    }
-
-    fn rts(&mut self) -> u8 {
-        self.pc = self.stkp_pop() as u16;
-        self.pc |= (self.stkp_pop() as u16) << 8;
-
-        self.pc_next();
-
-        0
     }
 
     // Instruction: Set Carry Flag
     // Function:    C = 1
-    fn sec(&mut self) -> u8 {
+    fn sec(&mut self) {
         self.set_flag(Flags6502::C, true);
-
-        0
     }
 
     // Instruction: Set Decimal Flag
     // Function:    D = 1
-    fn sed(&mut self) -> u8 {
+    fn sed(&mut self) {
         self.set_flag(Flags6502::D, true);
-
-        0
     }
 
     // Instruction: Set Interrupt Flag / Enable Interrupts
     // Function:    I = 1
-    fn sei(&mut self) -> u8 {
+    fn sei(&mut self) {
         self.set_flag(Flags6502::I, true);
-
-        0
     }
 
     // Instruction: Store Accumulator at Address
     // Function:    M = A
-    fn sta(&mut self) -> u8 {
+    fn sta(&mut self) {
         self.write(self.addr_abs, self.a);
-
-        0
     }
 
     // Instruction: Store X Register at Address
     // Function:    M = X
-    fn stx(&mut self) -> u8 {
+    fn stx(&mut self) {
         self.write(self.addr_abs, self.x);
-
-        0
     }
 
     // Instruction: Store Y Register at Address
     // Function:    M = Y
-    fn sty(&mut self) -> u8 {
+    fn sty(&mut self) {
         self.write(self.addr_abs, self.y);
-
-        0
     }
 
     // Instruction: Transfer Accumulator to X Register
     // Function:    X = A
     // Flags Out:   N, Z
-    fn tax(&mut self) -> u8 {
+    fn tax(&mut self) {
         self.x = self.a;
 
         self.set_flag(Flags6502::Z, self.x == 0);
         self.set_flag(Flags6502::N, (self.x & 0x80) > 0);
-
-        0
     }
 
     // Instruction: Transfer Accumulator to Y Register
     // Function:    Y = A
     // Flags Out:   N, Z
-    fn tay(&mut self) -> u8 {
+    fn tay(&mut self) {
         self.y = self.a;
 
         self.set_flag(Flags6502::Z, self.y == 0);
         self.set_flag(Flags6502::N, (self.y & 0x80) > 0);
-
-        0
     }
 
     // Instruction: Transfer Stack Pointer to X Register
     // Function:    X = stack pointer
     // Flags Out:   N, Z
-    fn tsx(&mut self) -> u8 {
+    fn tsx(&mut self) {
         self.x = self.stkp;
 
         self.set_flag(Flags6502::Z, self.x == 0);
         self.set_flag(Flags6502::N, (self.x & 0x80) > 0);
-
-        0
     }
 
     // Instruction: Transfer X Register to Accumulator
     // Function:    A = X
     // Flags Out:   N, Z
-    fn txa(&mut self) -> u8 {
+    fn txa(&mut self) {
         self.a = self.x;
 
         self.set_flag(Flags6502::Z, self.a == 0);
         self.set_flag(Flags6502::N, (self.a & 0x80) > 0);
-
-        0
     }
 
     // Instruction: Transfer X Register to Stack Pointer
     // Function:    stack pointer = X
-    fn txs(&mut self) -> u8 {
+    fn txs(&mut self) {
         self.stkp = self.x;
-        0
     }
 
     // Instruction: Transfer Y Register to Accumulator
     // Function:    A = Y
     // Flags Out:   N, Z
-    fn tya(&mut self) -> u8 {
+    fn tya(&mut self) {
         self.a = self.y;
 
         self.set_flag(Flags6502::Z, self.a == 0);
         self.set_flag(Flags6502::N, (self.a & 0x80) > 0);
-
-        0
     }
 
     /* *************** unofficial ***************  */
-    fn alr(&mut self) -> u8 {
+    fn alr(&mut self) {
         self.fetch();
         let src = self.a & self.fetched;
         let result = src.wrapping_shr(1);
@@ -893,25 +768,24 @@ impl Cpu6502 {
         self.set_flag(Flags6502::N, is_negative);
 
         self.a = result;
-        0
     }
 
-    fn anc(&mut self) -> u8 {
+    fn anc(&mut self) {
         self.fetch();
 
         let result = self.a & self.fetched;
         let is_zero = result == 0;
         let is_negative = (result & 0x80) == 0x80;
-        let is_carry = self.get_flag(Flags6502::N) > 0;
+        // o carry recebe o bit 7 do resultado, como se fosse um ASL/ROL
+        let is_carry = is_negative;
 
         self.set_flag(Flags6502::C, is_carry);
         self.set_flag(Flags6502::Z, is_zero);
         self.set_flag(Flags6502::N, is_negative);
         self.a = result;
-        1
     }
 
-    fn arr(&mut self) -> u8 {
+    fn arr(&mut self) {
         self.fetch();
         let src = self.a & self.fetched;
         let result = src.wrapping_shr(1)
@@ -932,14 +806,15 @@ impl Cpu6502 {
         self.set_flag(Flags6502::V, is_overflow);
 
         self.a = result;
-        1
     }
 
-    fn axs(&mut self) -> u8 {
+    // X = (A & X) - M, sem usar o carry de entrada e sem mexer no overflow
+    fn axs(&mut self) {
         self.fetch();
-        let src = self.a & self.fetched;
+        let src = self.a & self.x;
 
-        let (result, is_carry) = self.a.overflowing_sub(src);
+        let (result, is_borrow) = src.overflowing_sub(self.fetched);
+        let is_carry = !is_borrow;
 
         let is_zero = result == 0;
         let is_negative = (result & 0x80) == 0x80;
@@ -948,10 +823,9 @@ impl Cpu6502 {
         self.set_flag(Flags6502::Z, is_zero);
         self.set_flag(Flags6502::N, is_negative);
         self.x = result;
-        1
     }
 
-    fn lax(&mut self) -> u8 {
+    fn lax(&mut self) {
         self.fetch();
 
         let is_zero = self.fetched == 0;
@@ -961,19 +835,17 @@ impl Cpu6502 {
         self.set_flag(Flags6502::N, is_negative);
         self.a = self.fetched;
         self.x = self.fetched;
-        1
     }
 
-    fn sax(&mut self) -> u8 {
+    fn sax(&mut self) {
         self.fetch();
 
         let result = self.a & self.x;
         self.write(self.addr_abs, result);
-        1
     }
 
     /** DEC and CMP: decrementa de um endereço e depois compara com o acumulador */
-    fn dcp(&mut self) -> u8 {
+    fn dcp(&mut self) {
         self.fetch();
         // DEC
         let dec_result = self.fetched.wrapping_sub(1);
@@ -989,10 +861,9 @@ impl Cpu6502 {
         self.set_flag(Flags6502::C, is_carry);
         self.set_flag(Flags6502::Z, is_zero);
         self.set_flag(Flags6502::N, is_negative);
-        3
     }
 
-    fn isc(&mut self) -> u8 {
+    fn isc(&mut self) {
         self.fetch();
 
         // INC
@@ -1018,10 +889,9 @@ impl Cpu6502 {
         self.set_flag(Flags6502::N, is_negative);
         self.set_flag(Flags6502::V, is_overflow);
         self.a = result;
-        1
     }
 
-    fn rla(&mut self) -> u8 {
+    fn rla(&mut self) {
         self.fetch();
 
         // ROL
@@ -1047,12 +917,10 @@ impl Cpu6502 {
         self.set_flag(Flags6502::N, is_negative);
 
         self.a = result_and;
-
-        3
     }
 
     // ROR -> ADC
-    fn rra(&mut self) -> u8 {
+    fn rra(&mut self) {
         self.fetch();
 
         // ROR
@@ -1088,12 +956,10 @@ impl Cpu6502 {
         self.set_flag(Flags6502::N, is_negative);
         self.set_flag(Flags6502::V, is_overflow);
         self.a = result_adc;
-
-        3
     }
 
     // ASL -> ORA
-    fn slo(&mut self) -> u8 {
+    fn slo(&mut self) {
         self.fetch();
 
         // ASL
@@ -1112,12 +978,10 @@ impl Cpu6502 {
         self.set_flag(Flags6502::Z, is_zero);
         self.set_flag(Flags6502::N, is_negative);
         self.a = result_ora;
-
-        3
     }
 
     // LSR -> EOR
-    fn sre(&mut self) -> u8 {
+    fn sre(&mut self) {
         self.fetch();
 
         // LSR
@@ -1137,24 +1001,106 @@ impl Cpu6502 {
         self.set_flag(Flags6502::Z, is_zero);
         self.set_flag(Flags6502::N, is_negative);
         self.a = result_eor;
+    }
+
+    fn skb(&mut self) {
+        self.fetch();
+    }
+
+    fn ign(&mut self) {
+        self.fetch();
+    }
+
+    // Byte alto do endereço base + 1, usado pelas instruções SHA, SHX, SHY e TAS.
+    // Quando o índice muda de página, o valor gravado também substitui o byte
+    // alto do endereço efetivo (o 6502 mistura os dois no barramento interno)
+    fn unstable_store(&mut self, value: u8) {
+        let hi = (self.addr_abs >> 8) as u8;
+        let base_hi_next = if self.page_crossed {
+            hi
+        } else {
+            hi.wrapping_add(1)
+        };
+        let result = value & base_hi_next;
+
+        if self.page_crossed {
+            self.addr_abs = ((result as u16) << 8) | (self.addr_abs & 0x00FF);
+        }
+        self.write(self.addr_abs, result);
+    }
+
+    // Instruction: SHA (AHX)
+    // Function:    M = A & X & (H + 1)
+    fn sha(&mut self) {
+        self.unstable_store(self.a & self.x);
+    }
+
+    // Instruction: SHX (SXA)
+    // Function:    M = X & (H + 1)
+    fn shx(&mut self) {
+        self.unstable_store(self.x);
+    }
+
+    // Instruction: SHY (SYA)
+    // Function:    M = Y & (H + 1)
+    fn shy(&mut self) {
+        self.unstable_store(self.y);
+    }
 
-        3
+    // Instruction: TAS (XAS, SHS)
+    // Function:    S = A & X, M = S & (H + 1)
+    fn tas(&mut self) {
+        self.stkp = self.a & self.x;
+        self.unstable_store(self.stkp);
     }
 
-    fn skb(&mut self) -> u8 {
+    // Instruction: LAS (LAR)
+    // Function:    A = X = S = M & S
+    // Flags Out:   N, Z
+    fn las(&mut self) {
+        self.fetch();
+        let result = self.fetched & self.stkp;
+
+        self.set_flag(Flags6502::Z, result == 0);
+        self.set_flag(Flags6502::N, (result & 0x80) == 0x80);
+        self.a = result;
+        self.x = result;
+        self.stkp = result;
+    }
+
+    // Instruction: XAA (ANE)
+    // Function:    A = (A | MAGIC) & X & M
+    // Flags Out:   N, Z
+    // O valor de MAGIC varia entre chips e temperatura, 0xEE é o mais comum no 2A03
+    fn xaa(&mut self) {
         self.fetch();
+        let result = (self.a | UNSTABLE_MAGIC) & self.x & self.fetched;
 
-        1
+        self.set_flag(Flags6502::Z, result == 0);
+        self.set_flag(Flags6502::N, (result & 0x80) == 0x80);
+        self.a = result;
     }
 
-    fn ign(&mut self) -> u8 {
+    // Instruction: LXA (LAX imediato, ATX)
+    // Function:    A = X = (A | MAGIC) & M
+    // Flags Out:   N, Z
+    fn lxa(&mut self) {
         self.fetch();
+        let result = (self.a | UNSTABLE_MAGIC) & self.fetched;
+
+        self.set_flag(Flags6502::Z, result == 0);
+        self.set_flag(Flags6502::N, (result & 0x80) == 0x80);
+        self.a = result;
+        self.x = result;
+    }
 
-        1
+    // Instruction: JAM (KIL, HLT)
+    // Function:    trava a CPU, só um reset faz ela voltar a executar
+    fn jam(&mut self) {
+        self.halted = true;
     }
 
     // This function captures illegal opcodes
-    fn xxx(&mut self) -> u8 {
-        0
+    fn xxx(&mut self) {
     }
 }
@@ -0,0 +1,204 @@
+/** https://github.com/Klaus2m5/6502_65C02_functional_tests */
+use std::fs;
+
+use crate::bus::Bus;
+use crate::cartridge::Cartridge;
+use crate::cpu::Cpu6502;
+
+// Quantidade de instruções guardadas no histórico que é exibido quando o teste para
+const HISTORY_SIZE: usize = 16;
+
+// Limite de instruções executadas antes de considerar que o teste travou sem entrar em loop
+const MAX_INSTRUCTIONS: u64 = 200_000_000;
+
+// No teste decimal o resultado fica guardado no endereço ERROR da página zero (0 = sucesso)
+const DECIMAL_ERROR_ADDR: u16 = 0x000B;
+
+#[derive(Clone, Copy)]
+pub enum KlausSuite {
+    Functional,
+    Decimal,
+}
+
+impl KlausSuite {
+    pub fn from(name: &str) -> Option<KlausSuite> {
+        match name {
+            "functional" => Some(KlausSuite::Functional),
+            "decimal" => Some(KlausSuite::Decimal),
+            _ => None,
+        }
+    }
+
+    // Endereço onde a imagem binária é carregada
+    pub fn load_addres(&self) -> u16 {
+        match self {
+            KlausSuite::Functional => 0x0000,
+            KlausSuite::Decimal => 0x0200,
+        }
+    }
+
+    // Endereço da primeira instrução do teste
+    pub fn start_addres(&self) -> u16 {
+        match self {
+            KlausSuite::Functional => 0x0400,
+            KlausSuite::Decimal => 0x0200,
+        }
+    }
+
+    // Endereço do loop de sucesso, no teste decimal o resultado é lido da memória
+    pub fn success_addres(&self) -> Option<u16> {
+        match self {
+            KlausSuite::Functional => Some(0x3469),
+            KlausSuite::Decimal => None,
+        }
+    }
+}
+
+pub struct KlausTest {
+    pub cpu: Cpu6502,
+    pub suite: KlausSuite,
+    pub success: Option<u16>,
+    history: Vec<String>,
+    instructions: u64,
+}
+
+impl KlausTest {
+    pub fn new(suite: KlausSuite, file_name: &str, load: u16) -> KlausTest {
+        let image = fs::read(file_name).unwrap_or_else(|err| {
+            panic!("Failed to load file: {:?}", err);
+        });
+
+        let cartridge = Cartridge::flat(load, &image);
+        let bus = Bus::new(cartridge);
+
+        KlausTest {
+            cpu: Cpu6502::new_with_bus(bus),
+            suite,
+            success: suite.success_addres(),
+            history: vec![],
+            instructions: 0,
+        }
+    }
+
+    fn push_history(&mut self) {
+        if self.history.len() == HISTORY_SIZE {
+            self.history.remove(0);
+        }
+        self.history.push(self.cpu.disassemble_instruction());
+    }
+
+    /** Executa o teste até o PC entrar em um loop (trap), ou seja, até uma instrução
+    desviar para ela mesma. Retorna o endereço do trap */
+    pub fn run(&mut self, start: u16) -> Option<u16> {
+        self.cpu.reset();
+        self.cpu.pc = start;
+
+        while self.instructions < MAX_INSTRUCTIONS {
+            let pc = self.cpu.pc;
+            self.push_history();
+
+            // executando a instrução inteira
+            self.cpu.cpu_clock();
+            while !self.cpu.complete() {
+                self.cpu.cpu_clock();
+            }
+            self.instructions += 1;
+
+            // um JAM também prende a CPU no mesmo lugar
+            if self.cpu.pc == pc || self.cpu.halted {
+                return Some(pc);
+            }
+        }
+
+        None
+    }
+
+    pub fn passed(&mut self, trap: u16) -> bool {
+        match self.suite {
+            KlausSuite::Functional => self.success == Some(trap),
+            KlausSuite::Decimal => {
+                self.success.is_none_or(|success| success == trap)
+                    && self.cpu.bus_read(DECIMAL_ERROR_ADDR, true) == 0
+            }
+        }
+    }
+
+    /** Roda o teste e imprime o resultado, retorna true se o teste passou */
+    pub fn start(&mut self, start: u16) -> bool {
+        let trap = self.run(start);
+
+        let passed = match trap {
+            Some(trap) => {
+                let passed = self.passed(trap);
+                if self.cpu.halted {
+                    println!("[klaus] JAM ${:02X} at ${:04X}", self.cpu.opcode, trap);
+                }
+                println!(
+                    "[klaus] trap at ${:04X} after {} instructions ({} cycles): {}",
+                    trap,
+                    self.instructions,
+                    self.cpu.clock_count,
+                    if passed { "PASSED" } else { "FAILED" }
+                );
+                passed
+            }
+            None => {
+                println!(
+                    "[klaus] no trap detected after {} instructions: FAILED",
+                    self.instructions
+                );
+                false
+            }
+        };
+
+        println!(
+            "[klaus] A: {:#04x} X: {:#04x} Y: {:#04x} STACK: {:#04x} STATUS: {:#010b}",
+            self.cpu.a, self.cpu.x, self.cpu.y, self.cpu.stkp, self.cpu.status
+        );
+        println!("[klaus] last {} instructions:", self.history.len());
+        for instruction in &self.history {
+            println!("    {}", instruction);
+        }
+
+        passed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::path::Path;
+
+    // Os binários não fazem parte do repositório, então os testes só rodam com
+    // "cargo test -- --ignored" e procuram os arquivos na pasta de KLAUS_TESTS
+    // (padrão "roms/klaus"). Um arquivo que não existe é uma falha, não um sucesso
+    fn test_file(name: &str) -> String {
+        let folder = env::var("KLAUS_TESTS").unwrap_or_else(|_| "roms/klaus".to_string());
+        let file = Path::new(&folder).join(name);
+        assert!(
+            file.exists(),
+            "{} not found, set KLAUS_TESTS",
+            file.display()
+        );
+        file.to_string_lossy().to_string()
+    }
+
+    #[test]
+    #[ignore = "needs the Klaus Dormann binaries in KLAUS_TESTS"]
+    fn functional() {
+        let file = test_file("6502_functional_test.bin");
+        let suite = KlausSuite::Functional;
+        let mut test = KlausTest::new(suite, &file, suite.load_addres());
+        assert!(test.start(suite.start_addres()));
+    }
+
+    #[test]
+    #[ignore = "needs the Klaus Dormann binaries in KLAUS_TESTS"]
+    fn decimal() {
+        let file = test_file("6502_decimal_test.bin");
+        let suite = KlausSuite::Decimal;
+        let mut test = KlausTest::new(suite, &file, suite.load_addres());
+        assert!(test.start(suite.start_addres()));
+    }
+}
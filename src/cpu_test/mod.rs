@@ -0,0 +1,5 @@
+mod klaus_test;
+mod single_step;
+
+pub use klaus_test::*;
+pub use single_step::*;
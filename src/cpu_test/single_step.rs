@@ -0,0 +1,265 @@
+/** https://github.com/SingleStepTests/ProcessorTests/tree/main/nes6502 */
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::bus::Bus;
+use crate::cartridge::Cartridge;
+use crate::cpu::{BusActivity, BusOperation, Cpu6502};
+
+// Estado da CPU antes e depois da instrução, "ram" guarda apenas os endereços usados no teste
+#[derive(Deserialize)]
+struct CpuState {
+    pc: u16,
+    s: u8,
+    a: u8,
+    x: u8,
+    y: u8,
+    p: u8,
+    ram: Vec<(u16, u8)>,
+}
+
+#[derive(Deserialize)]
+struct SingleStepCase {
+    name: String,
+    initial: CpuState,
+    #[serde(rename = "final")]
+    expected: CpuState,
+    // Um acesso ao barramento por ciclo: (endereço, valor, "read" | "write")
+    cycles: Vec<(u16, u8, String)>,
+}
+
+#[derive(Default)]
+struct SingleStepReport {
+    total: usize,
+    state_failures: usize,
+    bus_failures: usize,
+    first_failure: Option<String>,
+}
+
+fn format_bus_log(log: &[(u16, u8, BusOperation)]) -> String {
+    log.iter()
+        .map(|(addres, data, operation)| {
+            let operation = match operation {
+                BusOperation::Read => "r",
+                BusOperation::Write => "w",
+            };
+            format!("{}:{:04X}={:02X}", operation, addres, data)
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+pub struct SingleStepTest {
+    pub cpu: Cpu6502,
+}
+
+impl SingleStepTest {
+    pub fn new() -> SingleStepTest {
+        let cartridge = Cartridge::flat(0x0000, &[]);
+        let bus = Bus::new(cartridge);
+
+        SingleStepTest {
+            cpu: Cpu6502::new_with_bus(bus),
+        }
+    }
+
+    fn format_state(&mut self, ram: &[(u16, u8)]) -> String {
+        let mut text = format!(
+            "PC:{:04X} A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X}",
+            self.cpu.pc, self.cpu.a, self.cpu.x, self.cpu.y, self.cpu.status, self.cpu.stkp
+        );
+        for (addres, _) in ram {
+            text += &format!(" [{:04X}]={:02X}", addres, self.cpu.bus_read(*addres, true));
+        }
+        text
+    }
+
+    /** Executa um caso de teste, retorna (estado correto, barramento correto, detalhes) */
+    fn run_case(&mut self, case: &SingleStepCase) -> (bool, bool, String) {
+        for (addres, data) in &case.initial.ram {
+            self.cpu.bus.write(*addres, *data);
+        }
+
+        self.cpu.pc = case.initial.pc;
+        self.cpu.stkp = case.initial.s;
+        self.cpu.a = case.initial.a;
+        self.cpu.x = case.initial.x;
+        self.cpu.y = case.initial.y;
+        self.cpu.status = case.initial.p;
+        // cada caso começa do zero, um JAM ou uma interrupção do caso anterior
+        // não pode continuar valendo
+        self.cpu.clear_micro_state();
+
+        // executando a instrução inteira registrando todos os acessos ao barramento
+        self.cpu.start_bus_log();
+        self.cpu.cpu_clock();
+        while !self.cpu.complete() {
+            self.cpu.cpu_clock();
+        }
+        let log: Vec<(u16, u8, BusOperation)> = self
+            .cpu
+            .stop_bus_log()
+            .iter()
+            .map(|activity: &BusActivity| (activity.addres, activity.data, activity.operation))
+            .collect();
+
+        let expected = &case.expected;
+        let mut state_ok = self.cpu.pc == expected.pc
+            && self.cpu.stkp == expected.s
+            && self.cpu.a == expected.a
+            && self.cpu.x == expected.x
+            && self.cpu.y == expected.y
+            && self.cpu.status == expected.p;
+        for (addres, data) in &expected.ram {
+            state_ok &= self.cpu.bus_read(*addres, true) == *data;
+        }
+
+        let expected_log: Vec<(u16, u8, BusOperation)> = case
+            .cycles
+            .iter()
+            .map(|(addres, data, operation)| {
+                let operation = if operation == "write" {
+                    BusOperation::Write
+                } else {
+                    BusOperation::Read
+                };
+                (*addres, *data, operation)
+            })
+            .collect();
+        let bus_ok = log == expected_log;
+
+        let mut details = String::new();
+        if !state_ok || !bus_ok {
+            details = format!(
+                "test \"{}\"\n    expected: PC:{:04X} A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X}",
+                case.name, expected.pc, expected.a, expected.x, expected.y, expected.p, expected.s
+            );
+            for (addres, data) in &expected.ram {
+                details += &format!(" [{:04X}]={:02X}", addres, data);
+            }
+            details += &format!("\n    got:      {}", self.format_state(&expected.ram));
+            details += &format!("\n    expected bus: {}", format_bus_log(&expected_log));
+            details += &format!("\n    got bus:      {}", format_bus_log(&log));
+        }
+
+        // limpando a memória para o próximo caso
+        for (addres, _) in case.initial.ram.iter().chain(expected.ram.iter()) {
+            self.cpu.bus.write(*addres, 0);
+        }
+        for (addres, _, _) in &log {
+            self.cpu.bus.write(*addres, 0);
+        }
+
+        (state_ok, bus_ok, details)
+    }
+
+    fn run_file(&mut self, file_name: &Path) -> SingleStepReport {
+        let content = fs::read_to_string(file_name).unwrap_or_else(|err| {
+            panic!("Failed to load file: {:?}", err);
+        });
+        let cases: Vec<SingleStepCase> = serde_json::from_str(&content).unwrap_or_else(|err| {
+            panic!("Failed to parse {}: {:?}", file_name.display(), err);
+        });
+
+        let mut report = SingleStepReport::default();
+        for case in &cases {
+            let (state_ok, bus_ok, details) = self.run_case(case);
+            report.total += 1;
+            if !state_ok {
+                report.state_failures += 1;
+            }
+            if !bus_ok {
+                report.bus_failures += 1;
+            }
+            if (!state_ok || !bus_ok) && report.first_failure.is_none() {
+                report.first_failure = Some(details);
+            }
+        }
+
+        report
+    }
+
+    /** Roda um arquivo .json ou todos os arquivos .json de uma pasta, retorna true se
+    todos os casos passaram (estado final e acessos ao barramento) */
+    pub fn start(&mut self, path: &str) -> bool {
+        let path = Path::new(path);
+        let mut files: Vec<PathBuf> = if path.is_dir() {
+            fs::read_dir(path)
+                .unwrap()
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|file| file.extension().is_some_and(|ext| ext == "json"))
+                .collect()
+        } else {
+            vec![path.to_path_buf()]
+        };
+        files.sort();
+
+        let mut passed = true;
+        for file in &files {
+            let report = self.run_file(file);
+            println!(
+                "[single-step] {}: {} cases, {} state failures, {} bus failures",
+                file.display(),
+                report.total,
+                report.state_failures,
+                report.bus_failures
+            );
+            if let Some(details) = report.first_failure {
+                println!("    {}", details);
+                passed = false;
+            }
+        }
+
+        passed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn case(json: &str) -> SingleStepCase {
+        serde_json::from_str(json).unwrap()
+    }
+
+    // Os arquivos .json do nes6502 não fazem parte do repositório, então o teste só
+    // roda com "cargo test -- --ignored" e lê a pasta de SINGLE_STEP_TESTS (padrão
+    // "roms/nes6502/v1"). Uma pasta que não existe é uma falha, não um sucesso
+    #[test]
+    #[ignore = "needs the nes6502 json files in SINGLE_STEP_TESTS"]
+    fn nes6502() {
+        let folder =
+            env::var("SINGLE_STEP_TESTS").unwrap_or_else(|_| "roms/nes6502/v1".to_string());
+        assert!(
+            Path::new(&folder).exists(),
+            "{} not found, set SINGLE_STEP_TESTS",
+            folder
+        );
+        assert!(SingleStepTest::new().start(&folder));
+    }
+
+    #[test]
+    fn case_after_jam() {
+        let mut test = SingleStepTest::new();
+
+        // 02 (JAM) trava a CPU, o caso seguinte precisa rodar normalmente
+        test.run_case(&case(
+            r#"{"name": "02 jam",
+                "initial": {"pc": 768, "s": 253, "a": 0, "x": 0, "y": 0, "p": 36, "ram": [[768, 2]]},
+                "final": {"pc": 769, "s": 253, "a": 0, "x": 0, "y": 0, "p": 36, "ram": [[768, 2]]},
+                "cycles": [[768, 2, "read"]]}"#,
+        ));
+        assert!(test.cpu.halted);
+
+        let (state_ok, bus_ok, details) = test.run_case(&case(
+            r#"{"name": "a9 lda #$42",
+                "initial": {"pc": 512, "s": 253, "a": 0, "x": 0, "y": 0, "p": 36, "ram": [[512, 169], [513, 66]]},
+                "final": {"pc": 514, "s": 253, "a": 66, "x": 0, "y": 0, "p": 36, "ram": [[512, 169], [513, 66]]},
+                "cycles": [[512, 169, "read"], [513, 66, "read"]]}"#,
+        ));
+        assert!(state_ok && bus_ok, "{}", details);
+    }
+}
@@ -1,16 +1,17 @@
 mod code;
 
-use graphics::{clear, Context};
-use piston::Key;
-use piston_window::{G2d, G2dTextureContext, Glyphs};
+#[cfg(feature = "window")]
+mod window;
+
 use rand::Rng;
 
 use std::{thread, time::Duration};
 
-use crate::video::{Frame, Pixel, Video, BLACK_PIXEL};
-use crate::{bus::Bus, video::draw_cpu};
-use crate::{cartridge::Cartridge, video::draw_code};
-use crate::{cpu::Cpu6502, video::draw_ram};
+use crate::bus::Bus;
+use crate::cartridge::Cartridge;
+use crate::cpu::Cpu6502;
+use crate::pad::PadButton;
+use crate::video::{Frame, Image, Pixel, Video};
 
 use code::GAME_CODE;
 
@@ -32,6 +33,7 @@ pub struct SnakeGame {
     pub cpu: Cpu6502,
     running: bool,
     history: Vec<String>,
+    #[cfg(feature = "window")]
     ram_offset: u16,
     screen: Frame,
 }
@@ -65,7 +67,7 @@ impl Video for SnakeGame {
             loop {
                 let end_cycle = self.cpu.pc == 0x734;
                 self.cpu.cpu_clock();
-                while self.cpu.cycles > 0 {
+                while !self.cpu.complete() {
                     self.cpu.cpu_clock();
                 }
                 if end_cycle {
@@ -83,73 +85,33 @@ impl Video for SnakeGame {
         }
     }
 
-    fn update_textures(&mut self, texture_context: &mut G2dTextureContext) {
-        self.screen.update_texture(texture_context);
+    fn screen_image(&mut self) -> Image {
+        self.screen.canvas_image()
     }
 
-    fn draw(&mut self, context: Context, gl: &mut G2d, glyphs: &mut Glyphs) {
-        clear(BLACK_PIXEL.get_color(), gl);
-
-        // Draws
-        self.screen.render_image(50, 50, 10.0, context, gl);
-        draw_cpu(550, 50, &mut self.cpu, context, gl, glyphs);
-        draw_code(550, 200, &self.history, context, gl, glyphs);
-        draw_ram(
-            550,
-            400,
-            self.ram_offset,
-            &mut self.cpu,
-            10,
-            context,
-            gl,
-            glyphs,
-        );
+    // O jogo lê a última tecla em $FF, como o "w", "a", "s" e "d" do teclado
+    fn on_pad_press(&mut self, button: PadButton) {
+        match button {
+            PadButton::Up => self.cpu.write(0xFF, 0x77),
+            PadButton::Down => self.cpu.write(0xFF, 0x73),
+            PadButton::Left => self.cpu.write(0xFF, 0x61),
+            PadButton::Right => self.cpu.write(0xFF, 0x64),
+            _ => {}
+        }
     }
 
-    fn on_buttom_press(&mut self, key: Key) {
-        match key {
-            Key::Up => self.cpu.write(0xFF, 0x77),
-            Key::Down => self.cpu.write(0xFF, 0x73),
-            Key::Left => self.cpu.write(0xFF, 0x61),
-            Key::Right => self.cpu.write(0xFF, 0x64),
-            Key::P => self.running = !self.running,
-            Key::PageDown => {
-                if self.ram_offset < (0xFFFE - 100) {
-                    self.ram_offset += 100;
-                } else {
-                    self.ram_offset = 0xFFFF;
-                }
-            }
-            Key::PageUp => {
-                if self.ram_offset > 100 {
-                    self.ram_offset -= 100;
-                } else {
-                    self.ram_offset = 0;
-                }
-            }
-            Key::NumPadPlus => {
-                if self.ram_offset < 0xFFFE {
-                    self.ram_offset += 1;
-                }
-            }
-            Key::NumPadMinus => {
-                if self.ram_offset > 0 {
-                    self.ram_offset -= 1;
-                }
-            }
-            Key::N => {
-                self.cpu.cpu_clock();
-                while self.cpu.cycles > 0 {
-                    self.cpu.cpu_clock();
-                }
+    fn toggle_pause(&mut self) {
+        self.running = !self.running;
+    }
 
-                if self.history.len() == 5 {
-                    self.history.remove(0);
-                }
-                self.history.push(self.cpu.disassemble_instruction());
-            }
-            _ => {}
-        }
+    fn reset(&mut self) {
+        self.cpu.reset();
+        self.cpu.load(0x0600, Vec::from(GAME_CODE));
+        self.cpu.pc = 0x0600;
+    }
+
+    fn is_running(&self) -> bool {
+        self.running
     }
 }
 
@@ -161,16 +123,9 @@ impl SnakeGame {
             cpu: Cpu6502::new_with_bus(bus),
             running: false,
             history: vec![],
+            #[cfg(feature = "window")]
             ram_offset: 0,
             screen: Frame::new(32, 32),
         }
     }
-
-    pub fn start(&mut self) {
-        self.cpu.reset();
-        self.cpu.load(0x0600, Vec::from(GAME_CODE));
-        self.cpu.pc = 0x0600;
-
-        self.start_loop("Snake Game");
-    }
 }
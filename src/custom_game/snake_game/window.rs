@@ -0,0 +1,85 @@
+use graphics::{clear, Context};
+use piston::Key;
+use piston_window::{G2d, G2dTextureContext, Glyphs};
+
+use super::SnakeGame;
+use crate::pad::PadButton;
+use crate::video::{draw_code, draw_cpu, draw_ram, Video, Window, BLACK_PIXEL};
+
+impl Window for SnakeGame {
+    fn update_textures(&mut self, texture_context: &mut G2dTextureContext) {
+        self.screen.update_texture(texture_context);
+    }
+
+    fn draw(&mut self, context: Context, gl: &mut G2d, glyphs: &mut Glyphs) {
+        clear(BLACK_PIXEL.get_color(), gl);
+
+        // Draws
+        self.screen.render_image(50, 50, 10.0, context, gl);
+        draw_cpu(550, 50, &mut self.cpu, context, gl, glyphs);
+        draw_code(550, 200, &self.history, context, gl, glyphs);
+        draw_ram(
+            550,
+            400,
+            self.ram_offset,
+            &mut self.cpu,
+            10,
+            context,
+            gl,
+            glyphs,
+        );
+    }
+
+    fn on_buttom_press(&mut self, key: Key) {
+        match key {
+            Key::Up => self.on_pad_press(PadButton::Up),
+            Key::Down => self.on_pad_press(PadButton::Down),
+            Key::Left => self.on_pad_press(PadButton::Left),
+            Key::Right => self.on_pad_press(PadButton::Right),
+            Key::P => self.toggle_pause(),
+            Key::PageDown => {
+                if self.ram_offset < (0xFFFE - 100) {
+                    self.ram_offset += 100;
+                } else {
+                    self.ram_offset = 0xFFFF;
+                }
+            }
+            Key::PageUp => {
+                if self.ram_offset > 100 {
+                    self.ram_offset -= 100;
+                } else {
+                    self.ram_offset = 0;
+                }
+            }
+            Key::NumPadPlus => {
+                if self.ram_offset < 0xFFFE {
+                    self.ram_offset += 1;
+                }
+            }
+            Key::NumPadMinus => {
+                if self.ram_offset > 0 {
+                    self.ram_offset -= 1;
+                }
+            }
+            Key::N => {
+                self.cpu.cpu_clock();
+                while !self.cpu.complete() {
+                    self.cpu.cpu_clock();
+                }
+
+                if self.history.len() == 5 {
+                    self.history.remove(0);
+                }
+                self.history.push(self.cpu.disassemble_instruction());
+            }
+            _ => {}
+        }
+    }
+}
+
+impl SnakeGame {
+    pub fn start(&mut self) {
+        self.reset();
+        self.start_loop("Snake Game");
+    }
+}
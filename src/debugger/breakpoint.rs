@@ -0,0 +1,251 @@
+use super::{Expression, Symbols};
+use crate::cpu::BusOperation;
+
+// Breakpoints e watchpoints =====================================
+// Um breakpoint é escrito como "[tipo][:espaço] endereço[-endereço] [if condição]":
+//   $C000                  para quando a CPU for executar $C000
+//   x $C000 if X == 3      só para se a condição for verdadeira
+//   w $2005                para depois de uma escrita em $2005
+//   rw $0300-$03FF         leitura ou escrita na faixa
+//   w:ppu $2000-$23FF      escrita na VRAM pela CPU ($2007)
+// Os tipos são x (execução, padrão), r (leitura), w (escrita) e rw. O espaço
+// é cpu (padrão) ou ppu, que só aceita watchpoints. Os endereços da CPU
+// também podem ser nomes dos símbolos carregados ("w player_x", "nmi").
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BreakpointKind {
+    Execute,
+    Read,
+    Write,
+    ReadWrite,
+}
+
+impl BreakpointKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BreakpointKind::Execute => "x",
+            BreakpointKind::Read => "r",
+            BreakpointKind::Write => "w",
+            BreakpointKind::ReadWrite => "rw",
+        }
+    }
+
+    /** O watchpoint para nesse tipo de acesso ao barramento? */
+    pub fn matches(&self, operation: BusOperation) -> bool {
+        match self {
+            BreakpointKind::Execute => false,
+            BreakpointKind::Read => operation == BusOperation::Read,
+            BreakpointKind::Write => operation == BusOperation::Write,
+            BreakpointKind::ReadWrite => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AddressSpace {
+    Cpu,
+    Ppu,
+}
+
+impl AddressSpace {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AddressSpace::Cpu => "cpu",
+            AddressSpace::Ppu => "ppu",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Breakpoint {
+    pub kind: BreakpointKind,
+    pub space: AddressSpace,
+    pub start: u16,
+    pub end: u16,
+    pub condition: Option<Expression>,
+    /** Texto da condição, para mostrar o breakpoint como foi escrito */
+    pub condition_text: String,
+    pub enabled: bool,
+}
+
+/** Endereço em hexadecimal: "C000", "$C000" ou "0xC000" */
+pub fn parse_addres(value: &str) -> Result<u16, String> {
+    let hex = value.trim_start_matches("0x").trim_start_matches('$');
+    u16::from_str_radix(hex, 16).map_err(|_| format!("invalid address: {}", value))
+}
+
+/** Endereço em hexadecimal ou o nome de um símbolo, o símbolo tem preferência ("add" também
+é um número hexadecimal válido) */
+pub fn parse_addres_or_symbol(value: &str, symbols: &Symbols) -> Result<u16, String> {
+    match symbols.addres(value) {
+        Some(addres) => Ok(addres),
+        None => parse_addres(value),
+    }
+}
+
+impl Breakpoint {
+    pub fn parse(text: &str, symbols: &Symbols) -> Result<Breakpoint, String> {
+        // A condição é tudo depois do primeiro "if"
+        let (target, condition_text) = match text.find(" if ") {
+            Some(position) => (&text[..position], text[position + 4..].trim()),
+            None => (text, ""),
+        };
+
+        let mut words: Vec<&str> = target.split_whitespace().collect();
+        let (kind, space) = if words.len() > 1 {
+            let mut kind_space = words.remove(0).split(':');
+            let kind = match kind_space.next() {
+                Some("x") => BreakpointKind::Execute,
+                Some("r") => BreakpointKind::Read,
+                Some("w") => BreakpointKind::Write,
+                Some("rw") => BreakpointKind::ReadWrite,
+                kind => return Err(format!("invalid breakpoint type: {:?}", kind)),
+            };
+            let space = match kind_space.next() {
+                None | Some("cpu") => AddressSpace::Cpu,
+                Some("ppu") => AddressSpace::Ppu,
+                Some(space) => return Err(format!("invalid address space: {}", space)),
+            };
+            (kind, space)
+        } else {
+            (BreakpointKind::Execute, AddressSpace::Cpu)
+        };
+
+        if kind == BreakpointKind::Execute && space == AddressSpace::Ppu {
+            return Err("execution breakpoints are only valid on the cpu".to_string());
+        }
+        if words.len() != 1 {
+            return Err(format!("invalid breakpoint: {}", text));
+        }
+
+        // Os símbolos são endereços da CPU, na PPU só valem números
+        let parse = |value: &str| match space {
+            AddressSpace::Cpu => parse_addres_or_symbol(value, symbols),
+            AddressSpace::Ppu => parse_addres(value),
+        };
+        let (start, end) = match words[0].split_once('-') {
+            Some((start, end)) => (parse(start)?, parse(end)?),
+            None => {
+                let addres = parse(words[0])?;
+                (addres, addres)
+            }
+        };
+        if end < start {
+            return Err(format!("invalid range: {}", words[0]));
+        }
+
+        let condition = if condition_text.is_empty() {
+            None
+        } else {
+            Some(Expression::parse(condition_text, symbols)?)
+        };
+
+        Ok(Breakpoint {
+            kind,
+            space,
+            start,
+            end,
+            condition,
+            condition_text: condition_text.to_string(),
+            enabled: true,
+        })
+    }
+
+    pub fn contains(&self, addres: u16) -> bool {
+        addres >= self.start && addres <= self.end
+    }
+
+    /** O breakpoint no mesmo formato aceito pelo parse */
+    pub fn describe(&self) -> String {
+        let mut text = self.kind.name().to_string();
+        if self.space != AddressSpace::Cpu {
+            text += &format!(":{}", self.space.name());
+        }
+        text += &format!(" ${:04X}", self.start);
+        if self.end != self.start {
+            text += &format!("-${:04X}", self.end);
+        }
+        if !self.condition_text.is_empty() {
+            text += &format!(" if {}", self.condition_text);
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<Breakpoint, String> {
+        let mut symbols = Symbols::new();
+        symbols.add_cpu(0xC123, "nmi");
+        symbols.add_cpu(0x0010, "add");
+        Breakpoint::parse(text, &symbols)
+    }
+
+    #[test]
+    fn kinds_and_spaces() {
+        let breakpoint = parse("$C000").unwrap();
+        assert_eq!(breakpoint.kind, BreakpointKind::Execute);
+        assert_eq!(breakpoint.space, AddressSpace::Cpu);
+        assert_eq!((breakpoint.start, breakpoint.end), (0xC000, 0xC000));
+        assert!(breakpoint.condition.is_none());
+
+        let breakpoint = parse("w:ppu $2000-$23FF").unwrap();
+        assert_eq!(breakpoint.kind, BreakpointKind::Write);
+        assert_eq!(breakpoint.space, AddressSpace::Ppu);
+
+        let breakpoint = parse("r:cpu 0x4016").unwrap();
+        assert_eq!(breakpoint.kind, BreakpointKind::Read);
+        assert_eq!(breakpoint.space, AddressSpace::Cpu);
+        assert_eq!(breakpoint.start, 0x4016);
+    }
+
+    #[test]
+    fn ranges() {
+        let breakpoint = parse("rw $0300-$03FF").unwrap();
+        assert_eq!(breakpoint.kind, BreakpointKind::ReadWrite);
+        assert!(breakpoint.contains(0x0300));
+        assert!(breakpoint.contains(0x03FF));
+        assert!(!breakpoint.contains(0x02FF));
+        assert!(!breakpoint.contains(0x0400));
+    }
+
+    #[test]
+    fn symbols_and_conditions() {
+        assert_eq!(parse("nmi").unwrap().start, 0xC123);
+        // O símbolo vem antes do número hexadecimal com o mesmo texto
+        assert_eq!(parse("w add").unwrap().start, 0x0010);
+        assert_eq!(parse("w $add").unwrap().start, 0x0ADD);
+        // Na PPU só valem números
+        assert!(parse("w:ppu nmi").is_err());
+
+        let breakpoint = parse("x $C000 if X == 3").unwrap();
+        assert!(breakpoint.condition.is_some());
+        assert_eq!(breakpoint.condition_text, "X == 3");
+        assert_eq!(breakpoint.describe(), "x $C000 if X == 3");
+        assert_eq!(
+            parse("rw:ppu $2000-$23FF").unwrap().describe(),
+            "rw:ppu $2000-$23FF"
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let cases = [
+            (
+                "x:ppu $2000",
+                "execution breakpoints are only valid on the cpu",
+            ),
+            ("r:apu $4000", "invalid address space: apu"),
+            ("q $10", "invalid breakpoint type: Some(\"q\")"),
+            ("w $0400-$0300", "invalid range: $0400-$0300"),
+            ("w $10 $20", "invalid breakpoint: w $10 $20"),
+            ("w $10000", "invalid address: $10000"),
+            ("x $C000 if A +", "unexpected end of expression"),
+        ];
+        for (text, error) in cases.iter() {
+            assert_eq!(parse(text).map(|_| ()), Err(error.to_string()), "{}", text);
+        }
+    }
+}
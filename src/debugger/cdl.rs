@@ -0,0 +1,166 @@
+use std::fs;
+
+use super::trace::instruction_size;
+use crate::cpu::{AddressMode, Cpu6502, Instruction};
+use crate::ppu::Ppu2C02;
+
+// Code/Data Logger =============================================
+// Marca cada byte da PRG que a CPU executou como código ou leu como dado
+// e cada byte da CHR que a PPU desenhou ou que a CPU leu pelo $2007. O
+// arquivo é o .cdl do FCEUX: um byte de flags por byte da PRG, seguido de
+// um por byte da CHR. Quando o arquivo já existe (e tem o tamanho certo)
+// as flags novas são somadas às que estavam lá, assim dá para juntar
+// várias sessões de jogo.
+//
+// Flags da PRG:
+//   0x01 código    0x02 dado    0x0C bits 13-14 do endereço da CPU
+//   0x10 destino de um JMP indireto    0x20 dado lido por (zp),Y ou (zp,X)
+//   0x40 amostra do DMC, nunca marcada porque ainda não existe APU
+// Flags da CHR:
+//   0x01 desenhado    0x02 lido pelo $2007
+//
+// confira: https://fceux.com/web/help/CodeDataLogger.html
+
+pub const CDL_PRG_CODE: u8 = 0x01;
+pub const CDL_PRG_DATA: u8 = 0x02;
+pub const CDL_PRG_INDIRECT_CODE: u8 = 0x10;
+pub const CDL_PRG_INDIRECT_DATA: u8 = 0x20;
+pub const CDL_CHR_DRAWN: u8 = 0x01;
+pub const CDL_CHR_READ: u8 = 0x02;
+
+pub struct CodeDataLog {
+    pub path: String,
+    pub prg: Vec<u8>,
+    /** A última instrução foi um JMP indireto, a próxima é o destino dele */
+    indirect_jump: bool,
+}
+
+// Flags com os bits do endereço da CPU, que o FCEUX usa para saber em qual
+// janela de 8KB o byte estava mapeado
+fn prg_flag(addres: u16, flag: u8) -> u8 {
+    flag | ((addres >> 13) as u8 & 0x03) << 2
+}
+
+/** Quantidade de bytes com alguma das flags */
+fn count(log: &[u8], flags: u8) -> usize {
+    log.iter().filter(|byte| **byte & flags != 0).count()
+}
+
+// Marcação durante a execução
+impl Cpu6502 {
+    fn mark_prg(&mut self, addres: u16, flag: u8) {
+        if let Some(position) = self.prg_position(addres) {
+            if let Some(byte) = self.cdl.as_mut().and_then(|cdl| cdl.prg.get_mut(position)) {
+                *byte |= prg_flag(addres, flag);
+            }
+        }
+    }
+
+    /** Chamado na busca do opcode, marca a instrução inteira como código */
+    pub fn log_code(&mut self, pc: u16) {
+        let indirect = match &mut self.cdl {
+            Some(cdl) => std::mem::replace(&mut cdl.indirect_jump, false),
+            None => return,
+        };
+
+        let size = instruction_size(Instruction::from(self.bus_read(pc, true)));
+        let flag = if indirect {
+            CDL_PRG_CODE | CDL_PRG_INDIRECT_CODE
+        } else {
+            CDL_PRG_CODE
+        };
+        self.mark_prg(pc, flag);
+        for i in 1..size {
+            self.mark_prg(pc.wrapping_add(i), CDL_PRG_CODE);
+        }
+    }
+
+    /** Chamado quando uma instrução termina de ler o operando da memória */
+    pub fn log_data(&mut self, addres: u16, mode: AddressMode) {
+        if self.cdl.is_none() {
+            return;
+        }
+        let flag = match mode {
+            AddressMode::IZX | AddressMode::IZY => CDL_PRG_DATA | CDL_PRG_INDIRECT_DATA,
+            _ => CDL_PRG_DATA,
+        };
+        self.mark_prg(addres, flag);
+    }
+
+    pub fn log_indirect_jump(&mut self) {
+        if let Some(cdl) = &mut self.cdl {
+            cdl.indirect_jump = true;
+        }
+    }
+
+    /** Começa o log, somando ao arquivo se ele já existe */
+    pub fn start_cdl(&mut self, path: &str) -> Result<(), String> {
+        let prg_size = self.bus.prg_rom.prg_memory.len();
+        let chr_size = self.bus.ppu.chr_rom.chr_memory.len();
+
+        let (prg, chr) = match fs::read(path) {
+            Ok(data) if data.len() == prg_size + chr_size => {
+                let (prg, chr) = data.split_at(prg_size);
+                (prg.to_vec(), chr.to_vec())
+            }
+            Ok(_) => return Err(format!("{}: size is not PRG + CHR", path)),
+            Err(_) => (vec![0; prg_size], vec![0; chr_size]),
+        };
+
+        self.cdl = Some(CodeDataLog {
+            path: path.to_string(),
+            prg,
+            indirect_jump: false,
+        });
+        self.bus.ppu.chr_log = Some(chr);
+        Ok(())
+    }
+
+    /** Termina o log e grava o arquivo, retorna um resumo do que foi marcado */
+    pub fn stop_cdl(&mut self) -> Result<String, String> {
+        let cdl = match self.cdl.take() {
+            Some(cdl) => cdl,
+            None => return Ok(String::new()),
+        };
+        let chr = self.bus.ppu.chr_log.take().unwrap_or_default();
+
+        let mut data = cdl.prg.clone();
+        data.extend_from_slice(&chr);
+        fs::write(&cdl.path, data).map_err(|e| format!("{}: {}", cdl.path, e))?;
+
+        Ok(format!(
+            "PRG {} code, {} data of {}; CHR {} drawn, {} read of {}: {}",
+            count(&cdl.prg, CDL_PRG_CODE),
+            count(&cdl.prg, CDL_PRG_DATA),
+            cdl.prg.len(),
+            count(&chr, CDL_CHR_DRAWN),
+            count(&chr, CDL_CHR_READ),
+            chr.len(),
+            cdl.path
+        ))
+    }
+}
+
+impl Ppu2C02 {
+    /** Marca um byte da CHR, "addres" é o endereço no barramento da PPU */
+    pub fn log_chr(&mut self, addres: u16, flag: u8) {
+        if self.chr_log.is_none() || addres >= 0x2000 {
+            return;
+        }
+        let (mapped, position) = self.chr_rom.mapper.ppu_map_read(addres);
+        if let Some(byte) = self
+            .chr_log
+            .as_mut()
+            .and_then(|log| log.get_mut(position as usize))
+            .filter(|_| mapped)
+        {
+            *byte |= flag;
+        }
+    }
+
+    /** Leitura da pattern table para desenhar */
+    pub fn pattern_read(&mut self, addres: u16) -> u8 {
+        self.log_chr(addres, CDL_CHR_DRAWN);
+        self.ppu_read(addres)
+    }
+}
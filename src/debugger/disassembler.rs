@@ -0,0 +1,667 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::Path;
+
+use super::{instruction_name, CDL_PRG_CODE, CDL_PRG_DATA, CDL_PRG_INDIRECT_CODE};
+use crate::cpu::{AddressMode, Instruction};
+
+// Disassembler estático ========================================
+// Separa código e dados da PRG ROM inteira seguindo o código a partir
+// dos vetores de NMI, reset e IRQ: cada instrução marca os seus bytes como
+// código e os destinos dos branches, JMP e JSR entram na fila. Um JMP
+// indireto, RTS, RTI ou BRK termina o caminho. Os opcodes ilegais e o que
+// nunca foi alcançado viram ".byte".
+//
+// Sem executar o jogo não dá para saber qual banco está mapeado, então
+// com mais de 32KB o último banco de 16KB é considerado fixo em $C000 e os
+// outros em $8000 (UxROM, MMC1 no modo padrão). Os saltos do banco fixo
+// para $8000-$BFFF não são seguidos.
+//
+// Com um .cdl do Code/Data Logger o resultado fica bem melhor: o que o
+// jogo leu como dado nunca é decodificado e o que ele executou entra como
+// ponto de partida, inclusive os destinos dos JMP indiretos que só dá
+// para descobrir executando.
+//
+// A saída é um .asm do ca65 que gera o arquivo .nes idêntico, com um .org
+// por banco e um .cfg para o ld65:
+//   ca65 game.asm -o game.o && ld65 -C game.cfg game.o -o game.nes
+//
+// confira: https://cc65.github.io/doc/ca65.html
+
+const HEADER_SIZE: usize = 16;
+const TRAINER_SIZE: usize = 512;
+const BANK_SIZE: usize = 16384;
+const BYTES_PER_LINE: usize = 16;
+const VECTORS: u16 = 0xFFFA;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ByteKind {
+    Unknown,
+    Code,
+    /** Bytes de operando de uma instrução */
+    Operand,
+    Vector,
+    /** Lido como dado segundo o .cdl */
+    Data,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PrgBank {
+    /** Posição do banco na PRG */
+    start: usize,
+    size: usize,
+    /** Endereço da CPU onde o banco está mapeado */
+    base: u16,
+    /** O NROM de 16KB também aparece em $8000 */
+    mirror: Option<u16>,
+    fixed: bool,
+}
+
+impl PrgBank {
+    fn offset(&self, addres: u16) -> Option<usize> {
+        [Some(self.base), self.mirror]
+            .iter()
+            .flatten()
+            .find(|base| addres >= **base && ((addres - **base) as usize) < self.size)
+            .map(|base| (addres - base) as usize)
+    }
+}
+
+pub struct Disassembler {
+    rom: Vec<u8>,
+    prg_start: usize,
+    prg_size: usize,
+    banks: Vec<PrgBank>,
+    /** Um por byte da PRG */
+    kinds: Vec<ByteKind>,
+    /** Flags do .cdl de cada byte da PRG, vazio sem o arquivo */
+    cdl: Vec<u8>,
+    /** Nome dos endereços, pela posição na PRG */
+    pub labels: HashMap<usize, String>,
+}
+
+// Instruções que não continuam na próxima
+fn ends_flow(name: &str) -> bool {
+    matches!(name, "RTS" | "RTI" | "BRK" | "JMP")
+}
+
+fn instruction_size(mode: AddressMode) -> usize {
+    match mode {
+        AddressMode::IMP => 1,
+        AddressMode::ABS | AddressMode::ABX | AddressMode::ABY | AddressMode::IND => 3,
+        _ => 2,
+    }
+}
+
+// Opcodes que o ca65 gera do mesmo jeito (sem os ilegais e suas repetições)
+fn is_official(opcode: u8) -> bool {
+    let name = instruction_name(opcode);
+    !name.starts_with('*') && name != "XXX"
+}
+
+impl Disassembler {
+    /** Lê a ROM e separa código e dados, usando o .cdl do FCEUX quando existe */
+    pub fn from_file(file_name: &str, cdl: Option<&str>) -> Result<Disassembler, String> {
+        let rom = fs::read(file_name).map_err(|e| format!("{}: {}", file_name, e))?;
+        if rom.len() < HEADER_SIZE || &rom[0..4] != b"NES\x1a" || rom[4] == 0 {
+            return Err(format!("{}: not an iNES file", file_name));
+        }
+
+        let prg_start = HEADER_SIZE + if rom[6] & 0x04 > 0 { TRAINER_SIZE } else { 0 };
+        let prg_size = rom[4] as usize * BANK_SIZE;
+        if prg_start + prg_size > rom.len() {
+            return Err(format!("{}: file smaller than the header says", file_name));
+        }
+
+        let banks = match rom[4] {
+            1 => vec![PrgBank {
+                start: 0,
+                size: BANK_SIZE,
+                base: 0xC000,
+                mirror: Some(0x8000),
+                fixed: true,
+            }],
+            2 => vec![PrgBank {
+                start: 0,
+                size: 2 * BANK_SIZE,
+                base: 0x8000,
+                mirror: None,
+                fixed: true,
+            }],
+            count => (0..count as usize)
+                .map(|bank| {
+                    let fixed = bank == count as usize - 1;
+                    PrgBank {
+                        start: bank * BANK_SIZE,
+                        size: BANK_SIZE,
+                        base: if fixed { 0xC000 } else { 0x8000 },
+                        mirror: None,
+                        fixed,
+                    }
+                })
+                .collect(),
+        };
+
+        // O .cdl tem a PRG e depois a CHR, só a PRG interessa
+        let cdl = match cdl {
+            Some(cdl_file) => {
+                let mut data = fs::read(cdl_file).map_err(|e| format!("{}: {}", cdl_file, e))?;
+                if data.len() < prg_size {
+                    return Err(format!("{}: smaller than the PRG ROM", cdl_file));
+                }
+                data.truncate(prg_size);
+                data
+            }
+            None => vec![],
+        };
+
+        let mut disassembler = Disassembler {
+            rom,
+            prg_start,
+            prg_size,
+            banks,
+            kinds: vec![ByteKind::Unknown; prg_size],
+            cdl,
+            labels: HashMap::new(),
+        };
+        disassembler.analyze();
+        Ok(disassembler)
+    }
+
+    fn prg(&self, position: usize) -> u8 {
+        self.rom[self.prg_start + position]
+    }
+
+    /** Posição na PRG de um endereço da CPU visto de dentro do banco "from" (o banco fixo
+    é visto de todos os outros) */
+    fn resolve(&self, from: usize, addres: u16) -> Option<usize> {
+        self.banks
+            .iter()
+            .enumerate()
+            .filter(|(bank, info)| info.fixed || *bank == from)
+            .find_map(|(_, bank)| bank.offset(addres).map(|offset| bank.start + offset))
+    }
+
+    fn bank_of(&self, position: usize) -> usize {
+        self.banks
+            .iter()
+            .position(|bank| position >= bank.start && position < bank.start + bank.size)
+            .unwrap_or(0)
+    }
+
+    // Endereço da CPU de uma posição da PRG (o mapeamento principal do banco)
+    fn addres_of(&self, position: usize) -> u16 {
+        let bank = self.banks[self.bank_of(position)];
+        bank.base.wrapping_add((position - bank.start) as u16)
+    }
+
+    fn analyze(&mut self) {
+        let fixed = self.banks.len() - 1;
+        let vectors = self.resolve(fixed, VECTORS).unwrap();
+        for kind in &mut self.kinds[vectors..vectors + 6] {
+            *kind = ByteKind::Vector;
+        }
+
+        // Só dado, nunca executado
+        for (position, flags) in self.cdl.iter().enumerate() {
+            if flags & (CDL_PRG_CODE | CDL_PRG_DATA) == CDL_PRG_DATA
+                && self.kinds[position] == ByteKind::Unknown
+            {
+                self.kinds[position] = ByteKind::Data;
+            }
+        }
+
+        let mut pending = vec![];
+        let mut references = BTreeSet::new();
+        for (index, name) in ["nmi", "reset", "irq"].iter().enumerate() {
+            let addres = self.vector(index);
+            if let Some(position) = self.resolve(fixed, addres) {
+                if self.trace(position, &mut pending, &mut references) {
+                    self.labels
+                        .entry(position)
+                        .or_insert_with(|| name.to_string());
+                }
+            }
+        }
+
+        while let Some(position) = pending.pop() {
+            self.trace(position, &mut pending, &mut references);
+        }
+
+        // O código executado que não foi alcançado pelos vetores (depois de um JMP
+        // indireto, RTS usado como salto...) começa logo depois do que já foi seguido
+        for position in 0..self.cdl.len() {
+            let flags = self.cdl[position];
+            if flags & CDL_PRG_INDIRECT_CODE != 0 {
+                references.insert(position);
+            }
+            if flags & CDL_PRG_CODE == 0 || self.kinds[position] != ByteKind::Unknown {
+                continue;
+            }
+            pending.push(position);
+            while let Some(position) = pending.pop() {
+                self.trace(position, &mut pending, &mut references);
+            }
+        }
+
+        // Só ganham label os destinos que são o começo de uma instrução
+        for position in references {
+            if self.kinds[position] == ByteKind::Code && !self.labels.contains_key(&position) {
+                let label = self.label_name(position);
+                self.labels.insert(position, label);
+            }
+        }
+    }
+
+    fn vector(&self, index: usize) -> u16 {
+        let position = self.resolve(self.banks.len() - 1, VECTORS).unwrap() + index * 2;
+        (self.prg(position + 1) as u16) << 8 | self.prg(position) as u16
+    }
+
+    fn label_name(&self, position: usize) -> String {
+        let bank = self.bank_of(position);
+        if self.banks[bank].fixed {
+            format!("L{:04X}", self.addres_of(position))
+        } else {
+            format!("B{}_{:04X}", bank, self.addres_of(position))
+        }
+    }
+
+    // Segue o código a partir da posição até o fim do caminho, retorna false se ali não
+    // pode começar uma instrução
+    fn trace(
+        &mut self,
+        start: usize,
+        pending: &mut Vec<usize>,
+        references: &mut BTreeSet<usize>,
+    ) -> bool {
+        let mut position = start;
+
+        loop {
+            if self.kinds[position] == ByteKind::Code {
+                return true;
+            }
+            let opcode = self.prg(position);
+            let instruction = Instruction::from(opcode);
+            let size = instruction_size(instruction.addres_mode);
+            let bank = self.banks[self.bank_of(position)];
+
+            // Ilegal, no meio de outra instrução ou passando do fim do banco: é dado
+            if !is_official(opcode)
+                || position + size > bank.start + bank.size
+                || self.kinds[position..position + size]
+                    .iter()
+                    .any(|kind| *kind != ByteKind::Unknown)
+            {
+                return position != start;
+            }
+
+            self.kinds[position] = ByteKind::Code;
+            for kind in &mut self.kinds[position + 1..position + size] {
+                *kind = ByteKind::Operand;
+            }
+
+            let from = self.bank_of(position);
+            if let Some(target) = self.target(position) {
+                if let Some(target) = self.resolve(from, target) {
+                    references.insert(target);
+                    pending.push(target);
+                }
+            }
+
+            if ends_flow(instruction.name) {
+                return true;
+            }
+            position += size;
+        }
+    }
+
+    // Destino de um branch, JMP ou JSR
+    fn target(&self, position: usize) -> Option<u16> {
+        let instruction = Instruction::from(self.prg(position));
+        match instruction.addres_mode {
+            AddressMode::REL => {
+                let offset = self.prg(position + 1) as i8 as u16;
+                Some(
+                    self.addres_of(position)
+                        .wrapping_add(2)
+                        .wrapping_add(offset),
+                )
+            }
+            AddressMode::ABS if matches!(instruction.name, "JMP" | "JSR") => {
+                Some(self.word(position + 1))
+            }
+            _ => None,
+        }
+    }
+
+    fn word(&self, position: usize) -> u16 {
+        (self.prg(position + 1) as u16) << 8 | self.prg(position) as u16
+    }
+
+    // Label de um endereço usado pela instrução na posição, ou o endereço em hexadecimal.
+    // Um endereço do espelho ($8000 no NROM de 16KB) fica em hexadecimal, senão o ca65
+    // montaria o endereço do label
+    fn addres_text(&self, position: usize, addres: u16) -> String {
+        self.resolve(self.bank_of(position), addres)
+            .filter(|target| self.addres_of(*target) == addres)
+            .and_then(|target| self.labels.get(&target))
+            .cloned()
+            .unwrap_or_else(|| format!("${:04X}", addres))
+    }
+
+    /** A instrução na posição no formato do ca65 */
+    fn instruction_text(&self, position: usize) -> String {
+        let opcode = self.prg(position);
+        let instruction = Instruction::from(opcode);
+        let byte = self.prg(position + 1);
+        let word = self.word(position + 1);
+        // Endereços absolutos na zero page precisam do "a:", senão o ca65 usa a zero page
+        let absolute = if word < 0x100 {
+            format!("a:${:04X}", word)
+        } else {
+            self.addres_text(position, word)
+        };
+
+        let operand = match instruction.addres_mode {
+            AddressMode::IMP => match opcode {
+                0x0A | 0x2A | 0x4A | 0x6A => "A".to_string(),
+                _ => String::new(),
+            },
+            AddressMode::IMM => format!("#${:02X}", byte),
+            AddressMode::ZP0 => format!("${:02X}", byte),
+            AddressMode::ZPX => format!("${:02X},X", byte),
+            AddressMode::ZPY => format!("${:02X},Y", byte),
+            AddressMode::ABS => absolute,
+            AddressMode::ABX => format!("{},X", absolute),
+            AddressMode::ABY => format!("{},Y", absolute),
+            AddressMode::IND => format!("(${:04X})", word),
+            AddressMode::IZX => format!("(${:02X},X)", byte),
+            AddressMode::IZY => format!("(${:02X}),Y", byte),
+            AddressMode::REL => {
+                let target = self.target(position).unwrap();
+                self.addres_text(position, target)
+            }
+            _ => String::new(),
+        };
+
+        if operand.is_empty() {
+            instruction.name.to_string()
+        } else {
+            format!("{} {}", instruction.name, operand)
+        }
+    }
+
+    /** O .asm do ca65 com o arquivo inteiro */
+    pub fn listing(&self) -> String {
+        let mut text = String::new();
+        let code = self
+            .kinds
+            .iter()
+            .filter(|kind| **kind == ByteKind::Code)
+            .count();
+        text += "; Gerado pelo disassembler do rust-nes-emulator\n";
+        text += &format!("; {} instruções, {} labels\n\n", code, self.labels.len());
+
+        text += "; Header iNES\n";
+        text += &byte_lines(&self.rom[..HEADER_SIZE], None);
+        if self.prg_start > HEADER_SIZE {
+            text += "\n; Trainer\n";
+            text += &byte_lines(&self.rom[HEADER_SIZE..self.prg_start], None);
+        }
+
+        for (index, bank) in self.banks.iter().enumerate() {
+            text += &format!(
+                "\n; Banco {} da PRG ({}KB){}\n.org ${:04X}\n",
+                index,
+                bank.size / 1024,
+                if bank.fixed { ", fixo" } else { "" },
+                bank.base
+            );
+            text += &self.bank_listing(bank);
+        }
+
+        let rest = &self.rom[self.prg_start + self.prg_size..];
+        if !rest.is_empty() {
+            text += "\n; CHR ROM\n";
+            text += &byte_lines(rest, None);
+        }
+        text
+    }
+
+    fn bank_listing(&self, bank: &PrgBank) -> String {
+        let mut text = String::new();
+        let mut position = bank.start;
+        let end = bank.start + bank.size;
+
+        while position < end {
+            let addres = self.addres_of(position);
+            if let Some(label) = self.labels.get(&position) {
+                text += &format!("{}:\n", label);
+            }
+
+            match self.kinds[position] {
+                ByteKind::Code => {
+                    let size = instruction_size(Instruction::from(self.prg(position)).addres_mode);
+                    text += &format!(
+                        "    {:<28}; ${:04X}\n",
+                        self.instruction_text(position),
+                        addres
+                    );
+                    position += size;
+                }
+                ByteKind::Vector => {
+                    let vectors: Vec<String> = (0..3)
+                        .map(|index| self.addres_text(position, self.vector(index)))
+                        .collect();
+                    text += &format!("    .word {}\n", vectors.join(", "));
+                    position += 6;
+                }
+                _ => {
+                    // Dados até a próxima instrução, label ou vetor
+                    let mut data_end = position + 1;
+                    while data_end < end
+                        && data_end - position < BYTES_PER_LINE
+                        && self.kinds[data_end] == self.kinds[position]
+                        && !self.labels.contains_key(&data_end)
+                    {
+                        data_end += 1;
+                    }
+                    let start = self.prg_start;
+                    text +=
+                        &byte_lines(&self.rom[start + position..start + data_end], Some(addres));
+                    position = data_end;
+                }
+            }
+        }
+        text
+    }
+
+    /** Salva o .asm e o .cfg do ld65 com o mesmo nome */
+    pub fn save(&self, file_name: &str) -> Result<(), String> {
+        fs::write(file_name, self.listing()).map_err(|e| format!("{}: {}", file_name, e))?;
+
+        let config = Path::new(file_name).with_extension("cfg");
+        let text = format!(
+            "MEMORY {{\n    ROM: start = $0, size = ${:X}, file = %O, fill = no;\n}}\n\
+             SEGMENTS {{\n    CODE: load = ROM, type = ro;\n}}\n",
+            self.rom.len()
+        );
+        fs::write(&config, text).map_err(|e| format!("{}: {}", config.display(), e))
+    }
+}
+
+// Bytes em linhas de ".byte", com o endereço da primeira em comentário
+fn byte_lines(bytes: &[u8], addres: Option<u16>) -> String {
+    let mut text = String::new();
+    for (line, chunk) in bytes.chunks(BYTES_PER_LINE).enumerate() {
+        let values: Vec<String> = chunk.iter().map(|byte| format!("${:02X}", byte)).collect();
+        let values = values.join(",");
+        text += &match addres {
+            Some(addres) => format!(
+                "    .byte {:<64}; ${:04X}\n",
+                values,
+                addres.wrapping_add((line * BYTES_PER_LINE) as u16)
+            ),
+            None => format!("    .byte {}\n", values),
+        };
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PPU_TEST_ROM: &str = "tests/roms/ppu_test.nes";
+
+    // Valor de "$XX", "$XXXX", "a:$XXXX" ou de um label
+    fn value(text: &str, labels: &HashMap<String, u16>) -> u16 {
+        let text = text.trim_start_matches("a:");
+        match text.strip_prefix('$') {
+            Some(hex) => u16::from_str_radix(hex, 16).unwrap(),
+            None => *labels
+                .get(text)
+                .unwrap_or_else(|| panic!("unknown label: {}", text)),
+        }
+    }
+
+    // Modo de endereçamento pela sintaxe do operando. Como no ca65, um endereço abaixo de
+    // $100 usa a zero page, a não ser com o "a:" na frente
+    fn operand_mode(
+        name: &str,
+        operand: &str,
+        labels: &HashMap<String, u16>,
+    ) -> (AddressMode, String) {
+        let indexed = |base: &str, zero_page, absolute| {
+            let known = base.starts_with('$') || labels.contains_key(base);
+            let mode = if known && value(base, labels) < 0x100 {
+                zero_page
+            } else {
+                absolute
+            };
+            (mode, base.to_string())
+        };
+        let inner = operand.strip_prefix('(');
+
+        if operand.is_empty() || operand == "A" {
+            (AddressMode::IMP, String::new())
+        } else if name.starts_with('B') && !matches!(name, "BIT" | "BRK") {
+            (AddressMode::REL, operand.to_string())
+        } else if let Some(byte) = operand.strip_prefix('#') {
+            (AddressMode::IMM, byte.to_string())
+        } else if let Some(base) = inner.and_then(|inner| inner.strip_suffix(",X)")) {
+            (AddressMode::IZX, base.to_string())
+        } else if let Some(base) = inner.and_then(|inner| inner.strip_suffix("),Y")) {
+            (AddressMode::IZY, base.to_string())
+        } else if let Some(base) = inner.and_then(|inner| inner.strip_suffix(')')) {
+            (AddressMode::IND, base.to_string())
+        } else if let Some(base) = operand.strip_suffix(",X") {
+            indexed(base, AddressMode::ZPX, AddressMode::ABX)
+        } else if let Some(base) = operand.strip_suffix(",Y") {
+            indexed(base, AddressMode::ZPY, AddressMode::ABY)
+        } else {
+            indexed(operand, AddressMode::ZP0, AddressMode::ABS)
+        }
+    }
+
+    // Monta o .asm do listing de volta em bytes: a primeira passada acha os endereços dos
+    // labels e a segunda codifica cada linha com a tabela de opcodes da CPU
+    fn assemble(listing: &str) -> Vec<u8> {
+        let mut labels = HashMap::new();
+        let mut bytes = vec![];
+
+        for pass in 0..2 {
+            let mut pc: u16 = 0;
+            bytes.clear();
+
+            for line in listing.lines() {
+                let line = line.split(';').next().unwrap().trim();
+                if line.is_empty() {
+                    continue;
+                }
+
+                if let Some(label) = line.strip_suffix(':') {
+                    labels.insert(label.to_string(), pc);
+                    continue;
+                }
+
+                let (name, operand) = match line.split_once(' ') {
+                    Some((name, operand)) => (name, operand.trim()),
+                    None => (line, ""),
+                };
+                let start = bytes.len();
+                match name {
+                    ".org" => {
+                        pc = value(operand, &labels);
+                        continue;
+                    }
+                    ".byte" => {
+                        bytes.extend(operand.split(',').map(|byte| value(byte, &labels) as u8))
+                    }
+                    ".word" => {
+                        for word in operand.split(", ") {
+                            // Na primeira passada um label ainda pode não existir
+                            let word = if pass == 0 { 0 } else { value(word, &labels) };
+                            bytes.extend_from_slice(&word.to_le_bytes());
+                        }
+                    }
+                    _ => {
+                        let (mode, argument) = operand_mode(name, operand, &labels);
+                        let opcode = (0..=255u8)
+                            .find(|opcode| {
+                                let instruction = Instruction::from(*opcode);
+                                is_official(*opcode)
+                                    && instruction.name == name
+                                    && instruction.addres_mode == mode
+                            })
+                            .unwrap_or_else(|| panic!("no opcode for: {}", line));
+                        bytes.push(opcode);
+
+                        let argument = if pass == 0 || argument.is_empty() {
+                            0
+                        } else {
+                            value(&argument, &labels)
+                        };
+                        match instruction_size(mode) {
+                            2 if mode == AddressMode::REL => {
+                                let offset = argument.wrapping_sub(pc.wrapping_add(2)) as i16;
+                                assert!(pass == 0 || (-128..=127).contains(&offset), "{}", line);
+                                bytes.push(offset as u8);
+                            }
+                            2 => bytes.push(argument as u8),
+                            3 => bytes.extend_from_slice(&argument.to_le_bytes()),
+                            _ => {}
+                        }
+                    }
+                }
+                pc = pc.wrapping_add((bytes.len() - start) as u16);
+            }
+        }
+        bytes
+    }
+
+    #[test]
+    fn reassembles_ppu_test_rom() {
+        let rom = fs::read(PPU_TEST_ROM).unwrap();
+        let disassembler = Disassembler::from_file(PPU_TEST_ROM, None).unwrap();
+        let listing = disassembler.listing();
+
+        // O programa tem que sair como instruções e labels, não só como .byte
+        let instructions = listing
+            .lines()
+            .filter(|line| line.starts_with("    ") && !line.trim_start().starts_with('.'))
+            .count();
+        assert!(instructions > 100, "only {} instructions", instructions);
+        assert!(!disassembler.labels.is_empty());
+
+        let bytes = assemble(&listing);
+        assert_eq!(bytes.len(), rom.len());
+        if let Some(position) = (0..rom.len()).find(|i| bytes[*i] != rom[*i]) {
+            panic!(
+                "byte {:#06X} is ${:02X}, expected ${:02X}",
+                position, bytes[position], rom[position]
+            );
+        }
+    }
+}
@@ -0,0 +1,458 @@
+use super::Symbols;
+use crate::cpu::{Cpu6502, Flags6502};
+
+// Expressões das condições ======================================
+// Uma linguagem pequena para as condições dos breakpoints, ex:
+//   A == $10 && [$0300] != 0
+//   X >= 8 || SCANLINE > 200
+//   (P & $80) != 0 && Z
+// Os valores são os registradores (A, X, Y, SP, PC, P), as flags (C, Z, I,
+// D, B, V, N), a posição da PPU (SCANLINE, CYCLE), números em decimal ou
+// hexadecimal ($FF ou 0xFF) e bytes da memória da CPU ([endereço]). Os
+// operadores seguem a precedência do C. Qualquer resultado diferente de 0
+// é verdadeiro. Os nomes dos símbolos carregados viram o endereço deles:
+//   [player_x] > $80 && PC == update
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    A,
+    X,
+    Y,
+    Sp,
+    Pc,
+    P,
+    Flag(Flags6502),
+    Scanline,
+    Cycle,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnaryOperator {
+    Not,
+    Negate,
+    Complement,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOperator {
+    Or,
+    And,
+    BitOr,
+    BitXor,
+    BitAnd,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    ShiftLeft,
+    ShiftRight,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+impl BinaryOperator {
+    // Precedência do operador, quanto maior mais cedo é calculado
+    fn precedence(&self) -> u8 {
+        match self {
+            BinaryOperator::Or => 1,
+            BinaryOperator::And => 2,
+            BinaryOperator::BitOr => 3,
+            BinaryOperator::BitXor => 4,
+            BinaryOperator::BitAnd => 5,
+            BinaryOperator::Equal | BinaryOperator::NotEqual => 6,
+            BinaryOperator::Less
+            | BinaryOperator::LessEqual
+            | BinaryOperator::Greater
+            | BinaryOperator::GreaterEqual => 7,
+            BinaryOperator::ShiftLeft | BinaryOperator::ShiftRight => 8,
+            BinaryOperator::Add | BinaryOperator::Subtract => 9,
+            BinaryOperator::Multiply | BinaryOperator::Divide => 10,
+        }
+    }
+
+    fn apply(&self, left: i64, right: i64) -> i64 {
+        match self {
+            BinaryOperator::Or => (left != 0 || right != 0) as i64,
+            BinaryOperator::And => (left != 0 && right != 0) as i64,
+            BinaryOperator::BitOr => left | right,
+            BinaryOperator::BitXor => left ^ right,
+            BinaryOperator::BitAnd => left & right,
+            BinaryOperator::Equal => (left == right) as i64,
+            BinaryOperator::NotEqual => (left != right) as i64,
+            BinaryOperator::Less => (left < right) as i64,
+            BinaryOperator::LessEqual => (left <= right) as i64,
+            BinaryOperator::Greater => (left > right) as i64,
+            BinaryOperator::GreaterEqual => (left >= right) as i64,
+            BinaryOperator::ShiftLeft => left.wrapping_shl(right as u32),
+            BinaryOperator::ShiftRight => left.wrapping_shr(right as u32),
+            BinaryOperator::Add => left.wrapping_add(right),
+            BinaryOperator::Subtract => left.wrapping_sub(right),
+            BinaryOperator::Multiply => left.wrapping_mul(right),
+            // Divisão por zero resulta em 0 em vez de travar o emulador
+            BinaryOperator::Divide => left.checked_div(right).unwrap_or(0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    Number(i64),
+    Value(Value),
+    /** Byte da memória da CPU, lido sem efeitos colaterais */
+    Memory(Box<Expression>),
+    Unary(UnaryOperator, Box<Expression>),
+    Binary(BinaryOperator, Box<Expression>, Box<Expression>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(i64),
+    Name(String),
+    Operator(&'static str),
+}
+
+// Operadores de dois caracteres vêm antes para "<=" não virar "<" e "="
+const OPERATORS: [&str; 23] = [
+    "||", "&&", "==", "!=", "<=", ">=", "<<", ">>", "<", ">", "+", "-", "*", "/", "&", "|", "^",
+    "!", "~", "(", ")", "[", "]",
+];
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        if c == '$' || c.is_ascii_digit() {
+            let (radix, start) = if c == '$' {
+                (16, i + 1)
+            } else if c == '0' && chars.get(i + 1).is_some_and(|x| *x == 'x' || *x == 'X') {
+                (16, i + 2)
+            } else {
+                (10, i)
+            };
+            let mut end = start;
+            while end < chars.len() && chars[end].is_digit(radix) {
+                end += 1;
+            }
+            let digits: String = chars[start..end].iter().collect();
+            let number = i64::from_str_radix(&digits, radix)
+                .map_err(|_| format!("invalid number at {}", i + 1))?;
+            tokens.push(Token::Number(number));
+            i = end;
+            continue;
+        }
+
+        if c.is_ascii_alphabetic() || c == '_' {
+            let mut end = i;
+            while end < chars.len() && (chars[end].is_ascii_alphanumeric() || chars[end] == '_') {
+                end += 1;
+            }
+            let name: String = chars[i..end].iter().collect();
+            tokens.push(Token::Name(name));
+            i = end;
+            continue;
+        }
+
+        let rest: String = chars[i..].iter().take(2).collect();
+        match OPERATORS
+            .iter()
+            .find(|operator| rest.starts_with(*operator))
+        {
+            Some(operator) => {
+                tokens.push(Token::Operator(operator));
+                i += operator.len();
+            }
+            None => return Err(format!("unexpected '{}' at {}", c, i + 1)),
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn binary_operator(token: &Token) -> Option<BinaryOperator> {
+    let operator = match token {
+        Token::Operator(operator) => *operator,
+        _ => return None,
+    };
+    let operator = match operator {
+        "||" => BinaryOperator::Or,
+        "&&" => BinaryOperator::And,
+        "|" => BinaryOperator::BitOr,
+        "^" => BinaryOperator::BitXor,
+        "&" => BinaryOperator::BitAnd,
+        "==" => BinaryOperator::Equal,
+        "!=" => BinaryOperator::NotEqual,
+        "<" => BinaryOperator::Less,
+        "<=" => BinaryOperator::LessEqual,
+        ">" => BinaryOperator::Greater,
+        ">=" => BinaryOperator::GreaterEqual,
+        "<<" => BinaryOperator::ShiftLeft,
+        ">>" => BinaryOperator::ShiftRight,
+        "+" => BinaryOperator::Add,
+        "-" => BinaryOperator::Subtract,
+        "*" => BinaryOperator::Multiply,
+        "/" => BinaryOperator::Divide,
+        _ => return None,
+    };
+    Some(operator)
+}
+
+fn value(name: &str) -> Option<Value> {
+    let value = match name {
+        "A" => Value::A,
+        "X" => Value::X,
+        "Y" => Value::Y,
+        "SP" | "S" => Value::Sp,
+        "PC" => Value::Pc,
+        "P" => Value::P,
+        "C" => Value::Flag(Flags6502::C),
+        "Z" => Value::Flag(Flags6502::Z),
+        "I" => Value::Flag(Flags6502::I),
+        "D" => Value::Flag(Flags6502::D),
+        "B" => Value::Flag(Flags6502::B),
+        "V" => Value::Flag(Flags6502::V),
+        "N" => Value::Flag(Flags6502::N),
+        "SCANLINE" => Value::Scanline,
+        "CYCLE" => Value::Cycle,
+        _ => return None,
+    };
+    Some(value)
+}
+
+// Parser por precedência: cada chamada junta os operadores com precedência >= "min"
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    symbols: &'a Symbols,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn expect(&mut self, operator: &str) -> Result<(), String> {
+        match self.next() {
+            Some(Token::Operator(found)) if found == operator => Ok(()),
+            _ => Err(format!("expected '{}'", operator)),
+        }
+    }
+
+    fn parse_binary(&mut self, min: u8) -> Result<Expression, String> {
+        let mut left = self.parse_unary()?;
+
+        while let Some(operator) = self.peek().and_then(binary_operator) {
+            if operator.precedence() < min {
+                break;
+            }
+            self.position += 1;
+            let right = self.parse_binary(operator.precedence() + 1)?;
+            left = Expression::Binary(operator, Box::new(left), Box::new(right));
+        }
+
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expression, String> {
+        match self.next() {
+            Some(Token::Number(number)) => Ok(Expression::Number(number)),
+            // Os registradores têm preferência sobre os símbolos com o mesmo nome
+            Some(Token::Name(name)) => {
+                match (value(&name.to_uppercase()), self.symbols.addres(&name)) {
+                    (Some(value), _) => Ok(Expression::Value(value)),
+                    (None, Some(addres)) => Ok(Expression::Number(addres as i64)),
+                    (None, None) => Err(format!("unknown value '{}'", name)),
+                }
+            }
+            Some(Token::Operator("(")) => {
+                let expression = self.parse_binary(0)?;
+                self.expect(")")?;
+                Ok(expression)
+            }
+            Some(Token::Operator("[")) => {
+                let addres = self.parse_binary(0)?;
+                self.expect("]")?;
+                Ok(Expression::Memory(Box::new(addres)))
+            }
+            Some(Token::Operator(operator)) => {
+                let operator = match operator {
+                    "!" => UnaryOperator::Not,
+                    "-" => UnaryOperator::Negate,
+                    "~" => UnaryOperator::Complement,
+                    _ => return Err(format!("unexpected '{}'", operator)),
+                };
+                let expression = self.parse_unary()?;
+                Ok(Expression::Unary(operator, Box::new(expression)))
+            }
+            None => Err("unexpected end of expression".to_string()),
+        }
+    }
+}
+
+impl Expression {
+    pub fn parse(text: &str, symbols: &Symbols) -> Result<Expression, String> {
+        let mut parser = Parser {
+            tokens: tokenize(text)?,
+            position: 0,
+            symbols,
+        };
+        let expression = parser.parse_binary(0)?;
+
+        if parser.position < parser.tokens.len() {
+            return Err(format!("unexpected {:?}", parser.tokens[parser.position]));
+        }
+        Ok(expression)
+    }
+
+    pub fn evaluate(&self, cpu: &mut Cpu6502) -> i64 {
+        match self {
+            Expression::Number(number) => *number,
+            Expression::Value(value) => match value {
+                Value::A => cpu.a as i64,
+                Value::X => cpu.x as i64,
+                Value::Y => cpu.y as i64,
+                Value::Sp => cpu.stkp as i64,
+                Value::Pc => cpu.pc as i64,
+                Value::P => cpu.status as i64,
+                Value::Flag(flag) => cpu.get_flag(*flag) as i64,
+                Value::Scanline => cpu.bus.ppu.scanline as i64,
+                Value::Cycle => cpu.bus.ppu.cycle as i64,
+            },
+            Expression::Memory(addres) => {
+                let addres = addres.evaluate(cpu) as u16;
+                cpu.bus_read(addres, true) as i64
+            }
+            Expression::Unary(operator, expression) => {
+                let value = expression.evaluate(cpu);
+                match operator {
+                    UnaryOperator::Not => (value == 0) as i64,
+                    UnaryOperator::Negate => value.wrapping_neg(),
+                    UnaryOperator::Complement => !value,
+                }
+            }
+            Expression::Binary(operator, left, right) => {
+                let left = left.evaluate(cpu);
+                let right = right.evaluate(cpu);
+                operator.apply(left, right)
+            }
+        }
+    }
+
+    pub fn is_true(&self, cpu: &mut Cpu6502) -> bool {
+        self.evaluate(cpu) != 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::Bus;
+    use crate::cartridge::Cartridge;
+
+    fn cpu() -> Cpu6502 {
+        Cpu6502::new_with_bus(Bus::new(Cartridge::flat(0x0000, &[])))
+    }
+
+    fn evaluate(text: &str, symbols: &Symbols, cpu: &mut Cpu6502) -> i64 {
+        Expression::parse(text, symbols).unwrap().evaluate(cpu)
+    }
+
+    #[test]
+    fn precedence() {
+        let symbols = Symbols::new();
+        let mut cpu = cpu();
+        let cases = [
+            ("1 + 2 * 3", 7),
+            ("(1 + 2) * 3", 9),
+            ("10 - 4 - 3", 3),
+            ("1 << 2 + 1", 8),
+            ("1 | 2 & 3", 3),
+            ("6 ^ 3 | 8", 13),
+            ("2 < 3 == 1", 1),
+            ("1 + 1 == 2 && 0 || 1", 1),
+            ("0 && 1 || 0", 0),
+            ("-1 + 3", 2),
+            ("!0 + 1", 2),
+            ("~0 & $FF", 255),
+            ("$10 + 0x10 + 16", 48),
+            ("8 / 0", 0),
+        ];
+        for (text, expected) in cases.iter() {
+            assert_eq!(evaluate(text, &symbols, &mut cpu), *expected, "{}", text);
+        }
+    }
+
+    #[test]
+    fn memory_and_registers() {
+        let symbols = Symbols::new();
+        let mut cpu = cpu();
+        cpu.bus.write(0x0300, 0x42);
+        cpu.bus.write(0x0010, 0x20);
+        cpu.bus.write(0x0020, 0x07);
+        cpu.a = 0x10;
+        cpu.x = 3;
+        cpu.status = Flags6502::Z as u8;
+
+        assert_eq!(evaluate("[$0300]", &symbols, &mut cpu), 0x42);
+        assert_eq!(evaluate("[$0200 + $100] == $42", &symbols, &mut cpu), 1);
+        assert_eq!(evaluate("[[$10]]", &symbols, &mut cpu), 7);
+        assert_eq!(evaluate("A == $10 && X >= 3", &symbols, &mut cpu), 1);
+        assert_eq!(evaluate("a + x", &symbols, &mut cpu), 0x13);
+        assert_eq!(evaluate("Z && !C", &symbols, &mut cpu), 1);
+        assert_eq!(evaluate("(P & $02) != 0", &symbols, &mut cpu), 1);
+    }
+
+    #[test]
+    fn registers_before_symbols() {
+        let mut symbols = Symbols::new();
+        symbols.add_cpu(0x0300, "X");
+        symbols.add_cpu(0x0301, "player_x");
+        let mut cpu = cpu();
+        cpu.x = 5;
+        cpu.bus.write(0x0301, 0x80);
+
+        assert_eq!(evaluate("X", &symbols, &mut cpu), 5);
+        assert_eq!(evaluate("player_x", &symbols, &mut cpu), 0x0301);
+        assert_eq!(evaluate("[player_x]", &symbols, &mut cpu), 0x80);
+    }
+
+    #[test]
+    fn errors() {
+        let symbols = Symbols::new();
+        let cases = [
+            ("A +", "unexpected end of expression"),
+            ("(A", "expected ')'"),
+            ("[1", "expected ']'"),
+            ("foo", "unknown value 'foo'"),
+            ("A # 1", "unexpected '#' at 3"),
+            ("$", "invalid number at 1"),
+            ("1 2", "unexpected Number(2)"),
+            (") 1", "unexpected ')'"),
+        ];
+        for (text, error) in cases.iter() {
+            assert_eq!(
+                Expression::parse(text, &symbols),
+                Err(error.to_string()),
+                "{}",
+                text
+            );
+        }
+    }
+}
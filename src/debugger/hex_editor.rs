@@ -0,0 +1,360 @@
+use crate::cpu::Cpu6502;
+
+// Editor hexadecimal ===========================================
+// Mostra e edita os bytes de qualquer memória do console. Cada espaço
+// é lido e escrito direto na memória dele, sem efeitos colaterais, menos
+// o barramento da CPU (e a janela da PRG-RAM) que escreve pelo barramento
+// como a CPU faria, então escrever em $2007 ou $4014 funciona de verdade.
+// Os bytes que o jogo muda ficam destacados por HIGHLIGHT_FRAMES frames,
+// comparando o espaço inteiro a cada atualização.
+
+const HIGHLIGHT_FRAMES: u8 = 60;
+pub const HEX_EDITOR_COLUMNS: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MemorySpace {
+    CpuBus,
+    Ram,
+    PpuBus,
+    NameTables,
+    Palette,
+    Oam,
+    PrgRom,
+    Chr,
+    PrgRam,
+}
+
+pub const MEMORY_SPACES: [MemorySpace; 9] = [
+    MemorySpace::CpuBus,
+    MemorySpace::Ram,
+    MemorySpace::PpuBus,
+    MemorySpace::NameTables,
+    MemorySpace::Palette,
+    MemorySpace::Oam,
+    MemorySpace::PrgRom,
+    MemorySpace::Chr,
+    MemorySpace::PrgRam,
+];
+
+impl MemorySpace {
+    /** Nome usado nos comandos */
+    pub fn name(&self) -> &'static str {
+        match self {
+            MemorySpace::CpuBus => "cpu",
+            MemorySpace::Ram => "ram",
+            MemorySpace::PpuBus => "ppu",
+            MemorySpace::NameTables => "nt",
+            MemorySpace::Palette => "pal",
+            MemorySpace::Oam => "oam",
+            MemorySpace::PrgRom => "prg",
+            MemorySpace::Chr => "chr",
+            MemorySpace::PrgRam => "prgram",
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            MemorySpace::CpuBus => "CPU BUS",
+            MemorySpace::Ram => "RAM",
+            MemorySpace::PpuBus => "PPU BUS",
+            MemorySpace::NameTables => "NAMETABLES",
+            MemorySpace::Palette => "PALETTE",
+            MemorySpace::Oam => "OAM",
+            MemorySpace::PrgRom => "PRG-ROM",
+            MemorySpace::Chr => "CHR",
+            MemorySpace::PrgRam => "PRG-RAM ($6000)",
+        }
+    }
+
+    pub fn from(name: &str) -> Option<MemorySpace> {
+        MEMORY_SPACES
+            .iter()
+            .find(|space| space.name() == name)
+            .copied()
+    }
+
+    pub fn next(&self) -> MemorySpace {
+        let index = MEMORY_SPACES
+            .iter()
+            .position(|space| space == self)
+            .unwrap();
+        MEMORY_SPACES[(index + 1) % MEMORY_SPACES.len()]
+    }
+}
+
+// Acesso aos espaços de memória
+impl Cpu6502 {
+    pub fn space_size(&self, space: MemorySpace) -> usize {
+        match space {
+            MemorySpace::CpuBus => 0x10000,
+            MemorySpace::Ram => self.bus.ram.len(),
+            MemorySpace::PpuBus => 0x4000,
+            MemorySpace::NameTables => 2048,
+            MemorySpace::Palette => 32,
+            MemorySpace::Oam => 256,
+            MemorySpace::PrgRom => self.bus.prg_rom.prg_memory.len(),
+            MemorySpace::Chr => self.bus.ppu.chr_rom.chr_memory.len(),
+            MemorySpace::PrgRam => 0x2000,
+        }
+    }
+
+    /** Lê um byte do espaço sem efeitos colaterais */
+    pub fn peek_space(&mut self, space: MemorySpace, addres: usize) -> u8 {
+        let ppu = &mut self.bus.ppu;
+        match space {
+            MemorySpace::CpuBus => self.bus.read(addres as u16, true),
+            MemorySpace::Ram => self.bus.ram[addres & 0x07FF],
+            MemorySpace::PpuBus => ppu.ppu_read(addres as u16 & 0x3FFF),
+            MemorySpace::NameTables => ppu.table_name[(addres >> 10) & 1][addres & 0x03FF],
+            MemorySpace::Palette => ppu.table_palette[addres & 0x1F],
+            MemorySpace::Oam => ppu.oam_read(addres as u8),
+            MemorySpace::PrgRom => self.bus.prg_rom.prg_memory[addres],
+            MemorySpace::Chr => ppu.chr_rom.chr_memory[addres],
+            MemorySpace::PrgRam => self.bus.read(0x6000 + (addres as u16 & 0x1FFF), true),
+        }
+    }
+
+    pub fn poke_space(&mut self, space: MemorySpace, addres: usize, data: u8) {
+        let ppu = &mut self.bus.ppu;
+        match space {
+            MemorySpace::CpuBus => self.bus.write(addres as u16, data),
+            MemorySpace::Ram => self.bus.ram[addres & 0x07FF] = data,
+            MemorySpace::PpuBus => ppu.ppu_write(addres as u16 & 0x3FFF, data),
+            MemorySpace::NameTables => ppu.table_name[(addres >> 10) & 1][addres & 0x03FF] = data,
+            MemorySpace::Palette => ppu.table_palette[addres & 0x1F] = data & 0x3F,
+            MemorySpace::Oam => ppu.oam_write(addres as u8, data),
+            MemorySpace::PrgRom => self.bus.prg_rom.prg_memory[addres] = data,
+            MemorySpace::Chr => ppu.chr_rom.chr_memory[addres] = data,
+            MemorySpace::PrgRam => self.bus.write(0x6000 + (addres as u16 & 0x1FFF), data),
+        }
+    }
+}
+
+/** Bytes em hexadecimal separados por espaço ("A9 00 8D") ou juntos ("A9008D") */
+pub fn parse_bytes(text: &str) -> Result<Vec<u8>, String> {
+    let digits: String = text
+        .split_whitespace()
+        .map(|word| word.trim_start_matches('$'))
+        .map(|word| {
+            if word.len() % 2 == 1 {
+                format!("0{}", word)
+            } else {
+                word.to_string()
+            }
+        })
+        .collect();
+    if digits.is_empty() {
+        return Err("no bytes".to_string());
+    }
+    // Antes de cortar de 2 em 2, um caractere de mais de um byte (UTF-8) quebraria o corte
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("invalid bytes: {}", text));
+    }
+
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&digits[i..i + 2], 16)
+                .map_err(|_| format!("invalid bytes: {}", text))
+        })
+        .collect()
+}
+
+/** Texto que está sendo digitado no editor */
+#[derive(Debug, Clone, PartialEq)]
+pub enum HexInput {
+    None,
+    Goto(String),
+    Search(String),
+}
+
+pub struct HexEditor {
+    pub space: MemorySpace,
+    /** Byte selecionado */
+    pub cursor: usize,
+    /** Primeira linha mostrada */
+    pub top: usize,
+    pub rows: usize,
+    /** Primeiro dígito do byte que está sendo editado */
+    pub nibble: Option<u8>,
+    pub input: HexInput,
+    pub message: String,
+    snapshot: Vec<u8>,
+    ages: Vec<u8>,
+}
+
+impl HexEditor {
+    pub fn new(rows: usize) -> HexEditor {
+        HexEditor {
+            space: MemorySpace::CpuBus,
+            cursor: 0,
+            top: 0,
+            rows,
+            nibble: None,
+            input: HexInput::None,
+            message: String::new(),
+            snapshot: vec![],
+            ages: vec![],
+        }
+    }
+
+    pub fn set_space(&mut self, space: MemorySpace) {
+        self.space = space;
+        self.cursor = 0;
+        self.top = 0;
+        self.nibble = None;
+        self.snapshot.clear();
+        self.ages.clear();
+    }
+
+    /** Compara o espaço com a última atualização, os bytes diferentes ganham destaque */
+    pub fn update(&mut self, cpu: &mut Cpu6502) {
+        let size = cpu.space_size(self.space);
+        if self.snapshot.len() != size {
+            self.snapshot = (0..size).map(|i| cpu.peek_space(self.space, i)).collect();
+            self.ages = vec![0; size];
+            return;
+        }
+
+        for i in 0..size {
+            let data = cpu.peek_space(self.space, i);
+            if data != self.snapshot[i] {
+                self.snapshot[i] = data;
+                self.ages[i] = HIGHLIGHT_FRAMES;
+            } else if self.ages[i] > 0 {
+                self.ages[i] -= 1;
+            }
+        }
+    }
+
+    /** Destaque do byte, de 1.0 (acabou de mudar) até 0.0 */
+    pub fn highlight(&self, addres: usize) -> f32 {
+        self.ages
+            .get(addres)
+            .map_or(0.0, |age| *age as f32 / HIGHLIGHT_FRAMES as f32)
+    }
+
+    /** Endereço do começo de cada linha visível */
+    pub fn visible_rows(&self, cpu: &Cpu6502) -> Vec<usize> {
+        let size = cpu.space_size(self.space);
+        (self.top..self.top + self.rows)
+            .map(|row| row * HEX_EDITOR_COLUMNS)
+            .filter(|addres| *addres < size)
+            .collect()
+    }
+
+    pub fn goto(&mut self, addres: usize, cpu: &Cpu6502) {
+        let size = cpu.space_size(self.space);
+        if size == 0 {
+            return;
+        }
+        self.cursor = addres.min(size - 1);
+        self.nibble = None;
+        self.scroll_to_cursor();
+    }
+
+    /** Move o cursor "delta" bytes, parando nas pontas */
+    pub fn move_cursor(&mut self, delta: isize, cpu: &Cpu6502) {
+        let addres = (self.cursor as isize + delta).max(0) as usize;
+        self.goto(addres, cpu);
+    }
+
+    // Rola só o necessário para o cursor aparecer
+    fn scroll_to_cursor(&mut self) {
+        let row = self.cursor / HEX_EDITOR_COLUMNS;
+        if row < self.top {
+            self.top = row;
+        } else if row >= self.top + self.rows {
+            self.top = row + 1 - self.rows;
+        }
+    }
+
+    /** Digita um dígito hexadecimal no byte do cursor, no segundo dígito o byte é escrito
+    e o cursor vai para o próximo */
+    pub fn type_digit(&mut self, digit: u8, cpu: &mut Cpu6502) {
+        match self.nibble.take() {
+            None => self.nibble = Some(digit),
+            Some(high) => {
+                self.write(self.cursor, &[high << 4 | digit], cpu);
+                self.move_cursor(1, cpu);
+            }
+        }
+    }
+
+    /** Escreve os bytes a partir do endereço, sem destacar como mudança do jogo */
+    pub fn write(&mut self, addres: usize, bytes: &[u8], cpu: &mut Cpu6502) {
+        let size = cpu.space_size(self.space);
+        for (i, data) in bytes.iter().enumerate() {
+            if addres + i >= size {
+                break;
+            }
+            cpu.poke_space(self.space, addres + i, *data);
+            if let Some(snapshot) = self.snapshot.get_mut(addres + i) {
+                *snapshot = cpu.peek_space(self.space, addres + i);
+            }
+        }
+    }
+
+    /** Procura os bytes depois do cursor, voltando para o começo no fim do espaço */
+    pub fn find(&mut self, pattern: &[u8], cpu: &mut Cpu6502) -> Option<usize> {
+        let size = cpu.space_size(self.space);
+        if pattern.is_empty() || pattern.len() > size {
+            return None;
+        }
+        let data: Vec<u8> = (0..size).map(|i| cpu.peek_space(self.space, i)).collect();
+        let found = (1..=size)
+            .map(|offset| (self.cursor + offset) % size)
+            .find(|start| {
+                *start + pattern.len() <= size && data[*start..*start + pattern.len()] == *pattern
+            })?;
+        self.goto(found, cpu);
+        Some(found)
+    }
+
+    /** Confirma o texto digitado (endereço ou bytes para procurar) */
+    pub fn submit(&mut self, cpu: &mut Cpu6502) {
+        let input = std::mem::replace(&mut self.input, HexInput::None);
+        self.message = match input {
+            HexInput::None => return,
+            HexInput::Goto(text) => match usize::from_str_radix(text.trim_start_matches('$'), 16) {
+                Ok(addres) if addres < cpu.space_size(self.space) => {
+                    self.goto(addres, cpu);
+                    format!("${:04X}", addres)
+                }
+                _ => format!("invalid address: {}", text),
+            },
+            HexInput::Search(text) => match parse_bytes(&text) {
+                Ok(pattern) => match self.find(&pattern, cpu) {
+                    Some(addres) => format!("found at ${:04X}", addres),
+                    None => format!("not found: {}", text),
+                },
+                Err(e) => e,
+            },
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_bytes_formats() {
+        assert_eq!(parse_bytes("A9 00 8D"), Ok(vec![0xA9, 0x00, 0x8D]));
+        assert_eq!(parse_bytes("a9008d"), Ok(vec![0xA9, 0x00, 0x8D]));
+        assert_eq!(parse_bytes("$A9 $0 f"), Ok(vec![0xA9, 0x00, 0x0F]));
+    }
+
+    #[test]
+    fn parse_bytes_errors() {
+        assert_eq!(parse_bytes("  "), Err("no bytes".to_string()));
+        assert_eq!(
+            parse_bytes("A9 G0"),
+            Err("invalid bytes: A9 G0".to_string())
+        );
+        // Não pode entrar em pânico cortando no meio de um caractere
+        assert_eq!(parse_bytes("éa"), Err("invalid bytes: éa".to_string()));
+        assert_eq!(parse_bytes("A9 é"), Err("invalid bytes: A9 é".to_string()));
+        assert_eq!(parse_bytes("+1"), Err("invalid bytes: +1".to_string()));
+    }
+}
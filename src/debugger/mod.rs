@@ -0,0 +1,19 @@
+mod breakpoint;
+mod cdl;
+mod disassembler;
+mod expression;
+mod hex_editor;
+mod session;
+mod symbols;
+mod terminal;
+mod trace;
+
+pub use breakpoint::*;
+pub use cdl::*;
+pub use disassembler::*;
+pub use expression::*;
+pub use hex_editor::*;
+pub use session::*;
+pub use symbols::*;
+pub use terminal::*;
+pub use trace::*;
@@ -0,0 +1,305 @@
+use super::{AddressSpace, Breakpoint, BreakpointKind, Symbols};
+use crate::cpu::{BusActivity, Cpu6502};
+
+// Debugger =====================================================
+// O debugger é consultado depois de cada clock do sistema (um ciclo
+// da PPU). Os watchpoints usam o registro de acessos ao barramento da
+// CPU (bus_log) e o registro de acessos da CPU à memória da PPU pelo
+// $2007 (data_log), os dois só ficam ligados enquanto existe algum
+// watchpoint. Os breakpoints de execução, os passos e as interrupções
+// são verificados no fim de cada instrução, quando o PC já aponta para
+// a próxima.
+
+// Opcodes que voltam de uma sub-rotina
+const RTS: u8 = 0x60;
+const RTI: u8 = 0x40;
+const JSR: u8 = 0x20;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StepMode {
+    Run,
+    /** Para na próxima instrução */
+    Into,
+    /** Para quando a CPU voltar para "pc" com a stack em "sp" (depois de um JSR) */
+    Over {
+        pc: u16,
+        sp: u8,
+    },
+    /** Para depois do RTS/RTI que tirar a stack de "sp" */
+    Out {
+        sp: u8,
+    },
+    /** Para no começo do scanline */
+    Scanline(i16),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BreakReason {
+    Breakpoint(usize),
+    Watchpoint(usize, BusActivity),
+    Step,
+    Scanline(i16),
+    Nmi,
+    Irq,
+}
+
+pub struct Debugger {
+    breakpoints: Vec<Breakpoint>,
+    pub break_on_nmi: bool,
+    pub break_on_irq: bool,
+    pub step: StepMode,
+    /** Motivo da última parada, até a emulação continuar */
+    pub break_reason: Option<BreakReason>,
+    // Existe algum watchpoint ligado na CPU / na PPU
+    watch_cpu: bool,
+    watch_ppu: bool,
+    // clock_count da CPU no último fim de instrução verificado
+    instruction_clock: u32,
+    last_scanline: i16,
+}
+
+impl Debugger {
+    pub fn new() -> Debugger {
+        Debugger {
+            breakpoints: vec![],
+            break_on_nmi: false,
+            break_on_irq: false,
+            step: StepMode::Run,
+            break_reason: None,
+            watch_cpu: false,
+            watch_ppu: false,
+            instruction_clock: 0,
+            last_scanline: 0,
+        }
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    /** Adiciona um breakpoint no formato de breakpoint.rs, retorna o índice dele. Os nomes
+    usados nele são procurados em "symbols" */
+    pub fn add_breakpoint(&mut self, text: &str, symbols: &Symbols) -> Result<usize, String> {
+        self.breakpoints.push(Breakpoint::parse(text, symbols)?);
+        self.update_watches();
+        Ok(self.breakpoints.len() - 1)
+    }
+
+    /** Remove o breakpoint, os índices dos seguintes diminuem em 1 */
+    pub fn remove_breakpoint(&mut self, index: usize) -> bool {
+        if index >= self.breakpoints.len() {
+            return false;
+        }
+        self.breakpoints.remove(index);
+        self.update_watches();
+        true
+    }
+
+    /** Liga/desliga o breakpoint, retorna o novo estado */
+    pub fn toggle_breakpoint(&mut self, index: usize) -> Option<bool> {
+        let breakpoint = self.breakpoints.get_mut(index)?;
+        breakpoint.enabled = !breakpoint.enabled;
+        let enabled = breakpoint.enabled;
+        self.update_watches();
+        Some(enabled)
+    }
+
+    fn update_watches(&mut self) {
+        let breakpoints = &self.breakpoints;
+        let watching = |space: AddressSpace| {
+            breakpoints.iter().any(|breakpoint| {
+                breakpoint.enabled
+                    && breakpoint.space == space
+                    && breakpoint.kind != BreakpointKind::Execute
+            })
+        };
+        self.watch_cpu = watching(AddressSpace::Cpu);
+        self.watch_ppu = watching(AddressSpace::Ppu);
+    }
+
+    /** Sem nada para verificar a emulação não precisa consultar o debugger */
+    pub fn is_active(&self) -> bool {
+        !self.breakpoints.is_empty()
+            || self.break_on_nmi
+            || self.break_on_irq
+            || self.step != StepMode::Run
+    }
+
+    /** Continua a emulação depois de uma parada */
+    pub fn resume(&mut self) {
+        self.break_reason = None;
+    }
+
+    pub fn step_into(&mut self) {
+        self.resume();
+        self.step = StepMode::Into;
+    }
+
+    /** Executa a próxima instrução, um JSR é executado até a sub-rotina voltar */
+    pub fn step_over(&mut self, cpu: &mut Cpu6502) {
+        self.resume();
+        self.step = if cpu.complete() && cpu.bus_read(cpu.pc, true) == JSR {
+            StepMode::Over {
+                pc: cpu.pc.wrapping_add(3),
+                sp: cpu.stkp,
+            }
+        } else {
+            StepMode::Into
+        };
+    }
+
+    /** Executa até a sub-rotina atual voltar */
+    pub fn step_out(&mut self, cpu: &Cpu6502) {
+        self.resume();
+        self.step = StepMode::Out { sp: cpu.stkp };
+    }
+
+    pub fn run_to_scanline(&mut self, scanline: i16) {
+        self.resume();
+        self.step = StepMode::Scanline(scanline);
+    }
+
+    /** Verifica o estado depois de um clock do sistema, retorna o motivo se a emulação
+    deve parar */
+    pub fn check(&mut self, cpu: &mut Cpu6502) -> Option<BreakReason> {
+        let reason = self.find_break(cpu);
+        if reason.is_some() {
+            self.step = StepMode::Run;
+            self.break_reason = reason;
+        }
+        reason
+    }
+
+    fn find_break(&mut self, cpu: &mut Cpu6502) -> Option<BreakReason> {
+        // Os registros só existem enquanto há watchpoints
+        if self.watch_cpu != cpu.bus_log.is_some() {
+            cpu.bus_log = if self.watch_cpu { Some(vec![]) } else { None };
+        }
+        if self.watch_ppu != cpu.bus.ppu.data_log.is_some() {
+            cpu.bus.ppu.data_log = if self.watch_ppu { Some(vec![]) } else { None };
+        }
+
+        let accesses = cpu.bus_log.as_mut().map(std::mem::take);
+        if let Some(reason) = self.check_watchpoints(cpu, AddressSpace::Cpu, accesses) {
+            return Some(reason);
+        }
+        let accesses = cpu.bus.ppu.data_log.as_mut().map(std::mem::take);
+        if let Some(reason) = self.check_watchpoints(cpu, AddressSpace::Ppu, accesses) {
+            return Some(reason);
+        }
+
+        let scanline = cpu.bus.ppu.scanline;
+        let new_scanline = scanline != self.last_scanline;
+        self.last_scanline = scanline;
+        if new_scanline && self.step == StepMode::Scanline(scanline) {
+            return Some(BreakReason::Scanline(scanline));
+        }
+
+        // O resto só é verificado uma vez, no fim de cada instrução
+        if !cpu.complete() || cpu.halted || cpu.clock_count == self.instruction_clock {
+            return None;
+        }
+        self.instruction_clock = cpu.clock_count;
+
+        // A "instrução" que terminou foi a sequência de uma interrupção, o PC já está
+        // no começo do handler
+        if cpu.hardware_interrupt {
+            let nmi = cpu.temp == 0xFFFA;
+            if nmi && self.break_on_nmi {
+                return Some(BreakReason::Nmi);
+            }
+            if !nmi && self.break_on_irq {
+                return Some(BreakReason::Irq);
+            }
+        }
+
+        match self.step {
+            StepMode::Into => return Some(BreakReason::Step),
+            StepMode::Over { pc, sp } if cpu.pc == pc && cpu.stkp >= sp => {
+                return Some(BreakReason::Step)
+            }
+            StepMode::Out { sp }
+                if !cpu.hardware_interrupt
+                    && (cpu.opcode == RTS || cpu.opcode == RTI)
+                    && cpu.stkp > sp =>
+            {
+                return Some(BreakReason::Step)
+            }
+            _ => {}
+        }
+
+        // Se uma interrupção vai ser atendida a próxima instrução não é a do PC
+        if cpu.interrupt_poll {
+            return None;
+        }
+
+        for index in 0..self.breakpoints.len() {
+            let breakpoint = &self.breakpoints[index];
+            if breakpoint.enabled
+                && breakpoint.kind == BreakpointKind::Execute
+                && breakpoint.contains(cpu.pc)
+                && condition_is_true(breakpoint, cpu)
+            {
+                return Some(BreakReason::Breakpoint(index));
+            }
+        }
+
+        None
+    }
+
+    fn check_watchpoints(
+        &self,
+        cpu: &mut Cpu6502,
+        space: AddressSpace,
+        accesses: Option<Vec<BusActivity>>,
+    ) -> Option<BreakReason> {
+        for access in accesses.unwrap_or_default() {
+            for (index, breakpoint) in self.breakpoints.iter().enumerate() {
+                if breakpoint.enabled
+                    && breakpoint.space == space
+                    && breakpoint.kind.matches(access.operation)
+                    && breakpoint.contains(access.addres)
+                    && condition_is_true(breakpoint, cpu)
+                {
+                    return Some(BreakReason::Watchpoint(index, access));
+                }
+            }
+        }
+        None
+    }
+
+    /** Texto do motivo da parada */
+    pub fn describe(&self, reason: &BreakReason) -> String {
+        let breakpoint = |index: &usize| {
+            self.breakpoints
+                .get(*index)
+                .map(|breakpoint| breakpoint.describe())
+                .unwrap_or_default()
+        };
+
+        match reason {
+            BreakReason::Breakpoint(index) => {
+                format!("breakpoint #{} {}", index, breakpoint(index))
+            }
+            BreakReason::Watchpoint(index, access) => format!(
+                "watchpoint #{} {}: {:?} ${:04X} = ${:02X}",
+                index,
+                breakpoint(index),
+                access.operation,
+                access.addres,
+                access.data
+            ),
+            BreakReason::Step => "step".to_string(),
+            BreakReason::Scanline(scanline) => format!("scanline {}", scanline),
+            BreakReason::Nmi => "NMI".to_string(),
+            BreakReason::Irq => "IRQ".to_string(),
+        }
+    }
+}
+
+fn condition_is_true(breakpoint: &Breakpoint, cpu: &mut Cpu6502) -> bool {
+    match &breakpoint.condition {
+        Some(condition) => condition.is_true(cpu),
+        None => true,
+    }
+}
@@ -0,0 +1,358 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::cpu::Cpu6502;
+
+// Símbolos =====================================================
+// Nomes para os endereços, lidos dos arquivos ao lado da ROM:
+//   game.dbg             informações de debug do ca65/ld65 (--dbgfile)
+//   game.nes.ram.nl      labels da RAM do FCEUX
+//   game.nes.0.nl ...    labels de cada banco de 16KB da PRG do FCEUX
+//   game.mlb             labels do Mesen
+// Os labels da PRG são guardados pela posição na PRG ROM e não pelo
+// endereço da CPU, assim o nome certo aparece mesmo quando dois bancos
+// usam o mesmo endereço. O resto (RAM, registradores, SRAM) é guardado
+// pelo endereço da CPU.
+//
+// confira:
+// - https://cc65.github.io/doc/debugging.html
+// - https://fceux.com/web/help/NLFilesFormat.html
+// - https://www.mesen.ca/docs/debugging/debuggerintegration.html
+
+const HEADER_SIZE: usize = 16;
+const TRAINER_SIZE: usize = 512;
+const BANK_SIZE: usize = 16384;
+
+pub struct Symbols {
+    cpu: HashMap<u16, String>,
+    prg: HashMap<usize, String>,
+    /** Endereço da CPU de cada nome, usado nos breakpoints */
+    names: HashMap<String, u16>,
+}
+
+impl Symbols {
+    pub fn new() -> Symbols {
+        Symbols {
+            cpu: HashMap::new(),
+            prg: HashMap::new(),
+            names: HashMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cpu.len() + self.prg.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cpu.is_empty() && self.prg.is_empty()
+    }
+
+    pub fn add_cpu(&mut self, addres: u16, name: &str) {
+        // A RAM se repete até $1FFF
+        let addres = if addres < 0x2000 {
+            addres & 0x07FF
+        } else {
+            addres
+        };
+        self.cpu.insert(addres, name.to_string());
+        self.names.insert(name.to_string(), addres);
+    }
+
+    // Sem o endereço da CPU (banco que não está mapeado) o nome aparece no disassembly,
+    // mas não pode ser usado nos breakpoints
+    fn add_prg(&mut self, position: usize, addres: Option<u16>, name: &str) {
+        self.prg.insert(position, name.to_string());
+        if let Some(addres) = addres {
+            self.names.insert(name.to_string(), addres);
+        }
+    }
+
+    /** Endereço da CPU de um nome */
+    pub fn addres(&self, name: &str) -> Option<u16> {
+        self.names.get(name).copied()
+    }
+
+    /** Nome de um endereço da CPU, "position" é a posição na PRG quando o endereço está
+    mapeado na ROM */
+    pub fn name(&self, addres: u16, position: Option<usize>) -> Option<&str> {
+        match position {
+            Some(position) => self.prg.get(&position),
+            None if addres < 0x2000 => self.cpu.get(&(addres & 0x07FF)),
+            None => self.cpu.get(&addres),
+        }
+        .map(|name| name.as_str())
+    }
+
+    /** Arquivo .dbg do ld65: os "seg" dizem onde cada segmento foi parar no arquivo
+    (ooffs) e os "sym" do tipo "lab" são os labels */
+    fn load_dbg(&mut self, text: &str, prg_start: usize, prg_size: usize) {
+        let fields = |line: &str| -> HashMap<String, String> {
+            line.split(',')
+                .filter_map(|field| field.split_once('='))
+                .map(|(key, value)| (key.trim().to_string(), value.trim_matches('"').to_string()))
+                .collect()
+        };
+        let number = |value: Option<&String>| {
+            value.and_then(|value| match value.strip_prefix("0x") {
+                Some(hex) => usize::from_str_radix(hex, 16).ok(),
+                None => value.parse().ok(),
+            })
+        };
+
+        // id -> (endereço inicial, posição no arquivo)
+        let mut segments: HashMap<String, (usize, Option<usize>)> = HashMap::new();
+        for line in text.lines() {
+            if let Some(line) = line.strip_prefix("seg\t") {
+                let fields = fields(line);
+                if let (Some(id), Some(start)) = (fields.get("id"), number(fields.get("start"))) {
+                    segments.insert(id.clone(), (start, number(fields.get("ooffs"))));
+                }
+            }
+        }
+
+        for line in text.lines() {
+            let line = match line.strip_prefix("sym\t") {
+                Some(line) => line,
+                None => continue,
+            };
+            let fields = fields(line);
+            let (name, value) = match (fields.get("name"), number(fields.get("val"))) {
+                (Some(name), Some(value)) if fields.get("type").is_some_and(|t| t == "lab") => {
+                    (name, value)
+                }
+                _ => continue,
+            };
+            // Labels locais ("@loop") se repetem em cada escopo
+            if name.starts_with('@') || value > 0xFFFF {
+                continue;
+            }
+
+            let segment = fields.get("seg").and_then(|id| segments.get(id));
+            match segment {
+                Some((start, Some(file_offset)))
+                    if file_offset + value - start >= prg_start
+                        && file_offset + value - start < prg_start + prg_size =>
+                {
+                    let position = file_offset + value - start - prg_start;
+                    self.add_prg(position, Some(value as u16), name);
+                }
+                _ => self.add_cpu(value as u16, name),
+            }
+        }
+    }
+
+    /** Arquivo .nl do FCEUX: "$C000#nome#comentário", "$0300/10#nome#" marca 16 bytes.
+    "bank" é o banco de 16KB dos arquivos da PRG */
+    fn load_nl(&mut self, text: &str, bank: Option<usize>) {
+        for line in text.lines() {
+            let mut fields = line.splitn(3, '#');
+            let (addres, name) = match (fields.next(), fields.next()) {
+                (Some(addres), Some(name)) if !name.trim().is_empty() => (addres, name.trim()),
+                _ => continue,
+            };
+            let addres = addres.trim().trim_start_matches('$');
+            let addres = addres.split('/').next().unwrap_or("");
+            let addres = match u16::from_str_radix(addres, 16) {
+                Ok(addres) => addres,
+                Err(_) => continue,
+            };
+
+            match bank {
+                Some(bank) => {
+                    let position = bank * BANK_SIZE + (addres as usize & 0x3FFF);
+                    self.add_prg(position, Some(addres), name)
+                }
+                None => self.add_cpu(addres, name),
+            }
+        }
+    }
+
+    /** Arquivo .mlb do Mesen: "tipo:endereço[-fim]:nome[:comentário]", com os tipos do
+    Mesen 1 (P, R, S, W, G) ou do Mesen 2 (NesPrgRom, NesInternalRam...). "prg_addres"
+    diz onde uma posição da PRG está mapeada agora */
+    fn load_mlb(&mut self, text: &str, prg_addres: &HashMap<usize, u16>) {
+        for line in text.lines() {
+            let fields: Vec<&str> = line.splitn(4, ':').collect();
+            if fields.len() < 3 || fields[2].trim().is_empty() {
+                continue;
+            }
+            let name = fields[2].trim();
+            let start = fields[1].split('-').next().unwrap_or("");
+            let value = match usize::from_str_radix(start, 16) {
+                Ok(value) => value,
+                Err(_) => continue,
+            };
+
+            match fields[0] {
+                "P" | "NesPrgRom" => self.add_prg(value, prg_addres.get(&value).copied(), name),
+                "R" | "NesInternalRam" => self.add_cpu(value as u16, name),
+                "S" | "W" | "NesSaveRam" | "NesWorkRam" => {
+                    self.add_cpu(0x6000 + (value as u16 & 0x1FFF), name)
+                }
+                "G" | "NesMemory" => self.add_cpu(value as u16, name),
+                _ => {}
+            }
+        }
+    }
+}
+
+// Busca dos nomes com o mapeamento atual do cartucho
+impl Cpu6502 {
+    /** Posição na PRG ROM do endereço, se ele está mapeado na ROM */
+    pub fn prg_position(&mut self, addres: u16) -> Option<usize> {
+        if addres < 0x4020 {
+            return None;
+        }
+        match self.bus.prg_rom.mapper.cpu_map_read(addres) {
+            (true, position) => Some(position as usize),
+            _ => None,
+        }
+    }
+
+    /** Nome do endereço nos símbolos carregados */
+    pub fn symbol(&mut self, addres: u16) -> Option<String> {
+        if self.symbols.is_empty() {
+            return None;
+        }
+        let position = self.prg_position(addres);
+        self.symbols
+            .name(addres, position)
+            .map(|name| name.to_string())
+    }
+
+    /** Carrega os arquivos de símbolos que existirem ao lado da ROM */
+    pub fn load_symbols(&mut self, rom: &str) {
+        let data = fs::read(rom).unwrap_or_default();
+        if data.len() < HEADER_SIZE {
+            return;
+        }
+        let prg_start = HEADER_SIZE + if data[6] & 0x04 > 0 { TRAINER_SIZE } else { 0 };
+        let prg_banks = data[4] as usize;
+
+        // Onde cada posição da PRG aparece agora. Quando ela aparece em dois lugares (NROM
+        // de 16KB) ganha a metade para onde aponta o vetor de reset
+        let reset = (self.bus_read(0xFFFD, true) as u16) << 8 | self.bus_read(0xFFFC, true) as u16;
+        let mut prg_addres = HashMap::new();
+        for addres in 0x8000..=0xFFFF {
+            if let Some(position) = self.prg_position(addres) {
+                if !prg_addres.contains_key(&position) || addres & 0xC000 == reset & 0xC000 {
+                    prg_addres.insert(position, addres);
+                }
+            }
+        }
+
+        let path = Path::new(rom);
+        let mut files = vec![];
+        files.push((path.with_extension("dbg"), "dbg", None));
+        files.push((path.with_extension("mlb"), "mlb", None));
+        files.push((
+            Path::new(&format!("{}.ram.nl", rom)).to_path_buf(),
+            "nl",
+            None,
+        ));
+        for bank in 0..prg_banks {
+            let file = format!("{}.{:X}.nl", rom, bank);
+            files.push((Path::new(&file).to_path_buf(), "nl", Some(bank)));
+        }
+
+        let mut symbols = std::mem::replace(&mut self.symbols, Symbols::new());
+        for (file, kind, bank) in files {
+            let text = match fs::read_to_string(&file) {
+                Ok(text) => text,
+                Err(_) => continue,
+            };
+            let before = symbols.len();
+            match kind {
+                "dbg" => symbols.load_dbg(&text, prg_start, prg_banks * BANK_SIZE),
+                "mlb" => symbols.load_mlb(&text, &prg_addres),
+                _ => symbols.load_nl(&text, bank),
+            }
+            println!(
+                "[symbols] {} symbols: {}",
+                symbols.len() - before,
+                file.display()
+            );
+        }
+        self.symbols = symbols;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dbg_labels() {
+        let text = "version\tmajor=2,minor=0\n\
+            seg\tid=0,name=\"HEADER\",start=0x000000,size=0x0010,type=ro,oname=\"game.nes\",ooffs=0\n\
+            seg\tid=1,name=\"CODE\",start=0x008000,size=0x4000,type=ro,oname=\"game.nes\",ooffs=16\n\
+            seg\tid=2,name=\"ZEROPAGE\",start=0x000000,size=0x0010,type=rw\n\
+            sym\tid=0,name=\"reset\",addrsize=absolute,scope=0,def=1,val=0x8010,seg=1,type=lab\n\
+            sym\tid=1,name=\"@loop\",addrsize=absolute,scope=1,def=2,val=0x8012,seg=1,type=lab\n\
+            sym\tid=2,name=\"frame\",addrsize=zeropage,scope=0,def=3,val=0x4,seg=2,type=lab\n\
+            sym\tid=3,name=\"PPUCTRL\",addrsize=absolute,scope=0,def=4,val=0x2000,type=equ\n";
+        let mut symbols = Symbols::new();
+        symbols.load_dbg(text, 16, 0x4000);
+
+        assert_eq!(symbols.addres("reset"), Some(0x8010));
+        assert_eq!(symbols.name(0x8010, Some(0x0010)), Some("reset"));
+        assert_eq!(symbols.addres("frame"), Some(0x0004));
+        assert_eq!(symbols.name(0x0804, None), Some("frame"));
+        // Labels locais e constantes ficam de fora
+        assert_eq!(symbols.addres("@loop"), None);
+        assert_eq!(symbols.addres("PPUCTRL"), None);
+        assert_eq!(symbols.len(), 2);
+    }
+
+    #[test]
+    fn nl_labels() {
+        let mut symbols = Symbols::new();
+        symbols.load_nl(
+            "$0010#frame#contador\n$0300/10#buffer#\n$0400##\nlixo\n",
+            None,
+        );
+        symbols.load_nl("$C005#nmi#\n", Some(1));
+
+        assert_eq!(symbols.addres("frame"), Some(0x0010));
+        assert_eq!(symbols.addres("buffer"), Some(0x0300));
+        assert_eq!(symbols.name(0x0400, None), None);
+        assert_eq!(symbols.addres("nmi"), Some(0xC005));
+        assert_eq!(symbols.name(0xC005, Some(BANK_SIZE + 5)), Some("nmi"));
+        assert_eq!(symbols.name(0xC005, Some(5)), None);
+        assert_eq!(symbols.len(), 3);
+    }
+
+    #[test]
+    fn mlb_labels() {
+        let text = "P:0010:reset\n\
+            P:4000:other_bank\n\
+            NesPrgRom:0012-0013:table:comentário\n\
+            R:0010:frame\n\
+            W:0000:save\n\
+            G:2000:PPUCTRL\n\
+            R:0020:\n";
+        let prg_addres: HashMap<usize, u16> = [(0x0010, 0x8010), (0x0012, 0x8012)].into();
+        let mut symbols = Symbols::new();
+        symbols.load_mlb(text, &prg_addres);
+
+        assert_eq!(symbols.addres("reset"), Some(0x8010));
+        assert_eq!(symbols.name(0x8010, Some(0x0010)), Some("reset"));
+        assert_eq!(symbols.addres("table"), Some(0x8012));
+        assert_eq!(symbols.addres("frame"), Some(0x0010));
+        assert_eq!(symbols.addres("save"), Some(0x6000));
+        assert_eq!(symbols.addres("PPUCTRL"), Some(0x2000));
+        assert_eq!(symbols.len(), 6);
+    }
+
+    #[test]
+    fn mlb_label_in_unmapped_bank() {
+        let mut symbols = Symbols::new();
+        symbols.load_mlb("P:4000:other_bank\n", &HashMap::new());
+
+        // Nada de um $8000 inventado para os breakpoints, mas o nome continua no disassembly
+        assert_eq!(symbols.addres("other_bank"), None);
+        assert_eq!(symbols.name(0x8000, Some(0x4000)), Some("other_bank"));
+        assert!(!symbols.is_empty());
+    }
+}
@@ -0,0 +1,489 @@
+use std::io::{self, BufRead, IsTerminal, Write};
+
+use super::{parse_bytes, AddressSpace, Debugger, HexEditor, MemorySpace, HEX_EDITOR_COLUMNS};
+use crate::bus::Bus;
+use crate::cartridge::Cartridge;
+use crate::cpu::{Cpu6502, Flags6502};
+
+// Debugger no terminal =========================================
+// Front end em texto para o debugger, para máquinas sem display (CI,
+// SSH). Depois de cada comando a tela é redesenhada com a disassembly
+// em volta do PC, os registradores, a stack, o estado da PPU e a
+// memória. Os comandos são lidos linha a linha da entrada padrão, então
+// também dá para usar com um script:
+//   printf 'b $C000\nc\nq\n' | rust-nes-emulator --rom game.nes --tui
+// Quando a saída não é um terminal as cores e a limpeza da tela são
+// desligadas.
+
+const CLEAR: &str = "\x1b[2J\x1b[H";
+const RESET: &str = "\x1b[0m";
+const REVERSE: &str = "\x1b[7m";
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+
+const DISASSEMBLY_LINES: usize = 16;
+const DISASSEMBLY_BEFORE: usize = 5;
+const DISASSEMBLY_WIDTH: usize = 44;
+const MEMORY_LINES: usize = 8;
+const STACK_LINES: u16 = 8;
+
+// Os comandos que executam desistem depois de 1 minuto emulado sem parar
+const MAX_FRAMES: u64 = 3600;
+
+const HELP: &str = "\
+s [n]         step into (n instruções)
+n             step over
+o             step out
+c             continue
+f [n]         executa n frames
+l <linha>     run to scanline
+b <bp>        breakpoint: [x|r|w|rw][:cpu|:ppu] endereço[-endereço] [if condição]
+bd <n>        remove o breakpoint n
+bt <n>        liga/desliga o breakpoint n
+nmi / irq     liga/desliga a parada nas interrupções
+m [esp] [end] mostra a memória a partir do endereço, esp: cpu ram ppu nt pal oam prg chr prgram
+e <end> <bytes>  escreve os bytes na memória mostrada
+/ <bytes>     procura os bytes na memória mostrada
+r             reset
+q             sair
+(linha vazia repete o último comando)";
+
+pub struct TerminalDebugger {
+    pub cpu: Cpu6502,
+    pub debugger: Debugger,
+    memory: HexEditor,
+    message: String,
+    last_command: String,
+    frames: u64,
+    color: bool,
+}
+
+impl TerminalDebugger {
+    pub fn new(file_name: &str) -> TerminalDebugger {
+        let cartridge = Cartridge::new(file_name.to_string());
+        let mut cpu = Cpu6502::new_with_bus(Bus::new(cartridge));
+        cpu.load_symbols(file_name);
+        cpu.reset();
+
+        TerminalDebugger {
+            cpu,
+            debugger: Debugger::new(),
+            memory: HexEditor::new(MEMORY_LINES),
+            message: "h para ajuda".to_string(),
+            last_command: String::new(),
+            frames: 0,
+            color: io::stdout().is_terminal(),
+        }
+    }
+
+    /** Lê e executa os comandos até "q" ou o fim da entrada */
+    pub fn start(&mut self) {
+        let stdin = io::stdin();
+        let mut lines = stdin.lock().lines();
+
+        loop {
+            print!("{}(nes) ", self.render());
+            io::stdout().flush().unwrap();
+
+            let line = match lines.next() {
+                Some(Ok(line)) => line,
+                _ => break,
+            };
+            if !self.color {
+                // Sem terminal o comando não aparece na saída, repete para o log ficar legível
+                println!("{}", line);
+            }
+            if !self.execute(&line) {
+                break;
+            }
+        }
+    }
+
+    /** Executa um comando, retorna false para sair */
+    pub fn execute(&mut self, line: &str) -> bool {
+        let line = match line.trim() {
+            "" => self.last_command.clone(),
+            line => line.to_string(),
+        };
+        self.last_command = line.clone();
+
+        let (command, argument) = match line.split_once(' ') {
+            Some((command, argument)) => (command, argument.trim()),
+            None => (&line[..], ""),
+        };
+        let count = argument.parse::<u64>().unwrap_or(1).max(1);
+
+        self.message = match command {
+            "s" | "step" => {
+                let mut message = String::new();
+                for _ in 0..count {
+                    self.debugger.step_into();
+                    message = self.run(MAX_FRAMES);
+                    if self.cpu.halted {
+                        break;
+                    }
+                }
+                message
+            }
+            "n" | "next" => {
+                self.debugger.step_over(&mut self.cpu);
+                self.run(MAX_FRAMES)
+            }
+            "o" | "out" => {
+                self.debugger.step_out(&self.cpu);
+                self.run(MAX_FRAMES)
+            }
+            "c" | "continue" => {
+                self.debugger.resume();
+                self.run(MAX_FRAMES)
+            }
+            "f" | "frame" => {
+                self.debugger.resume();
+                self.run(count)
+            }
+            "l" | "line" => match argument.parse::<i16>() {
+                Ok(scanline) if (-1..261).contains(&scanline) => {
+                    self.debugger.run_to_scanline(scanline);
+                    self.run(MAX_FRAMES)
+                }
+                _ => format!("invalid scanline: {}", argument),
+            },
+            "b" | "break" => match self.debugger.add_breakpoint(argument, &self.cpu.symbols) {
+                Ok(index) => format!(
+                    "breakpoint #{} {}",
+                    index,
+                    self.debugger.breakpoints()[index].describe()
+                ),
+                Err(e) => e,
+            },
+            "bd" | "delete" => match argument.parse() {
+                Ok(index) if self.debugger.remove_breakpoint(index) => {
+                    format!("breakpoint #{} removed", index)
+                }
+                _ => format!("invalid breakpoint: {}", argument),
+            },
+            "bt" | "toggle" => match argument
+                .parse()
+                .ok()
+                .and_then(|index| self.debugger.toggle_breakpoint(index))
+            {
+                Some(enabled) => format!("breakpoint #{} enabled: {}", argument, enabled),
+                None => format!("invalid breakpoint: {}", argument),
+            },
+            "nmi" => {
+                self.debugger.break_on_nmi = !self.debugger.break_on_nmi;
+                format!("break on NMI: {}", self.debugger.break_on_nmi)
+            }
+            "irq" => {
+                self.debugger.break_on_irq = !self.debugger.break_on_irq;
+                format!("break on IRQ: {}", self.debugger.break_on_irq)
+            }
+            "m" | "memory" => self.set_memory_view(argument),
+            "e" | "edit" => self.edit_memory(argument),
+            "/" | "find" => match parse_bytes(argument) {
+                Ok(pattern) => match self.memory.find(&pattern, &mut self.cpu) {
+                    Some(addres) => format!("found at ${:04X}", addres),
+                    None => format!("not found: {}", argument),
+                },
+                Err(e) => e,
+            },
+            "r" | "reset" => {
+                self.cpu.reset();
+                "reset".to_string()
+            }
+            "h" | "help" | "?" => HELP.to_string(),
+            "q" | "quit" => return false,
+            _ => format!("unknown command: {} (h para ajuda)", command),
+        };
+
+        true
+    }
+
+    fn set_memory_view(&mut self, argument: &str) -> String {
+        let mut words = argument.split_whitespace().peekable();
+        if let Some(space) = words.peek().and_then(|word| MemorySpace::from(word)) {
+            words.next();
+            self.memory.set_space(space);
+        }
+
+        let addres = match words.next() {
+            Some(addres) => match usize::from_str_radix(addres.trim_start_matches('$'), 16) {
+                Ok(addres) if addres < self.cpu.space_size(self.memory.space) => addres,
+                _ => return format!("invalid address: {}", addres),
+            },
+            None => self.memory.cursor,
+        };
+        self.memory.goto(addres, &self.cpu);
+        // O endereço pedido na primeira linha
+        self.memory.top = addres / HEX_EDITOR_COLUMNS;
+        format!("memory {} ${:04X}", self.memory.space.name(), addres)
+    }
+
+    fn edit_memory(&mut self, argument: &str) -> String {
+        let (addres, bytes) = match argument.split_once(' ') {
+            Some((addres, bytes)) => (addres, bytes),
+            None => return "e <endereço> <bytes>".to_string(),
+        };
+        let addres = match usize::from_str_radix(addres.trim_start_matches('$'), 16) {
+            Ok(addres) if addres < self.cpu.space_size(self.memory.space) => addres,
+            _ => return format!("invalid address: {}", addres),
+        };
+        match parse_bytes(bytes) {
+            Ok(bytes) => {
+                self.memory.write(addres, &bytes, &mut self.cpu);
+                format!(
+                    "{} bytes written at {} ${:04X}",
+                    bytes.len(),
+                    self.memory.space.name(),
+                    addres
+                )
+            }
+            Err(e) => e,
+        }
+    }
+
+    // Executa até o debugger parar, a CPU travar ou passarem "frames" frames
+    fn run(&mut self, frames: u64) -> String {
+        let mut remaining = frames;
+
+        loop {
+            self.cpu.clock();
+
+            if self.debugger.is_active() {
+                if let Some(reason) = self.debugger.check(&mut self.cpu) {
+                    return self.debugger.describe(&reason);
+                }
+            }
+
+            if self.cpu.halted {
+                return format!("JAM ${:02X}", self.cpu.opcode);
+            }
+
+            if self.cpu.bus.ppu.frame_complete {
+                self.cpu.bus.ppu.frame_complete = false;
+                self.frames += 1;
+                remaining -= 1;
+                if remaining == 0 {
+                    return format!("{} frames", frames);
+                }
+            }
+        }
+    }
+}
+
+// Telas
+impl TerminalDebugger {
+    fn paint(&self, text: &str, code: &str) -> String {
+        if self.color {
+            format!("{}{}{}", code, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+
+    fn render(&mut self) -> String {
+        let left = self.disassembly_pane();
+        let mut right = self.registers_pane();
+        right.push(String::new());
+        right.append(&mut self.stack_pane());
+        right.push(String::new());
+        right.append(&mut self.ppu_pane());
+
+        let mut screen = if self.color {
+            CLEAR.to_string()
+        } else {
+            String::new()
+        };
+
+        // A disassembly à esquerda e o resto à direita
+        for i in 0..left.len().max(right.len()) {
+            let (text, code) = left.get(i).cloned().unwrap_or_default();
+            let text = format!("{:width$}", text, width = DISASSEMBLY_WIDTH);
+            let text = match code {
+                Some(code) => self.paint(&text, code),
+                None => text,
+            };
+            screen += &format!("{} {}\n", text, right.get(i).cloned().unwrap_or_default());
+        }
+
+        screen.push('\n');
+        for line in self.memory_pane() {
+            screen += &line;
+            screen.push('\n');
+        }
+
+        screen.push('\n');
+        for (index, breakpoint) in self.debugger.breakpoints().iter().enumerate() {
+            let line = format!("#{} {}", index, breakpoint.describe());
+            if breakpoint.enabled {
+                screen += &self.paint(&line, RED);
+            } else {
+                screen += &line;
+                screen += " (off)";
+            }
+            screen.push('\n');
+        }
+
+        screen += &self.paint(&self.message, YELLOW);
+        screen.push('\n');
+        screen
+    }
+
+    // Linhas da disassembly com a cor de cada uma (PC em destaque, breakpoints em vermelho)
+    fn disassembly_pane(&mut self) -> Vec<(String, Option<&'static str>)> {
+        let pc = self.cpu.pc;
+
+        // Não dá para saber onde as instruções anteriores começam, a disassembly começa
+        // um pouco antes e só é usada se passar exatamente pelo PC
+        let before = self.cpu.disassemble(pc.saturating_sub(24), pc);
+        let mut before: Vec<u16> = if before.contains_key(&pc) {
+            before
+                .keys()
+                .copied()
+                .filter(|addres| *addres < pc)
+                .collect()
+        } else {
+            vec![]
+        };
+        before.sort_unstable();
+        let before = &before[before.len().saturating_sub(DISASSEMBLY_BEFORE)..];
+
+        let after = self.cpu.disassemble(pc, pc.saturating_add(48));
+        let mut after: Vec<u16> = after.keys().copied().collect();
+        after.sort_unstable();
+
+        let mut addresses: Vec<u16> = before.to_vec();
+        addresses.extend(after.iter().take(DISASSEMBLY_LINES - before.len()));
+
+        let lines = self
+            .cpu
+            .disassemble(addresses[0], *addresses.last().unwrap_or(&pc));
+        addresses
+            .iter()
+            .map(|addres| {
+                let line = lines.get(addres).cloned().unwrap_or_default();
+                let breakpoint = self.debugger.breakpoints().iter().any(|breakpoint| {
+                    breakpoint.enabled
+                        && breakpoint.space == AddressSpace::Cpu
+                        && breakpoint.contains(*addres)
+                });
+
+                match (*addres == pc, breakpoint) {
+                    (true, _) => (format!("> {}", line), Some(REVERSE)),
+                    (false, true) => (format!("* {}", line), Some(RED)),
+                    (false, false) => (format!("  {}", line), None),
+                }
+            })
+            .collect()
+    }
+
+    fn registers_pane(&mut self) -> Vec<String> {
+        let cpu = &mut self.cpu;
+        let mut flags = String::from("FLAGS ");
+        for (name, flag) in [
+            ("N", Flags6502::N),
+            ("V", Flags6502::V),
+            ("U", Flags6502::U),
+            ("B", Flags6502::B),
+            ("D", Flags6502::D),
+            ("I", Flags6502::I),
+            ("Z", Flags6502::Z),
+            ("C", Flags6502::C),
+        ] {
+            let active = cpu.get_flag(flag) == 1;
+            let text = if active { name } else { "-" };
+            flags += &if self.color {
+                format!("{}{}{}", if active { GREEN } else { RED }, text, RESET)
+            } else {
+                text.to_string()
+            };
+        }
+
+        vec![
+            format!(
+                "PC ${:04X}  A ${:02X}  X ${:02X}  Y ${:02X}",
+                cpu.pc, cpu.a, cpu.x, cpu.y
+            ),
+            format!(
+                "SP ${:02X}  P ${:02X}  CYC {}",
+                cpu.stkp, cpu.status, cpu.clock_count
+            ),
+            flags,
+        ]
+    }
+
+    fn stack_pane(&mut self) -> Vec<String> {
+        let mut lines = vec![format!("STACK ${:02X}", self.cpu.stkp)];
+        for offset in 1..=STACK_LINES {
+            let addres = 0x0100 + self.cpu.stkp as u16 + offset;
+            if addres > 0x01FF {
+                break;
+            }
+            let data = self.cpu.bus_read(addres, true);
+            lines.push(format!("  ${:04X}: ${:02X}", addres, data));
+        }
+        lines
+    }
+
+    fn ppu_pane(&self) -> Vec<String> {
+        let ppu = &self.cpu.bus.ppu;
+        vec![
+            format!(
+                "PPU   SCANLINE {}  CYCLE {}  FRAME {}",
+                ppu.scanline, ppu.cycle, self.frames
+            ),
+            format!(
+                "CTRL ${:02X}  MASK ${:02X}  STATUS ${:02X}",
+                ppu.control.reg, ppu.mask.reg, ppu.status.reg
+            ),
+            format!(
+                "V ${:04X}  T ${:04X}  X {}  W {}",
+                ppu.vram_addr.reg, ppu.tram_addr.reg, ppu.fine_x, ppu.address_latch
+            ),
+            format!("OAMADDR ${:02X}", ppu.oam_addr),
+        ]
+    }
+
+    // Os bytes que mudaram desde o último comando ficam em vermelho, o cursor em destaque
+    fn memory_pane(&mut self) -> Vec<String> {
+        self.memory.update(&mut self.cpu);
+        let mut lines = vec![format!("MEMORY {}", self.memory.space.title())];
+
+        for start in self.memory.visible_rows(&self.cpu) {
+            let size = self.cpu.space_size(self.memory.space);
+            let end = (start + HEX_EDITOR_COLUMNS).min(size);
+            let bytes: Vec<u8> = (start..end)
+                .map(|addres| self.cpu.peek_space(self.memory.space, addres))
+                .collect();
+
+            let hex: Vec<String> = bytes
+                .iter()
+                .enumerate()
+                .map(|(i, byte)| {
+                    let text = format!("{:02X}", byte);
+                    if start + i == self.memory.cursor {
+                        self.paint(&text, REVERSE)
+                    } else if self.memory.highlight(start + i) >= 1.0 {
+                        self.paint(&text, RED)
+                    } else {
+                        text
+                    }
+                })
+                .collect();
+            let ascii: String = bytes
+                .iter()
+                .map(|byte| {
+                    if byte.is_ascii_graphic() {
+                        *byte as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+            lines.push(format!("${:04X}: {}  {}", start, hex.join(" "), ascii));
+        }
+
+        lines
+    }
+}
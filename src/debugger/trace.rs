@@ -0,0 +1,415 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use crate::cpu::{AddressMode, Cpu6502, Flags6502, Instruction};
+
+// Trace das instruções =========================================
+// Escreve uma linha por instrução executada, com o estado da CPU antes
+// dela, para comparar com o log de outros emuladores (diff). O formato é
+// um texto com campos entre chaves, "{NOME}" ou "{NOME:largura}", os
+// números são alinhados à direita e os textos à esquerda:
+//   PC       endereço da instrução
+//   BYTES    bytes da instrução ("4C F5 C5")
+//   MARK     "*" nas instruções ilegais, " " nas outras
+//   DISASM   instrução com o endereço efetivo e o valor, como no nestest
+//            ("LDA ($89),Y = 0300 @ 0300 = 89")
+//   A X Y P SP          registradores em hexadecimal
+//   FLAGS    flags em letras, maiúsculas quando ligadas ("nvUbdIzc")
+//   CYC      ciclo da CPU
+//   SL DOT   scanline e ciclo da PPU
+//   FRAME    frames desde o começo do trace
+//   LABEL    nome do PC nos símbolos carregados (vazio se não tiver)
+// "nestest" e "mesen" são os formatos prontos.
+//
+// confira:
+// - https://www.qmtpro.com/~nes/misc/nestest.log
+// - https://www.mesen.ca/docs/debugging/tracelogger.html
+
+pub const NESTEST_FORMAT: &str =
+    "{PC}  {BYTES:8} {MARK}{DISASM:32}A:{A} X:{X} Y:{Y} P:{P} SP:{SP} PPU:{SL:3},{DOT:3} CYC:{CYC}";
+pub const MESEN_FORMAT: &str =
+    "{PC}  {DISASM:32} A:{A} X:{X} Y:{Y} S:{SP} P:{FLAGS} V:{SL:3} H:{DOT:3} Fr:{FRAME} Cycle:{CYC}";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TraceField {
+    Pc,
+    Bytes,
+    Mark,
+    Disasm,
+    A,
+    X,
+    Y,
+    P,
+    Sp,
+    Flags,
+    Cycle,
+    Scanline,
+    Dot,
+    Frame,
+    Label,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TracePart {
+    Text(String),
+    Field(TraceField, usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceFormat {
+    parts: Vec<TracePart>,
+}
+
+impl TraceFormat {
+    /** "nestest", "mesen" ou um formato com os campos entre chaves */
+    pub fn from(text: &str) -> Result<TraceFormat, String> {
+        let text = match text {
+            "nestest" => NESTEST_FORMAT,
+            "mesen" => MESEN_FORMAT,
+            text => text,
+        };
+
+        let mut parts = vec![];
+        let mut rest = text;
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                parts.push(TracePart::Text(rest[..start].to_string()));
+            }
+            let end = rest[start..]
+                .find('}')
+                .ok_or_else(|| format!("unclosed field in trace format: {}", text))?
+                + start;
+
+            let field = &rest[start + 1..end];
+            let (name, width) = match field.split_once(':') {
+                Some((name, width)) => (
+                    name,
+                    width
+                        .parse()
+                        .map_err(|_| format!("invalid width in trace field: {}", field))?,
+                ),
+                None => (field, 0),
+            };
+            let field = match name {
+                "PC" => TraceField::Pc,
+                "BYTES" => TraceField::Bytes,
+                "MARK" => TraceField::Mark,
+                "DISASM" => TraceField::Disasm,
+                "A" => TraceField::A,
+                "X" => TraceField::X,
+                "Y" => TraceField::Y,
+                "P" => TraceField::P,
+                "SP" => TraceField::Sp,
+                "FLAGS" => TraceField::Flags,
+                "CYC" => TraceField::Cycle,
+                "SL" => TraceField::Scanline,
+                "DOT" => TraceField::Dot,
+                "FRAME" => TraceField::Frame,
+                "LABEL" => TraceField::Label,
+                _ => return Err(format!("unknown trace field: {}", name)),
+            };
+            parts.push(TracePart::Field(field, width));
+            rest = &rest[end + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(TracePart::Text(rest.to_string()));
+        }
+
+        Ok(TraceFormat { parts })
+    }
+}
+
+/** Formato e gatilhos usados quando o trace começa */
+#[derive(Debug, Clone)]
+pub struct TraceOptions {
+    pub format: TraceFormat,
+    /** Só começa a escrever quando o PC chegar nesse endereço */
+    pub start: Option<u16>,
+    /** Para de escrever depois da instrução nesse endereço (e espera o start de novo) */
+    pub stop: Option<u16>,
+}
+
+impl TraceOptions {
+    pub fn new() -> TraceOptions {
+        TraceOptions {
+            format: TraceFormat::from(NESTEST_FORMAT).unwrap(),
+            start: None,
+            stop: None,
+        }
+    }
+}
+
+pub struct TraceLogger {
+    pub path: String,
+    options: TraceOptions,
+    writer: BufWriter<File>,
+    /** Esperando o gatilho de start */
+    waiting: bool,
+    pub lines: u64,
+    frame: u64,
+    // clock_count da CPU na última instrução escrita
+    last_clock: u32,
+}
+
+impl TraceLogger {
+    pub fn start(path: &str, options: TraceOptions) -> Result<TraceLogger, String> {
+        let file = File::create(path).map_err(|e| format!("{}: {}", path, e))?;
+        Ok(TraceLogger {
+            path: path.to_string(),
+            waiting: options.start.is_some(),
+            options,
+            writer: BufWriter::new(file),
+            lines: 0,
+            frame: 0,
+            last_clock: u32::MAX,
+        })
+    }
+
+    /** Chamado antes de cada clock do sistema, escreve a próxima instrução quando a
+    anterior acabou de terminar */
+    pub fn clock(&mut self, cpu: &mut Cpu6502) -> Result<(), String> {
+        // Uma instrução por clock_count: durante o DMA a CPU fica parada entre instruções.
+        // Com uma interrupção pendente a próxima "instrução" é a sequência dela, o trace
+        // continua no começo do handler
+        if !cpu.complete() || cpu.halted || cpu.interrupt_poll || cpu.clock_count == self.last_clock
+        {
+            return Ok(());
+        }
+        self.last_clock = cpu.clock_count;
+
+        if self.waiting {
+            if Some(cpu.pc) != self.options.start {
+                return Ok(());
+            }
+            self.waiting = false;
+        }
+
+        let line = self.format_line(cpu);
+        writeln!(self.writer, "{}", line).map_err(|e| format!("{}: {}", self.path, e))?;
+        self.lines += 1;
+
+        if Some(cpu.pc) == self.options.stop {
+            self.waiting = true;
+        }
+        Ok(())
+    }
+
+    pub fn end_frame(&mut self) {
+        self.frame += 1;
+    }
+
+    pub fn finish(mut self) -> Result<(), String> {
+        self.writer
+            .flush()
+            .map_err(|e| format!("{}: {}", self.path, e))
+    }
+
+    fn format_line(&self, cpu: &mut Cpu6502) -> String {
+        let mut line = String::new();
+        for part in &self.options.format.parts {
+            match part {
+                TracePart::Text(text) => line += text,
+                TracePart::Field(field, width) => {
+                    let (value, number) = self.field(*field, cpu);
+                    line += &if number {
+                        format!("{:>width$}", value, width = width)
+                    } else {
+                        format!("{:<width$}", value, width = width)
+                    };
+                }
+            }
+        }
+        line.trim_end().to_string()
+    }
+
+    // Valor do campo e se ele é um número (alinhado à direita)
+    fn field(&self, field: TraceField, cpu: &mut Cpu6502) -> (String, bool) {
+        match field {
+            TraceField::Pc => (format!("{:04X}", cpu.pc), false),
+            TraceField::Bytes => {
+                let size = instruction_size(Instruction::from(cpu.bus_read(cpu.pc, true)));
+                let bytes: Vec<String> = (0..size)
+                    .map(|i| format!("{:02X}", cpu.bus_read(cpu.pc.wrapping_add(i), true)))
+                    .collect();
+                (bytes.join(" "), false)
+            }
+            TraceField::Mark => {
+                let name = instruction_name(cpu.bus_read(cpu.pc, true));
+                (
+                    if name.starts_with('*') { "*" } else { " " }.to_string(),
+                    false,
+                )
+            }
+            TraceField::Disasm => (disassemble_trace(cpu), false),
+            TraceField::A => (format!("{:02X}", cpu.a), false),
+            TraceField::X => (format!("{:02X}", cpu.x), false),
+            TraceField::Y => (format!("{:02X}", cpu.y), false),
+            TraceField::P => (format!("{:02X}", cpu.status), false),
+            TraceField::Sp => (format!("{:02X}", cpu.stkp), false),
+            TraceField::Flags => {
+                let flags = [
+                    ('n', Flags6502::N),
+                    ('v', Flags6502::V),
+                    ('u', Flags6502::U),
+                    ('b', Flags6502::B),
+                    ('d', Flags6502::D),
+                    ('i', Flags6502::I),
+                    ('z', Flags6502::Z),
+                    ('c', Flags6502::C),
+                ];
+                let text = flags
+                    .iter()
+                    .map(|(name, flag)| {
+                        if cpu.get_flag(*flag) == 1 {
+                            name.to_ascii_uppercase()
+                        } else {
+                            *name
+                        }
+                    })
+                    .collect();
+                (text, false)
+            }
+            TraceField::Cycle => (cpu.clock_count.to_string(), true),
+            TraceField::Scanline => (cpu.bus.ppu.scanline.to_string(), true),
+            TraceField::Dot => (cpu.bus.ppu.cycle.to_string(), true),
+            TraceField::Frame => (self.frame.to_string(), true),
+            TraceField::Label => (cpu.symbol(cpu.pc).unwrap_or_default(), false),
+        }
+    }
+}
+
+pub(super) fn instruction_size(instruction: Instruction) -> u16 {
+    match instruction.addres_mode {
+        AddressMode::IMP => 1,
+        AddressMode::ABS | AddressMode::ABX | AddressMode::ABY | AddressMode::IND => 3,
+        _ => 2,
+    }
+}
+
+/** Nome da instrução como no nestest: os NOPs ilegais são todos "*NOP", ISC é "*ISB" e
+os opcodes ilegais que repetem uma instrução oficial (SBC $EB, NOP $1A...) também levam "*" */
+pub fn instruction_name(opcode: u8) -> String {
+    let name = Instruction::from(opcode).name;
+    match name {
+        "*IGN" | "*SKB" => "*NOP".to_string(),
+        "*ISC" => "*ISB".to_string(),
+        "SBC" if opcode == 0xEB => "*SBC".to_string(),
+        "NOP" if opcode != 0xEA => "*NOP".to_string(),
+        name => name.to_string(),
+    }
+}
+
+// Nome do símbolo do endereço ou o endereço em hexadecimal
+fn operand_name(cpu: &mut Cpu6502, addres: u16, hex_size: usize) -> String {
+    cpu.symbol(addres)
+        .unwrap_or_else(|| format!("${:0width$X}", addres, width = hex_size))
+}
+
+/** A instrução no PC no formato do nestest, com o endereço efetivo e o valor lido
+(sem efeitos colaterais) calculados com os registradores atuais. Os endereços com nome
+nos símbolos carregados aparecem com o nome */
+pub fn disassemble_trace(cpu: &mut Cpu6502) -> String {
+    let pc = cpu.pc;
+    let opcode = cpu.bus_read(pc, true);
+    let instruction = Instruction::from(opcode);
+    let name = instruction_name(opcode);
+    let name = name.trim_start_matches('*');
+
+    let byte = cpu.bus_read(pc.wrapping_add(1), true);
+    let word = (cpu.bus_read(pc.wrapping_add(2), true) as u16) << 8 | byte as u16;
+    // Lê 16 bits sem passar da página (bug do 6502 no JMP indireto e nos ponteiros da zero page)
+    let read_pointer = |cpu: &mut Cpu6502, addres: u16| {
+        let hi_addres = (addres & 0xFF00) | (addres.wrapping_add(1) & 0x00FF);
+        (cpu.bus_read(hi_addres, true) as u16) << 8 | cpu.bus_read(addres, true) as u16
+    };
+
+    let operand = match instruction.addres_mode {
+        AddressMode::IMP => match opcode {
+            // Instruções no acumulador
+            0x0A | 0x2A | 0x4A | 0x6A => "A".to_string(),
+            _ => String::new(),
+        },
+        AddressMode::IMM => format!("#${:02X}", byte),
+        AddressMode::ZP0 => format!(
+            "{} = {:02X}",
+            operand_name(cpu, byte as u16, 2),
+            cpu.bus_read(byte as u16, true)
+        ),
+        AddressMode::ZPX | AddressMode::ZPY => {
+            let (register, index) = if instruction.addres_mode == AddressMode::ZPX {
+                ("X", cpu.x)
+            } else {
+                ("Y", cpu.y)
+            };
+            let addres = byte.wrapping_add(index);
+            format!(
+                "{},{} @ {:02X} = {:02X}",
+                operand_name(cpu, byte as u16, 2),
+                register,
+                addres,
+                cpu.bus_read(addres as u16, true)
+            )
+        }
+        AddressMode::ABS => match name {
+            "JMP" | "JSR" => operand_name(cpu, word, 4),
+            _ => format!(
+                "{} = {:02X}",
+                operand_name(cpu, word, 4),
+                cpu.bus_read(word, true)
+            ),
+        },
+        AddressMode::ABX | AddressMode::ABY => {
+            let (register, index) = if instruction.addres_mode == AddressMode::ABX {
+                ("X", cpu.x)
+            } else {
+                ("Y", cpu.y)
+            };
+            let addres = word.wrapping_add(index as u16);
+            format!(
+                "{},{} @ {:04X} = {:02X}",
+                operand_name(cpu, word, 4),
+                register,
+                addres,
+                cpu.bus_read(addres, true)
+            )
+        }
+        AddressMode::IND => format!(
+            "({}) = {:04X}",
+            operand_name(cpu, word, 4),
+            read_pointer(cpu, word)
+        ),
+        AddressMode::IZX => {
+            let pointer = byte.wrapping_add(cpu.x);
+            let addres = read_pointer(cpu, pointer as u16);
+            format!(
+                "({},X) @ {:02X} = {:04X} = {:02X}",
+                operand_name(cpu, byte as u16, 2),
+                pointer,
+                addres,
+                cpu.bus_read(addres, true)
+            )
+        }
+        AddressMode::IZY => {
+            let base = read_pointer(cpu, byte as u16);
+            let addres = base.wrapping_add(cpu.y as u16);
+            format!(
+                "({}),Y = {:04X} @ {:04X} = {:02X}",
+                operand_name(cpu, byte as u16, 2),
+                base,
+                addres,
+                cpu.bus_read(addres, true)
+            )
+        }
+        AddressMode::REL => {
+            let target = pc.wrapping_add(2).wrapping_add(byte as i8 as u16);
+            operand_name(cpu, target, 4)
+        }
+        _ => String::new(),
+    };
+
+    if operand.is_empty() {
+        name.to_string()
+    } else {
+        format!("{} {}", name, operand)
+    }
+}
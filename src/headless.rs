@@ -0,0 +1,265 @@
+use std::fs;
+use std::path::Path;
+
+use crate::nes::{FrameResult, Nes};
+
+// Modo headless ================================================
+// Roda a ROM sem abrir janela, o mais rápido possível, por um número
+// fixo de frames. A entrada dos controles vem de um movie (.fm2 do
+// FCEUX) e cada frame pode ser salvo em PNG e/ou resumido em um hash
+// da tela e da RAM. Os hashes podem ser comparados com arquivos
+// "golden" salvos antes, assim qualquer mudança na PPU/CPU que altere
+// a imagem ou o estado do jogo aparece como uma diferença.
+//
+// confira: https://fceux.com/web/help/fm2.html
+
+// Arquivo com um hash por frame dentro da pasta de dump / golden
+const HASHES_FILE: &str = "hashes.txt";
+
+#[derive(Clone, Copy)]
+pub struct MovieFrame {
+    pub pad1: u8,
+    pub pad2: u8,
+    pub reset: bool,
+}
+
+pub struct Movie {
+    pub frames: Vec<MovieFrame>,
+}
+
+impl Movie {
+    pub fn from_file(file_name: &str) -> Result<Movie, String> {
+        let text = fs::read_to_string(file_name).map_err(|e| format!("{}: {}", file_name, e))?;
+        Movie::from_fm2(&text).map_err(|e| format!("{}: {}", file_name, e))
+    }
+
+    /** Lê as linhas de entrada de um .fm2: "|comandos|RLDUTSBA|RLDUTSBA|porta 2|".
+    As linhas do cabeçalho (sem "|" no início) são ignoradas */
+    pub fn from_fm2(text: &str) -> Result<Movie, String> {
+        let mut frames = vec![];
+
+        for (number, line) in text.lines().enumerate() {
+            if !line.starts_with('|') {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split('|').collect();
+            if fields.len() < 4 {
+                return Err(format!("line {}: invalid input line", number + 1));
+            }
+
+            // bit 0 = reset, bit 1 = power
+            let commands: u8 = fields[1].trim().parse().unwrap_or(0);
+            frames.push(MovieFrame {
+                pad1: fm2_buttons(fields[2]),
+                pad2: fm2_buttons(fields[3]),
+                reset: commands & 0x03 != 0,
+            });
+        }
+
+        Ok(Movie { frames })
+    }
+}
+
+// Os botões do .fm2 estão na ordem RLDUTSBA, que é a ordem dos bits 0-7 do
+// registrador do controle. Qualquer caractere diferente de '.' e ' ' é pressionado
+fn fm2_buttons(field: &str) -> u8 {
+    field
+        .chars()
+        .take(8)
+        .enumerate()
+        .filter(|(_, button)| *button != '.' && *button != ' ')
+        .fold(0, |reg, (bit, _)| reg | (1 << bit))
+}
+
+// FNV-1a de 64 bits, estável entre versões do Rust (o hasher padrão não garante isso)
+fn fnv1a(data: impl Iterator<Item = u8>) -> u64 {
+    data.fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/** Hash de um frame: "frame tela ram" */
+pub fn frame_hash(frame: u64, nes: &Nes) -> String {
+    let screen = fnv1a(nes.screen_indices().iter().flat_map(|i| i.to_le_bytes()));
+    let ram = fnv1a(nes.ram().iter().copied());
+    format!("{:06} {:016x} {:016x}", frame, screen, ram)
+}
+
+pub struct Headless {
+    pub nes: Nes,
+    pub frames: u64,
+    pub movie: Option<Movie>,
+    /** Pasta onde os hashes (e os PNGs, se png = true) são salvos */
+    pub dump: Option<String>,
+    pub png: bool,
+    /** Pasta com os hashes esperados */
+    pub golden: Option<String>,
+    /** Em vez de comparar, salva os hashes na pasta golden */
+    pub update_golden: bool,
+}
+
+impl Headless {
+    pub fn new(nes: Nes, frames: u64) -> Headless {
+        Headless {
+            nes,
+            frames,
+            movie: None,
+            dump: None,
+            png: false,
+            golden: None,
+            update_golden: false,
+        }
+    }
+
+    /** Executa os frames, retorna false se a comparação com os golden falhou */
+    pub fn start(&mut self) -> Result<bool, String> {
+        if let Some(dump) = &self.dump {
+            fs::create_dir_all(dump).map_err(|e| format!("{}: {}", dump, e))?;
+        }
+
+        self.nes.reset();
+        let mut hashes = vec![];
+
+        for frame in 0..self.frames {
+            if let Some(input) = self
+                .movie
+                .as_ref()
+                .and_then(|movie| movie.frames.get(frame as usize))
+            {
+                if input.reset {
+                    self.nes.reset();
+                }
+                self.nes.set_pads(input.pad1, input.pad2);
+            }
+
+            if !self.run_frame(frame) {
+                println!("[headless] CPU halted at frame {}", frame);
+                break;
+            }
+
+            hashes.push(frame_hash(frame, &self.nes));
+
+            if let (Some(dump), true) = (&self.dump, self.png) {
+                let file_name = format!("{}/frame_{:06}.png", dump, frame);
+                self.nes.screenshot(&file_name)?;
+            }
+        }
+        self.nes.stop_recording();
+        self.nes.stop_trace();
+        self.nes.stop_cdl();
+
+        let text = hashes.join("\n") + "\n";
+        if let Some(dump) = &self.dump {
+            write_hashes(dump, &text)?;
+            println!("[headless] {} frames dumped to {}", hashes.len(), dump);
+        }
+
+        match &self.golden {
+            Some(golden) if self.update_golden => {
+                fs::create_dir_all(golden).map_err(|e| format!("{}: {}", golden, e))?;
+                write_hashes(golden, &text)?;
+                println!("[headless] golden updated: {}", golden);
+                Ok(true)
+            }
+            Some(golden) => compare_golden(golden, &hashes),
+            None => Ok(true),
+        }
+    }
+
+    // Executa um frame inteiro: as paradas do debugger (--break) só são mostradas e a
+    // emulação continua até o fim do frame, assim o hash é sempre de um frame completo.
+    // Retorna false se a CPU travou
+    fn run_frame(&mut self, frame: u64) -> bool {
+        loop {
+            match self.nes.run_frame() {
+                FrameResult::Complete => return true,
+                FrameResult::Halted => return false,
+                FrameResult::Break => {
+                    println!("[headless] debugger break at frame {}, continuing", frame);
+                    self.nes.debugger.resume();
+                }
+            }
+        }
+    }
+}
+
+fn write_hashes(folder: &str, text: &str) -> Result<(), String> {
+    let path = Path::new(folder).join(HASHES_FILE);
+    fs::write(&path, text).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/** Compara os hashes gerados com os da pasta golden e mostra os frames diferentes */
+pub fn compare_golden(golden: &str, hashes: &[String]) -> Result<bool, String> {
+    let path = Path::new(golden).join(HASHES_FILE);
+    let expected = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let expected: Vec<&str> = expected.lines().collect();
+
+    let mut failures = 0;
+    for (frame, hash) in hashes.iter().enumerate() {
+        match expected.get(frame) {
+            Some(expected) if *expected == hash => {}
+            Some(expected) => {
+                if failures == 0 {
+                    println!("[golden] first difference:");
+                    println!("[golden]   expected: {}", expected);
+                    println!("[golden]   result:   {}", hash);
+                }
+                failures += 1;
+            }
+            None => {
+                println!("[golden] frame {} not in {}", frame, path.display());
+                failures += 1;
+                break;
+            }
+        }
+    }
+
+    if hashes.len() < expected.len() {
+        println!(
+            "[golden] only {} of {} frames were run",
+            hashes.len(),
+            expected.len()
+        );
+        failures += 1;
+    }
+
+    if failures == 0 {
+        println!("[golden] {} frames match {}", hashes.len(), golden);
+    } else {
+        println!("[golden] {} frames differ from {}", failures, golden);
+    }
+    Ok(failures == 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ROM feita para os testes (fonte em tests/roms/ppu_test.s), os hashes esperados são
+    // atualizados com:
+    //   cargo run --release -- --rom tests/roms/ppu_test.nes --headless --frames 160
+    //     --golden tests/golden/ppu_test --update-golden
+    const PPU_TEST_ROM: &str = "tests/roms/ppu_test.nes";
+    const PPU_TEST_GOLDEN: &str = "tests/golden/ppu_test";
+    const PPU_TEST_FRAMES: u64 = 160;
+
+    #[test]
+    fn ppu_test_matches_golden() {
+        let nes = Nes::new_with_cartridge(PPU_TEST_ROM);
+        let mut headless = Headless::new(nes, PPU_TEST_FRAMES);
+        headless.golden = Some(PPU_TEST_GOLDEN.to_string());
+        assert_eq!(headless.start(), Ok(true));
+    }
+
+    #[test]
+    fn fm2_buttons() {
+        let movie =
+            Movie::from_fm2("version 3\n|0|R.......|........||\n|1|...UT.BA|.L......||\n").unwrap();
+        assert_eq!(movie.frames.len(), 2);
+        assert_eq!((movie.frames[0].pad1, movie.frames[0].reset), (0x01, false));
+        assert_eq!(movie.frames[1].pad1, 0x08 | 0x10 | 0x40 | 0x80);
+        assert_eq!(movie.frames[1].pad2, 0x02);
+        assert!(movie.frames[1].reset);
+    }
+}
@@ -1,19 +1,45 @@
+// Sem a janela sobram funções de debug e exportação que só ela usa
+#![cfg_attr(not(feature = "window"), allow(dead_code, unused_imports))]
+
 mod bus;
 mod cartridge;
 mod cpu;
+mod cpu_test;
 mod custom_game;
+mod debugger;
+mod headless;
 mod mapper;
 mod nes;
 mod pad;
 mod ppu;
+mod terminal;
 mod video;
 
+use cpu_test::{KlausSuite, KlausTest, SingleStepTest};
+use debugger::{Disassembler, TerminalDebugger, TraceFormat, TraceOptions};
+use headless::{Headless, Movie};
 use nes::Nes;
-use std::env;
+use std::{env, process};
+use terminal::{Terminal, HOLD_FRAMES};
+use video::{AspectMode, NtscParams, NtscPreset, Overscan, Palette, RecordFormat, Scaler};
 
 enum GAME_MODE {
     ROM,
     CUSTOM,
+    KLAUS,
+    SINGLE_STEP,
+}
+
+// Lê um endereço em hexadecimal nos formatos "0400", "0x0400" ou "$0400"
+fn parse_addres(value: &str) -> u16 {
+    let hex = value.trim_start_matches("0x").trim_start_matches('$');
+    u16::from_str_radix(hex, 16).unwrap_or_else(|_| panic!("invalid address: {}", value))
+}
+
+fn parse_float(value: &str) -> f32 {
+    value
+        .parse()
+        .unwrap_or_else(|_| panic!("invalid number: {}", value))
 }
 
 fn main() {
@@ -28,6 +54,37 @@ fn main() {
     let mut mode = GAME_MODE::ROM;
     let mut debug = false;
     let mut game = "snake";
+    let mut suite = KlausSuite::Functional;
+    let mut test_file = "";
+    let mut load: Option<u16> = None;
+    let mut start: Option<u16> = None;
+    let mut success: Option<u16> = None;
+    let mut palette_file = "";
+    let mut ntsc = NtscParams::new();
+    let mut filter: Option<NtscPreset> = None;
+    let mut scaler: Option<Scaler> = None;
+    let mut crt = false;
+    let mut overscan = Overscan::new();
+    let mut aspect = AspectMode::Square;
+    let mut record = "";
+    let mut record_format = RecordFormat::Avi;
+    let mut headless = false;
+    let mut frames: u64 = 600;
+    let mut movie = "";
+    let mut dump = "";
+    let mut dump_png = false;
+    let mut golden = "";
+    let mut update_golden = false;
+    let mut breakpoints: Vec<&str> = vec![];
+    let mut break_nmi = false;
+    let mut break_irq = false;
+    let mut tui = false;
+    let mut terminal = false;
+    let mut hold_frames = HOLD_FRAMES;
+    let mut trace = "";
+    let mut disasm = "";
+    let mut cdl = "";
+    let mut trace_options = TraceOptions::new();
 
     for i in 0..args.len() {
         let arg = &args[i][..];
@@ -45,22 +102,311 @@ fn main() {
             game = &args[i + 1];
             mode = GAME_MODE::CUSTOM;
         }
+
+        if arg == "--klaus" && i + 2 < args.len() {
+            suite = KlausSuite::from(&args[i + 1]).expect("invalid klaus suite");
+            test_file = &args[i + 2];
+            mode = GAME_MODE::KLAUS;
+        }
+
+        if arg == "--single-step" && i + 1 < args.len() {
+            test_file = &args[i + 1];
+            mode = GAME_MODE::SINGLE_STEP;
+        }
+
+        if arg == "--load" && i + 1 < args.len() {
+            load = Some(parse_addres(&args[i + 1]));
+        }
+
+        if arg == "--start" && i + 1 < args.len() {
+            start = Some(parse_addres(&args[i + 1]));
+        }
+
+        if arg == "--success" && i + 1 < args.len() {
+            success = Some(parse_addres(&args[i + 1]));
+        }
+
+        // Paleta: arquivo .pal ou "ntsc" para usar o gerador
+        if arg == "--palette" && i + 1 < args.len() {
+            palette_file = &args[i + 1];
+        }
+
+        if arg == "--ntsc" && i + 1 < args.len() {
+            filter = Some(NtscPreset::from(&args[i + 1]).expect("invalid ntsc preset"));
+        }
+
+        if arg == "--scaler" && i + 1 < args.len() {
+            scaler = Some(Scaler::from(&args[i + 1]).expect("invalid scaler"));
+        }
+
+        if arg == "--crt" {
+            crt = true;
+        }
+
+        // Bordas escondidas: "8" ou "topo,baixo,esquerda,direita"
+        if arg == "--overscan" && i + 1 < args.len() {
+            overscan = Overscan::from(&args[i + 1]).unwrap_or_else(|e| panic!("{}", e));
+        }
+
+        if arg == "--aspect" && i + 1 < args.len() {
+            aspect = AspectMode::from(&args[i + 1]).expect("invalid aspect mode");
+        }
+
+        // Grava desde o primeiro frame, o formato vem da extensão (.y4m, .avi ou .gif)
+        if arg == "--record" && i + 1 < args.len() {
+            record = &args[i + 1];
+        }
+
+        if arg == "--record-format" && i + 1 < args.len() {
+            record_format = RecordFormat::from(&args[i + 1]).expect("invalid record format");
+        }
+
+        // Sem janela: --headless --frames N --input movie.fm2 --dump pasta/
+        if arg == "--headless" {
+            headless = true;
+        }
+
+        if arg == "--frames" && i + 1 < args.len() {
+            frames = args[i + 1]
+                .parse()
+                .unwrap_or_else(|_| panic!("invalid number: {}", args[i + 1]));
+        }
+
+        if arg == "--input" && i + 1 < args.len() {
+            movie = &args[i + 1];
+        }
+
+        if arg == "--dump" && i + 1 < args.len() {
+            dump = &args[i + 1];
+        }
+
+        if arg == "--dump-png" {
+            dump_png = true;
+        }
+
+        if arg == "--golden" && i + 1 < args.len() {
+            golden = &args[i + 1];
+        }
+
+        if arg == "--update-golden" {
+            update_golden = true;
+        }
+
+        if arg == "--break" && i + 1 < args.len() {
+            breakpoints.push(&args[i + 1]);
+        }
+
+        if arg == "--break-nmi" {
+            break_nmi = true;
+        }
+
+        if arg == "--break-irq" {
+            break_irq = true;
+        }
+
+        // Debugger no terminal, sem janela
+        if arg == "--tui" {
+            tui = true;
+        }
+
+        // Joga no terminal, com a tela em caracteres coloridos
+        if arg == "--terminal" {
+            terminal = true;
+        }
+
+        // Frames que um botão fica apertado em cada tecla do terminal
+        if arg == "--hold-frames" && i + 1 < args.len() {
+            hold_frames = args[i + 1]
+                .parse()
+                .unwrap_or_else(|_| panic!("invalid number: {}", args[i + 1]));
+        }
+
+        // Trace das instruções: --trace arquivo.log [--trace-format nestest|mesen|"{PC} ..."]
+        if arg == "--trace" && i + 1 < args.len() {
+            trace = &args[i + 1];
+        }
+
+        if arg == "--trace-format" && i + 1 < args.len() {
+            trace_options.format =
+                TraceFormat::from(&args[i + 1]).unwrap_or_else(|e| panic!("{}", e));
+        }
+
+        if arg == "--trace-start" && i + 1 < args.len() {
+            trace_options.start = Some(parse_addres(&args[i + 1]));
+        }
+
+        if arg == "--trace-stop" && i + 1 < args.len() {
+            trace_options.stop = Some(parse_addres(&args[i + 1]));
+        }
+
+        // Disassembly da ROM inteira para o ca65, sem executar o jogo
+        if arg == "--disasm" && i + 1 < args.len() {
+            disasm = &args[i + 1];
+        }
+
+        // Code/Data Logger no formato do FCEUX, também usado pelo --disasm
+        if arg == "--cdl" && i + 1 < args.len() {
+            cdl = &args[i + 1];
+        }
+
+        if arg == "--hue" && i + 1 < args.len() {
+            ntsc.hue = parse_float(&args[i + 1]);
+        }
+
+        if arg == "--saturation" && i + 1 < args.len() {
+            ntsc.saturation = parse_float(&args[i + 1]);
+        }
+
+        if arg == "--contrast" && i + 1 < args.len() {
+            ntsc.contrast = parse_float(&args[i + 1]);
+        }
+
+        if arg == "--brightness" && i + 1 < args.len() {
+            ntsc.brightness = parse_float(&args[i + 1]);
+        }
+
+        if arg == "--gamma" && i + 1 < args.len() {
+            ntsc.gamma = parse_float(&args[i + 1]);
+        }
     }
 
     match mode {
+        GAME_MODE::ROM if !disasm.is_empty() => {
+            let cdl = if cdl.is_empty() { None } else { Some(cdl) };
+            let disassembler =
+                Disassembler::from_file(rom, cdl).unwrap_or_else(|e| panic!("{}", e));
+            disassembler
+                .save(disasm)
+                .unwrap_or_else(|e| panic!("{}", e));
+            println!("[disasm] {} labels: {}", disassembler.labels.len(), disasm);
+        }
+        GAME_MODE::ROM if tui => {
+            let mut terminal = TerminalDebugger::new(rom);
+            for breakpoint in &breakpoints {
+                terminal
+                    .debugger
+                    .add_breakpoint(breakpoint, &terminal.cpu.symbols)
+                    .unwrap_or_else(|e| panic!("{}", e));
+            }
+            terminal.debugger.break_on_nmi = break_nmi;
+            terminal.debugger.break_on_irq = break_irq;
+            terminal.start();
+        }
         GAME_MODE::ROM => {
             let mut nes = Nes::new_with_cartridge(rom);
             nes.debug = debug;
-            nes.start();
+            match palette_file {
+                "" => {}
+                "ntsc" => nes.set_palette(Palette::generate(&ntsc)),
+                file => {
+                    nes.set_palette(Palette::from_file(file).unwrap_or_else(|e| panic!("{}", e)))
+                }
+            }
+            nes.set_ntsc_params(ntsc);
+            nes.set_filter(filter);
+            nes.set_scaler(scaler);
+            nes.set_crt(crt);
+            nes.set_overscan(overscan);
+            nes.set_aspect(aspect);
+            nes.set_record_format(record_format);
+            for breakpoint in &breakpoints {
+                nes.add_breakpoint(breakpoint)
+                    .unwrap_or_else(|e| panic!("{}", e));
+            }
+            for (index, breakpoint) in nes.debugger.breakpoints().iter().enumerate() {
+                println!("[debugger] #{} {}", index, breakpoint.describe());
+            }
+            nes.debugger.break_on_nmi = break_nmi;
+            nes.debugger.break_on_irq = break_irq;
+            if !record.is_empty() {
+                let format = RecordFormat::from_file_name(record).expect("invalid record format");
+                nes.start_recording(record, format)
+                    .unwrap_or_else(|e| panic!("{}", e));
+            }
+
+            nes.set_trace_options(trace_options);
+            if !trace.is_empty() {
+                nes.start_trace(trace).unwrap_or_else(|e| panic!("{}", e));
+            }
+            if !cdl.is_empty() {
+                nes.start_cdl(cdl).unwrap_or_else(|e| panic!("{}", e));
+            }
+
+            if terminal {
+                let mut terminal = Terminal::new(nes);
+                terminal.hold_frames = hold_frames;
+                terminal.start().unwrap_or_else(|e| panic!("{}", e));
+                terminal.video.stop_recording();
+                terminal.video.stop_trace();
+                terminal.video.stop_cdl();
+                return;
+            }
+
+            #[cfg(feature = "window")]
+            if !headless {
+                nes.start();
+                return;
+            }
+
+            #[cfg(not(feature = "window"))]
+            if !headless {
+                eprintln!("built without the \"window\" feature, use --terminal or --headless");
+                process::exit(1);
+            }
+
+            let mut runner = Headless::new(nes, frames);
+            if !movie.is_empty() {
+                runner.movie = Some(Movie::from_file(movie).unwrap_or_else(|e| panic!("{}", e)));
+            }
+            if !dump.is_empty() {
+                runner.dump = Some(dump.to_string());
+            }
+            runner.png = dump_png;
+            if !golden.is_empty() {
+                runner.golden = Some(golden.to_string());
+            }
+            runner.update_golden = update_golden;
+
+            if !runner.start().unwrap_or_else(|e| panic!("{}", e)) {
+                process::exit(1);
+            }
         }
         GAME_MODE::CUSTOM => {
             match game {
+                "snake" if terminal => {
+                    let mut terminal = Terminal::new(custom_game::SnakeGame::new());
+                    terminal.hold_frames = hold_frames;
+                    terminal.start().unwrap_or_else(|e| panic!("{}", e));
+                }
+                #[cfg(feature = "window")]
                 "snake" => {
                     let mut nes = custom_game::SnakeGame::new();
                     nes.start();
                 }
+                #[cfg(not(feature = "window"))]
+                "snake" => {
+                    eprintln!("built without the \"window\" feature, use --terminal");
+                    process::exit(1);
+                }
                 _ => panic!("invalid custom game"),
             };
         }
+        GAME_MODE::KLAUS => {
+            let mut test =
+                KlausTest::new(suite, test_file, load.unwrap_or(suite.load_addres()));
+            if success.is_some() {
+                test.success = success;
+            }
+
+            if !test.start(start.unwrap_or(suite.start_addres())) {
+                process::exit(1);
+            }
+        }
+        GAME_MODE::SINGLE_STEP => {
+            let mut test = SingleStepTest::new();
+            if !test.start(test_file) {
+                process::exit(1);
+            }
+        }
     }
 }
@@ -3,6 +3,7 @@ use super::mappers;
 #[derive(Clone, Copy)]
 pub enum MapperType {
     Mapper000,
+    Flat,
 }
 
 #[derive(Clone, Copy)]
@@ -17,12 +18,14 @@ impl Mapper {
     pub fn cpu_map_read(&mut self, addr: u16) -> (bool, u32) {
         match self.mapper_type {
             MapperType::Mapper000 => mappers::cpu_map_read(self, addr),
+            MapperType::Flat => mappers::flat_cpu_map_read(self, addr),
         }
     }
 
     pub fn cpu_map_write(&mut self, addr: u16, data: u8) -> (bool, u32) {
         match self.mapper_type {
             MapperType::Mapper000 => mappers::cpu_map_write(self, addr, data),
+            MapperType::Flat => mappers::flat_cpu_map_write(self, addr, data),
         }
     }
 
@@ -30,11 +33,13 @@ impl Mapper {
     pub fn ppu_map_read(&mut self, addr: u16) -> (bool, u32) {
         match self.mapper_type {
             MapperType::Mapper000 => mappers::ppu_map_read(self, addr),
+            MapperType::Flat => mappers::flat_ppu_map_read(self, addr),
         }
     }
     pub fn ppu_map_write(&mut self, addr: u16) -> (bool, u32) {
         match self.mapper_type {
             MapperType::Mapper000 => mappers::ppu_map_write(self, addr),
+            MapperType::Flat => mappers::flat_ppu_map_write(self, addr),
         }
     }
 
@@ -50,9 +55,18 @@ impl Mapper {
         }
     }
 
+    pub fn create_mapper_flat() -> Mapper {
+        Mapper {
+            prg_banks: 0,
+            chr_banks: 0,
+            mapper_type: MapperType::Flat,
+        }
+    }
+
     pub fn get_type(&self) -> &'static str {
         match self.mapper_type {
             MapperType::Mapper000 => "Mapper000",
+            MapperType::Flat => "Flat",
         }
     }
 }
@@ -0,0 +1,23 @@
+use super::super::Mapper;
+
+// Mapper "flat" não existe em nenhum cartucho real, ele é usado para rodar
+// programas de teste do 6502 (ex: Klaus Dormann) que esperam 64KB de memória
+// plana, ou seja, todo o barramento da CPU é mapeado direto para a PRG memory
+//     CPU Address Bus          PRG memory
+//     0x0000 -> 0xFFFF: Map    0x0000 -> 0xFFFF
+pub fn flat_cpu_map_read(_mapper: &mut Mapper, addr: u16) -> (bool, u32) {
+    (true, addr as u32)
+}
+
+pub fn flat_cpu_map_write(_mapper: &mut Mapper, addr: u16, _data: u8) -> (bool, u32) {
+    (true, addr as u32)
+}
+
+// Não existe PPU conectada em memória plana
+pub fn flat_ppu_map_read(_mapper: &mut Mapper, _addr: u16) -> (bool, u32) {
+    (false, 0)
+}
+
+pub fn flat_ppu_map_write(_mapper: &mut Mapper, _addr: u16) -> (bool, u32) {
+    (false, 0)
+}
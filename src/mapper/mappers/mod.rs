@@ -1,3 +1,5 @@
 mod mapper_000;
+mod mapper_flat;
 
 pub use mapper_000::*;
+pub use mapper_flat::*;
@@ -0,0 +1,369 @@
+#[cfg(feature = "window")]
+mod window;
+
+use crate::cartridge::Cartridge;
+use crate::debugger::{Debugger, TraceLogger, TraceOptions};
+use crate::pad::PadButton;
+use crate::ppu::{SCREEN_HEIGHT, SCREEN_WIDTH};
+use crate::video::{
+    AspectMode, Image, NtscFilter, NtscParams, NtscPreset, Overscan, Palette, PostProcess,
+    RecordFormat, Recorder, Scaler, Video,
+};
+use crate::{bus::Bus, cpu::Cpu6502};
+
+#[cfg(feature = "window")]
+use window::WindowState;
+
+/** Como terminou a execução de um frame */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FrameResult {
+    Complete,
+    /** O debugger parou no meio do frame */
+    Break,
+    /** A CPU travou em um JAM */
+    Halted,
+}
+
+pub struct Nes {
+    pub debug: bool,
+    cpu: Cpu6502,
+    filter: Option<NtscFilter>,
+    ntsc_params: NtscParams,
+    scaler: Option<Scaler>,
+    crt: bool,
+    overscan: Overscan,
+    aspect: AspectMode,
+    recorder: Option<Recorder>,
+    record_format: RecordFormat,
+    trace: Option<TraceLogger>,
+    trace_options: TraceOptions,
+    palette_table: u8,
+    cartridge: String,
+    running: bool,
+    history: Vec<String>,
+    #[cfg(feature = "window")]
+    window: WindowState,
+    pub debugger: Debugger,
+}
+
+impl Nes {
+    fn push_history(&mut self) {
+        if self.history.len() == 5 {
+            self.history.remove(0);
+        }
+        self.history.push(self.cpu.disassemble_instruction());
+    }
+}
+
+impl Video for Nes {
+    fn main_loop(&mut self) {
+        if self.running {
+            self.run_frame();
+        }
+    }
+
+    fn screen_image(&mut self) -> Image {
+        Nes::screen_image(self)
+    }
+
+    fn on_pad_press(&mut self, button: PadButton) {
+        self.cpu.bus.pad1.press_button(button);
+    }
+
+    fn on_pad_release(&mut self, button: PadButton) {
+        self.cpu.bus.pad1.release_button(button);
+    }
+
+    fn toggle_pause(&mut self) {
+        self.running = !self.running;
+        self.debugger.resume();
+    }
+
+    fn reset(&mut self) {
+        Nes::reset(self);
+    }
+
+    fn is_running(&self) -> bool {
+        self.running
+    }
+}
+
+impl Nes {
+    pub fn new_with_cartridge(file_name: &str) -> Nes {
+        let cartridge = Cartridge::new(file_name.to_string());
+        let bus = Bus::new(cartridge);
+        let mut cpu = Cpu6502::new_with_bus(bus);
+        cpu.load_symbols(file_name);
+        Nes {
+            cpu,
+            filter: None,
+            ntsc_params: NtscParams::new(),
+            scaler: None,
+            crt: false,
+            overscan: Overscan::new(),
+            aspect: AspectMode::Square,
+            recorder: None,
+            record_format: RecordFormat::Avi,
+            trace: None,
+            trace_options: TraceOptions::new(),
+            cartridge: file_name.to_string(),
+            running: false,
+            palette_table: 0,
+            history: vec![],
+            #[cfg(feature = "window")]
+            window: WindowState::new(),
+            debugger: Debugger::new(),
+            debug: false,
+        }
+    }
+
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.cpu.bus.ppu.palette = palette;
+    }
+
+    pub fn set_ntsc_params(&mut self, params: NtscParams) {
+        self.ntsc_params = params;
+        if let Some(filter) = &mut self.filter {
+            filter.params = params;
+        }
+    }
+
+    /** Liga o filtro NTSC com um preset ou desliga (None), a tela muda de largura */
+    pub fn set_filter(&mut self, preset: Option<NtscPreset>) {
+        self.filter = preset.map(|preset| NtscFilter::new(preset, self.ntsc_params));
+    }
+
+    /** Escala aplicada depois da paleta/filtro NTSC (None desliga) */
+    pub fn set_scaler(&mut self, scaler: Option<Scaler>) {
+        self.scaler = scaler;
+    }
+
+    /** Liga ou desliga o efeito de CRT, aplicado depois da escala */
+    pub fn set_crt(&mut self, crt: bool) {
+        self.crt = crt;
+    }
+
+    pub fn set_overscan(&mut self, overscan: Overscan) {
+        self.overscan = overscan;
+    }
+
+    pub fn set_aspect(&mut self, aspect: AspectMode) {
+        self.aspect = aspect;
+    }
+
+    /** Imagem do frame atual como é mostrada: overscan cortado, filtros de escala e
+    esticada para a proporção. É a imagem usada ao exportar a tela */
+    pub fn screen_image(&mut self) -> Image {
+        let image = self.post_process().apply(self.frame_image());
+        let width = self.overscan.visible_width();
+        let height = self.overscan.visible_height();
+        self.aspect.apply(&image, width, height)
+    }
+
+    /** Salva a tela atual em PNG */
+    pub fn screenshot(&mut self, path: &str) -> Result<(), String> {
+        self.screen_image().save_png(path)
+    }
+
+    /** Salva uma pattern table (0 ou 1) em PNG, com a paleta selecionada no debug */
+    pub fn save_pattern_table(&mut self, table: i8, path: &str) -> Result<(), String> {
+        self.cpu
+            .bus
+            .ppu
+            .get_pattern_table(table, self.palette_table)
+            .save_png(path)
+    }
+
+    /** Executa a emulação até o fim do frame atual, até o debugger parar ou até a CPU
+    travar (JAM). Depois de uma parada do debugger a próxima chamada continua o frame */
+    pub fn run_frame(&mut self) -> FrameResult {
+        loop {
+            // Antes do clock, para a primeira instrução depois do reset também aparecer
+            if let Some(trace) = &mut self.trace {
+                if let Err(e) = trace.clock(&mut self.cpu) {
+                    eprintln!("[nes] trace failed: {}", e);
+                    self.stop_trace();
+                }
+            }
+
+            self.cpu.clock();
+
+            if self.debug && self.cpu.complete() && self.cpu.bus.system_clock_counter % 3 == 0 {
+                self.push_history();
+            }
+
+            // Breakpoint, watchpoint ou fim de um passo: pausa no meio do frame
+            if self.debugger.is_active() {
+                if let Some(reason) = self.debugger.check(&mut self.cpu) {
+                    self.running = false;
+                    println!("[debugger] {}", self.debugger.describe(&reason));
+                    println!("[debugger] {}", self.cpu.registers_dump());
+                    return FrameResult::Break;
+                }
+            }
+
+            // JAM: a CPU travou, pausa a emulação e mostra os registradores
+            if self.cpu.halted {
+                self.running = false;
+                eprintln!(
+                    "[cpu] JAM ${:02X} at ${:04X}, press R to reset",
+                    self.cpu.opcode,
+                    self.cpu.pc.wrapping_sub(1)
+                );
+                eprintln!("[cpu] {}", self.cpu.registers_dump());
+                return FrameResult::Halted;
+            }
+
+            if self.cpu.bus.ppu.frame_complete {
+                break;
+            }
+        }
+
+        self.record_frame();
+        if let Some(trace) = &mut self.trace {
+            trace.end_frame();
+        }
+        self.cpu.bus.ppu.frame_complete = false;
+        FrameResult::Complete
+    }
+
+    pub fn reset(&mut self) {
+        self.cpu.reset();
+    }
+
+    /** Estado dos botões dos controles (bit 7 = A ... bit 0 = Right), usado pelos movies */
+    pub fn set_pads(&mut self, pad1: u8, pad2: u8) {
+        self.cpu.bus.pad1.set_reg(pad1);
+        self.cpu.bus.pad2.set_reg(pad2);
+    }
+
+    /** RAM interna de 2KB */
+    pub fn ram(&self) -> &[u8] {
+        &self.cpu.bus.ram
+    }
+
+    /** Tela atual em índices de 9 bits da paleta */
+    pub fn screen_indices(&self) -> &[u16] {
+        self.cpu.bus.ppu.get_screen()
+    }
+
+    /** Formato usado pela tecla de gravação */
+    pub fn set_record_format(&mut self, format: RecordFormat) {
+        self.record_format = format;
+    }
+
+    /** Começa a gravar todos os frames emulados a partir do próximo */
+    pub fn start_recording(&mut self, path: &str, format: RecordFormat) -> Result<(), String> {
+        self.stop_recording();
+
+        let image = self.screen_image();
+        self.recorder = Some(Recorder::start(path, format, image.width, image.height)?);
+        println!("[nes] recording: {}", path);
+        Ok(())
+    }
+
+    pub fn stop_recording(&mut self) {
+        if let Some(recorder) = self.recorder.take() {
+            let (path, frames, format) =
+                (recorder.path.clone(), recorder.frames(), recorder.format);
+            match recorder.finish() {
+                Ok(()) => println!("[nes] recorded {} frames ({:?}): {}", frames, format, path),
+                Err(e) => eprintln!("[nes] recording failed: {}", e),
+            }
+        }
+    }
+
+    /** Adiciona um breakpoint, que pode usar os nomes dos símbolos da ROM */
+    pub fn add_breakpoint(&mut self, text: &str) -> Result<usize, String> {
+        self.debugger.add_breakpoint(text, &self.cpu.symbols)
+    }
+
+    /** Formato e gatilhos usados pela tecla do trace */
+    pub fn set_trace_options(&mut self, options: TraceOptions) {
+        self.trace_options = options;
+    }
+
+    /** Começa a escrever cada instrução executada no arquivo */
+    pub fn start_trace(&mut self, path: &str) -> Result<(), String> {
+        self.stop_trace();
+
+        self.trace = Some(TraceLogger::start(path, self.trace_options.clone())?);
+        println!("[nes] trace: {}", path);
+        Ok(())
+    }
+
+    pub fn stop_trace(&mut self) {
+        if let Some(trace) = self.trace.take() {
+            let (path, lines) = (trace.path.clone(), trace.lines);
+            match trace.finish() {
+                Ok(()) => println!("[nes] traced {} instructions: {}", lines, path),
+                Err(e) => eprintln!("[nes] trace failed: {}", e),
+            }
+        }
+    }
+
+    /** Começa o Code/Data Logger, somando ao arquivo .cdl se ele já existe */
+    pub fn start_cdl(&mut self, path: &str) -> Result<(), String> {
+        self.stop_cdl();
+
+        self.cpu.start_cdl(path)?;
+        println!("[nes] cdl: {}", path);
+        Ok(())
+    }
+
+    pub fn stop_cdl(&mut self) {
+        match self.cpu.stop_cdl() {
+            Ok(summary) if summary.is_empty() => {}
+            Ok(summary) => println!("[nes] cdl {}", summary),
+            Err(e) => eprintln!("[nes] cdl failed: {}", e),
+        }
+    }
+
+    // Chamado no fim de cada frame emulado
+    fn record_frame(&mut self) {
+        if self.recorder.is_none() {
+            return;
+        }
+
+        let image = self.screen_image();
+        if let Some(recorder) = &mut self.recorder {
+            if let Err(e) = recorder.push_frame(&image) {
+                eprintln!("[nes] recording failed: {}", e);
+                self.stop_recording();
+            }
+        }
+    }
+
+    /** Imagem do frame atual (paleta ou filtro NTSC) com o overscan cortado */
+    fn frame_image(&mut self) -> Image {
+        let ppu = &self.cpu.bus.ppu;
+        let width = self.base_width();
+        let mut rgba = vec![0; width * SCREEN_HEIGHT * 4];
+        match &mut self.filter {
+            Some(filter) => {
+                filter.apply(ppu.get_screen(), &ppu.palette, ppu.frame_count, &mut rgba)
+            }
+            None => ppu.palette.to_rgba(ppu.get_screen(), &mut rgba),
+        }
+
+        self.overscan
+            .crop(&Image::from_rgba(width, SCREEN_HEIGHT, &rgba))
+    }
+
+    // Largura da imagem antes do pós-processamento
+    fn base_width(&self) -> usize {
+        match &self.filter {
+            Some(filter) => filter.output_width(),
+            None => SCREEN_WIDTH,
+        }
+    }
+
+    fn post_process(&self) -> PostProcess {
+        let mut post = PostProcess::new();
+        post.chain.extend(self.scaler);
+        if self.crt {
+            post.chain.push(Scaler::Crt);
+        }
+        post
+    }
+}
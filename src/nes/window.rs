@@ -0,0 +1,569 @@
+use graphics::{clear, Context};
+use piston::{Key, MouseButton};
+use piston_window::{G2d, G2dTextureContext, Glyphs};
+
+use super::Nes;
+use crate::debugger::{HexEditor, HexInput, HEX_EDITOR_COLUMNS};
+use crate::pad::PadButton;
+use crate::ppu::{PpuEvent, SCREEN_HEIGHT, SCREEN_WIDTH};
+use crate::video::{
+    draw_code, draw_cpu, draw_events, draw_memory, draw_name_tables, draw_ram, draw_sprite_boxes,
+    draw_sprites, draw_text, export_file_name, find_event, Frame, NameTableOptions, NtscPreset,
+    Scaler, ScreenArea, SpriteOptions, Video, Window, BLACK_PIXEL, EVENT_MAP_HEIGHT,
+    EVENT_MAP_WIDTH, NAME_TABLES_HEIGHT, NAME_TABLES_WIDTH, OAM_TABLE_HEIGHT, OAM_TABLE_WIDTH, RED,
+};
+
+// Janela do NES ================================================
+// Tela do jogo, painéis de debug e teclas de atalho na janela Piston.
+// Só existe com a feature "window", o resto do Nes não depende dela
+
+// Escala da tela do jogo na janela
+const SCREEN_SCALE: f64 = 2.7;
+// Canto do painel de debug, à direita da tela
+const DEBUG_X: usize = 720;
+const DEBUG_Y: usize = 10;
+// Linhas do editor hexadecimal
+const MEMORY_ROWS: usize = 36;
+
+// Painel mostrado no modo debug, trocado com a tecla V
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DebugView {
+    Cpu,
+    NameTables,
+    Sprites,
+    Events,
+    Memory,
+}
+
+impl DebugView {
+    fn next(&self) -> DebugView {
+        match self {
+            DebugView::Cpu => DebugView::NameTables,
+            DebugView::NameTables => DebugView::Sprites,
+            DebugView::Sprites => DebugView::Events,
+            DebugView::Events => DebugView::Memory,
+            DebugView::Memory => DebugView::Cpu,
+        }
+    }
+}
+
+/** O que só a janela usa: a textura da tela, o painel de debug e o mouse */
+pub struct WindowState {
+    screen: Frame,
+    ram_offset: u16,
+    memory: HexEditor,
+    view: DebugView,
+    tile_grid: bool,
+    attribute_grid: bool,
+    chosen_line: i16,
+    selected_event: Option<PpuEvent>,
+    mouse: (f64, f64),
+}
+
+impl WindowState {
+    pub fn new() -> WindowState {
+        WindowState {
+            screen: Frame::new(SCREEN_WIDTH, SCREEN_HEIGHT),
+            ram_offset: 0,
+            memory: HexEditor::new(MEMORY_ROWS),
+            view: DebugView::Cpu,
+            tile_grid: false,
+            attribute_grid: false,
+            chosen_line: 0,
+            selected_event: None,
+            mouse: (0.0, 0.0),
+        }
+    }
+}
+
+// Draws
+impl Nes {
+    fn draw_palette(&mut self, context: Context, gl: &mut G2d) {
+        // Draw Palettes & Pattern Tables ==============================================
+        let swatch_size = 8;
+        for p in 0..8 {
+            for s in 0..4 {
+                let x: u64 = 720 + p * (swatch_size * 5) + s * swatch_size;
+                let y: u64 = 380;
+
+                let pixel = graphics::rectangle::square(x as f64, y as f64, swatch_size as f64);
+
+                let color = self
+                    .cpu
+                    .bus
+                    .ppu
+                    .get_colour_from_palette_ram(p as u8, s as u8);
+                graphics::rectangle(color.get_color(), pixel, context.transform, gl);
+            }
+        }
+    }
+
+    fn draw_patterns(&mut self, context: Context, gl: &mut G2d) {
+        let ppu = &mut self.cpu.bus.ppu;
+        // Draw pattern
+        ppu.get_pattern_table(0, self.palette_table)
+            .render_image(720, 420, 1.8, context, gl);
+        ppu.get_pattern_table(1, self.palette_table)
+            .render_image(960, 420, 1.8, context, gl);
+    }
+
+    fn draw_screen(&mut self, context: Context, gl: &mut G2d) {
+        let area = self.screen_area();
+        self.window
+            .screen
+            .render_image_size(0, 0, area.width, area.height, context, gl);
+    }
+
+    // Tamanho em pixels da PPU depois do overscan, esticado para a proporção e
+    // encaixado no espaço da tela na janela
+    fn screen_area(&self) -> ScreenArea {
+        ScreenArea::new(
+            &self.overscan,
+            self.aspect,
+            SCREEN_WIDTH as f64 * SCREEN_SCALE,
+            SCREEN_HEIGHT as f64 * SCREEN_SCALE,
+        )
+    }
+
+    // Linha do visualizador de sprites e do "run to scanline": a linha embaixo do mouse,
+    // se ele estiver sobre a tela do jogo, ou a escolhida com [ e ]
+    fn selected_line(&self) -> i16 {
+        match self
+            .screen_area()
+            .to_ppu(self.window.mouse.0, self.window.mouse.1)
+        {
+            Some((_, y)) => y as i16,
+            None => self.window.chosen_line,
+        }
+    }
+
+    // Posição do mouse relativa ao canto do painel de debug, se estiver dentro de width x height
+    fn debug_hover(&self, width: usize, height: usize) -> Option<(usize, usize)> {
+        let x = self.window.mouse.0 - DEBUG_X as f64;
+        let y = self.window.mouse.1 - DEBUG_Y as f64;
+        if x < 0.0 || y < 0.0 || x >= width as f64 || y >= height as f64 {
+            return None;
+        }
+        Some((x as usize, y as usize))
+    }
+
+    // Teclas do editor hexadecimal, retorna se a tecla foi usada
+    fn memory_key(&mut self, key: Key) -> bool {
+        let editor = &mut self.window.memory;
+        let cpu = &mut self.cpu;
+        let digit = hex_digit(key);
+
+        // Digitando um endereço ou os bytes da busca
+        if editor.input != HexInput::None {
+            let input = match &mut editor.input {
+                HexInput::Goto(input) | HexInput::Search(input) => input,
+                HexInput::None => unreachable!(),
+            };
+            match key {
+                Key::Return => editor.submit(cpu),
+                // Backspace com o texto vazio desiste
+                Key::Backspace if input.is_empty() => editor.input = HexInput::None,
+                Key::Backspace => {
+                    input.pop();
+                }
+                Key::Space => input.push(' '),
+                _ => {
+                    if let Some(digit) = digit {
+                        input.push_str(&format!("{:X}", digit));
+                    }
+                }
+            }
+            return true;
+        }
+
+        let page = (editor.rows * HEX_EDITOR_COLUMNS) as isize;
+        match key {
+            Key::Up => editor.move_cursor(-(HEX_EDITOR_COLUMNS as isize), cpu),
+            Key::Down => editor.move_cursor(HEX_EDITOR_COLUMNS as isize, cpu),
+            Key::Left => editor.move_cursor(-1, cpu),
+            Key::Right => editor.move_cursor(1, cpu),
+            Key::PageUp => editor.move_cursor(-page, cpu),
+            Key::PageDown => editor.move_cursor(page, cpu),
+            Key::Tab => {
+                let space = editor.space.next();
+                editor.set_space(space);
+                editor.message = space.title().to_string();
+            }
+            Key::G => editor.input = HexInput::Goto(String::new()),
+            Key::Slash => editor.input = HexInput::Search(String::new()),
+            _ => match digit {
+                Some(digit) => editor.type_digit(digit, cpu),
+                None => return false,
+            },
+        }
+        true
+    }
+
+    pub fn start(&mut self) {
+        if self.cartridge.is_empty() {
+            panic!("[nes] No cartridge selected!");
+        }
+
+        self.cpu.reset();
+        let cartridge = self.cartridge.to_string();
+
+        self.history.push(self.cpu.disassemble_instruction());
+
+        // self.running = true;
+        self.start_loop(&cartridge);
+
+        // Janela fechada: termina a gravação para completar os cabeçalhos
+        self.stop_recording();
+        self.stop_trace();
+        self.stop_cdl();
+    }
+}
+
+impl Window for Nes {
+    fn update_textures(&mut self, texture_context: &mut G2dTextureContext) {
+        if self.debug && self.window.view == DebugView::Memory {
+            self.window.memory.update(&mut self.cpu);
+        }
+
+        let post = self.post_process();
+
+        if post.chain.is_empty() && self.overscan.is_empty() {
+            // O filtro NTSC muda a largura da tela
+            let width = self.base_width();
+            if self.window.screen.width != width || self.window.screen.height != SCREEN_HEIGHT {
+                self.window.screen = Frame::new(width, SCREEN_HEIGHT);
+            }
+
+            let ppu = &self.cpu.bus.ppu;
+            match &mut self.filter {
+                Some(filter) => {
+                    filter.apply(
+                        ppu.get_screen(),
+                        &ppu.palette,
+                        ppu.frame_count,
+                        &mut self.window.screen.canvas,
+                    );
+                    self.window.screen.upload_texture(texture_context);
+                }
+                None => {
+                    self.window.screen.update_texture_indexed(
+                        texture_context,
+                        ppu.get_screen(),
+                        &ppu.palette,
+                    );
+                }
+            }
+        } else {
+            // Overscan e pós-processamento: a textura passa a ter o tamanho da saída,
+            // mas continua desenhada no mesmo espaço
+            let image = post.apply(self.frame_image());
+            if self.window.screen.width != image.width || self.window.screen.height != image.height
+            {
+                self.window.screen = Frame::new(image.width, image.height);
+            }
+            image.write_rgba(&mut self.window.screen.canvas);
+            self.window.screen.upload_texture(texture_context);
+        }
+
+        if self.debug {
+            let ppu = &mut self.cpu.bus.ppu;
+            match self.window.view {
+                DebugView::Cpu => {
+                    // Draw pattern
+                    ppu.get_pattern_table(0, self.palette_table)
+                        .update_texture(texture_context);
+                    ppu.get_pattern_table(1, self.palette_table)
+                        .update_texture(texture_context);
+                }
+                DebugView::NameTables => ppu.get_name_table().update_texture(texture_context),
+                DebugView::Sprites => ppu.get_oam_table().update_texture(texture_context),
+                DebugView::Events | DebugView::Memory => {}
+            }
+        }
+    }
+
+    fn draw(&mut self, context: Context, gl: &mut G2d, glyphs: &mut Glyphs) {
+        clear(BLACK_PIXEL.get_color(), gl);
+
+        // Draws
+        self.draw_screen(context, gl);
+        if self.debug && self.window.view == DebugView::NameTables {
+            let options = NameTableOptions {
+                tile_grid: self.window.tile_grid,
+                attribute_grid: self.window.attribute_grid,
+                hover: self.debug_hover(NAME_TABLES_WIDTH, NAME_TABLES_HEIGHT),
+            };
+            draw_name_tables(
+                DEBUG_X,
+                DEBUG_Y,
+                &mut self.cpu.bus.ppu,
+                &options,
+                context,
+                gl,
+                glyphs,
+            );
+        } else if self.debug && self.window.view == DebugView::Sprites {
+            let options = SpriteOptions {
+                line: self.selected_line(),
+                hover: self.debug_hover(OAM_TABLE_WIDTH, OAM_TABLE_HEIGHT),
+            };
+            draw_sprite_boxes(
+                &self.cpu.bus.ppu,
+                options.line,
+                &self.screen_area(),
+                context,
+                gl,
+            );
+            draw_sprites(
+                DEBUG_X,
+                DEBUG_Y,
+                &mut self.cpu.bus.ppu,
+                &options,
+                context,
+                gl,
+                glyphs,
+            );
+        } else if self.debug && self.window.view == DebugView::Events {
+            draw_events(
+                DEBUG_X,
+                DEBUG_Y,
+                &self.cpu.bus.ppu,
+                self.window.selected_event.as_ref(),
+                context,
+                gl,
+                glyphs,
+            );
+        } else if self.debug && self.window.view == DebugView::Memory {
+            draw_memory(
+                DEBUG_X,
+                DEBUG_Y,
+                &self.window.memory,
+                &mut self.cpu,
+                context,
+                gl,
+                glyphs,
+            );
+        } else if self.debug {
+            self.draw_palette(context, gl);
+            self.draw_patterns(context, gl);
+            draw_cpu(720, 10, &mut self.cpu, context, gl, glyphs);
+            draw_code(720, 150, &self.history, context, gl, glyphs);
+            if let Some(reason) = &self.debugger.break_reason {
+                let text = format!("BREAK: {}", self.debugger.describe(reason));
+                draw_text(720, 280, &text, RED, context, gl, glyphs);
+            }
+            draw_ram(
+                1020,
+                10,
+                self.window.ram_offset,
+                &mut self.cpu,
+                10,
+                context,
+                gl,
+                glyphs,
+            );
+        }
+    }
+
+    fn on_buttom_press(&mut self, key: Key) {
+        // No editor hexadecimal as setas e os dígitos são do editor e não do jogo
+        if self.debug && self.window.view == DebugView::Memory && self.memory_key(key) {
+            return;
+        }
+
+        if let Some(button) = pad_button(key) {
+            self.on_pad_press(button);
+            return;
+        }
+
+        match key {
+            Key::P => self.toggle_pause(),
+            Key::R => self.reset(),
+            Key::F => {
+                // Sem filtro -> Composite -> S-Video -> RGB -> Monochrome -> sem filtro
+                let preset = match &self.filter {
+                    Some(filter) => filter.preset.next(),
+                    None => Some(NtscPreset::Composite),
+                };
+                self.set_filter(preset);
+            }
+            Key::S => {
+                // Sem escala -> Nearest 3x -> Scale2x -> Scale3x -> smooth2x -> smooth3x -> xBR 3x
+                self.scaler = Scaler::next(self.scaler);
+                println!("[video] scaler: {:?}", self.scaler);
+            }
+            Key::F12 => {
+                let file_name = export_file_name(&self.cartridge, "", "png");
+                match self.screenshot(&file_name) {
+                    Ok(()) => println!("[nes] screenshot: {}", file_name),
+                    Err(e) => eprintln!("[nes] screenshot failed: {}", e),
+                }
+            }
+            Key::F10 => {
+                if self.recorder.is_some() {
+                    self.stop_recording();
+                } else {
+                    let file_name =
+                        export_file_name(&self.cartridge, "", self.record_format.extension());
+                    if let Err(e) = self.start_recording(&file_name, self.record_format) {
+                        eprintln!("[nes] recording failed: {}", e);
+                    }
+                }
+            }
+            Key::F8 => {
+                if self.cpu.cdl.is_some() {
+                    self.stop_cdl();
+                } else {
+                    let file_name = export_file_name(&self.cartridge, "", "cdl");
+                    if let Err(e) = self.start_cdl(&file_name) {
+                        eprintln!("[nes] cdl failed: {}", e);
+                    }
+                }
+            }
+            Key::F9 => {
+                if self.trace.is_some() {
+                    self.stop_trace();
+                } else {
+                    let file_name = export_file_name(&self.cartridge, "_trace", "log");
+                    if let Err(e) = self.start_trace(&file_name) {
+                        eprintln!("[nes] trace failed: {}", e);
+                    }
+                }
+            }
+            Key::F11 => {
+                for table in 0..2 {
+                    let suffix = format!("_pattern{}", table);
+                    let file_name = export_file_name(&self.cartridge, &suffix, "png");
+                    match self.save_pattern_table(table, &file_name) {
+                        Ok(()) => println!("[nes] pattern table: {}", file_name),
+                        Err(e) => eprintln!("[nes] pattern table failed: {}", e),
+                    }
+                }
+            }
+            Key::A => {
+                self.aspect = self.aspect.next();
+                println!("[video] aspect: {:?}", self.aspect);
+            }
+            Key::K => {
+                self.crt = !self.crt;
+                println!("[video] crt: {}", self.crt);
+            }
+            // Debugger: N step into, O step over, U step out e L run to scanline
+            Key::N => {
+                self.debugger.step_into();
+                self.running = true;
+            }
+            Key::O => {
+                self.debugger.step_over(&mut self.cpu);
+                self.running = true;
+            }
+            Key::U => {
+                self.debugger.step_out(&self.cpu);
+                self.running = true;
+            }
+            Key::L => {
+                self.debugger.run_to_scanline(self.selected_line());
+                self.running = true;
+            }
+            Key::V => {
+                self.window.view = self.window.view.next();
+            }
+            Key::G => self.window.tile_grid = !self.window.tile_grid,
+            Key::H => self.window.attribute_grid = !self.window.attribute_grid,
+            Key::LeftBracket => self.window.chosen_line = (self.window.chosen_line + 239) % 240,
+            Key::RightBracket => self.window.chosen_line = (self.window.chosen_line + 1) % 240,
+            Key::T => {
+                if self.palette_table == 7 {
+                    self.palette_table = 0;
+                } else {
+                    self.palette_table += 1;
+                }
+            }
+            Key::PageDown => {
+                if self.window.ram_offset < (0xFFFE - 100) {
+                    self.window.ram_offset += 100;
+                } else {
+                    self.window.ram_offset = 0xFFFF;
+                }
+            }
+            Key::PageUp => {
+                if self.window.ram_offset > 100 {
+                    self.window.ram_offset -= 100;
+                } else {
+                    self.window.ram_offset = 0;
+                }
+            }
+            Key::NumPadPlus => {
+                if self.window.ram_offset < 0xFFFE {
+                    self.window.ram_offset += 1;
+                }
+            }
+            Key::NumPadMinus => {
+                if self.window.ram_offset > 0 {
+                    self.window.ram_offset -= 1;
+                }
+            }
+
+            _ => {}
+        }
+    }
+
+    fn on_mouse_move(&mut self, x: f64, y: f64) {
+        self.window.mouse = (x, y);
+    }
+
+    fn on_mouse_press(&mut self, button: MouseButton) {
+        // Clicando no mapa de eventos seleciona o evento mais perto
+        if self.debug && self.window.view == DebugView::Events && button == MouseButton::Left {
+            if let Some((x, y)) = self.debug_hover(EVENT_MAP_WIDTH, EVENT_MAP_HEIGHT) {
+                self.window.selected_event = find_event(&self.cpu.bus.ppu.events.last_frame, x, y);
+            }
+        }
+    }
+
+    fn on_buttom_release(&mut self, key: Key) {
+        if let Some(button) = pad_button(key) {
+            self.on_pad_release(button);
+        }
+    }
+}
+
+// Botão do controle de cada tecla
+fn pad_button(key: Key) -> Option<PadButton> {
+    let button = match key {
+        Key::Z => PadButton::B,
+        Key::X => PadButton::A,
+        Key::Up => PadButton::Up,
+        Key::Down => PadButton::Down,
+        Key::Right => PadButton::Right,
+        Key::Left => PadButton::Left,
+        Key::Space => PadButton::Start,
+        Key::C => PadButton::Select,
+        _ => return None,
+    };
+    Some(button)
+}
+
+// Valor da tecla de um dígito hexadecimal
+fn hex_digit(key: Key) -> Option<u8> {
+    let digit = match key {
+        Key::D0 | Key::NumPad0 => 0,
+        Key::D1 | Key::NumPad1 => 1,
+        Key::D2 | Key::NumPad2 => 2,
+        Key::D3 | Key::NumPad3 => 3,
+        Key::D4 | Key::NumPad4 => 4,
+        Key::D5 | Key::NumPad5 => 5,
+        Key::D6 | Key::NumPad6 => 6,
+        Key::D7 | Key::NumPad7 => 7,
+        Key::D8 | Key::NumPad8 => 8,
+        Key::D9 | Key::NumPad9 => 9,
+        Key::A => 0xA,
+        Key::B => 0xB,
+        Key::C => 0xC,
+        Key::D => 0xD,
+        Key::E => 0xE,
+        Key::F => 0xF,
+        _ => return None,
+    };
+    Some(digit)
+}
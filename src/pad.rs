@@ -1,3 +1,4 @@
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum PadButton {
     A,
     B,
@@ -41,6 +42,11 @@ impl Pad {
         return data as u8;
     }
 
+    // Lê o próximo bit sem avançar o registrador de deslocamento (depuração)
+    pub fn peek(&self) -> u8 {
+        ((self.reg << self.shift) & 0x80 > 0) as u8
+    }
+
     pub fn reset(&mut self) {
         self.reg = 0;
     }
@@ -71,6 +77,10 @@ impl Pad {
         }
     }
 
+    pub fn set_reg(&mut self, reg: u8) {
+        self.reg = reg;
+    }
+
     pub fn get_reg(&mut self) -> u8 {
         self.reg
     }
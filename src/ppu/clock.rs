@@ -1,4 +1,4 @@
-use super::Ppu2C02;
+use super::{Ppu2C02, PpuEventKind};
 
 // This little lambda function "flips" a byte
 // so 0b11100000 becomes 0b00000111. It's very
@@ -180,7 +180,7 @@ impl Ppu2C02 {
                         //                                         vertical scroll offset
                         // "+ 0"                                 : Mental clarity for plane offset
                         // Note: No PPU address bus offset required as it starts at 0x0000
-                        self.bg_next_tile_lsb = self.ppu_read(
+                        self.bg_next_tile_lsb = self.pattern_read(
                             ((self.control.get_pattern_background() as u16) << 12)
                                 + ((self.bg_next_tile_id as u16) << 4)
                                 + (self.vram_addr.get_fine_y() as u16)
@@ -190,7 +190,7 @@ impl Ppu2C02 {
                     6 => {
                         // Fetch the next background tile MSB bit plane from the pattern memory
                         // This is the same as above, but has a +8 offset to select the next bit plane
-                        self.bg_next_tile_msb = self.ppu_read(
+                        self.bg_next_tile_msb = self.pattern_read(
                             ((self.control.get_pattern_background() as u16) << 12)
                                 + ((self.bg_next_tile_id as u16) << 4)
                                 + (self.vram_addr.get_fine_y() as u16)
@@ -275,16 +275,7 @@ impl Ppu2C02 {
                     // If the difference is positive then the scanline is at least at the
                     // same height as the sprite, so check if it resides in the sprite vertically
                     // depending on the current "sprite height mode"
-                    // FLAGGED
-                    if diff >= 0
-                        && diff
-                            < (if self.control.get_sprite_size() > 1 {
-                                16
-                            } else {
-                                8
-                            })
-                        && self.sprite_count < 8
-                    {
+                    if diff >= 0 && diff < self.sprite_height() && self.sprite_count < 8 {
                         // Sprite is visible, so copy the attribute entry over to our
                         // scanline sprite cache. Ive added < 8 here to guard the array
                         // being written to.
@@ -372,13 +363,13 @@ impl Ppu2C02 {
                                 // Reading Top half Tile
                                 sprite_pattern_addr_lo = (((self.sprite_scanline[i as usize].id as u16) & 0x01)      << 12)    // Which Pattern Table? 0KB or 4KB offset
                                     | ((((self.sprite_scanline[i as usize].id as u16) & 0xFE) + 1) << 4 )    // Which Cell? Tile ID * 16 (16 bytes per tile)
-                                    | (7 - (self.scanline as u16 - self.sprite_scanline[i as usize].y as u16) & 0x07);
+                                    | (7 - ((self.scanline as u16 - self.sprite_scanline[i as usize].y as u16) & 0x07));
                             // Which Row in cell? (0->7)
                             } else {
                                 // Reading Bottom Half Tile
                                 sprite_pattern_addr_lo = (((self.sprite_scanline[i as usize].id as u16) & 0x01)       << 12)    // Which Pattern Table? 0KB or 4KB offset
                                     | (((self.sprite_scanline[i as usize].id as u16) & 0xFE)       << 4 )    // Which Cell? Tile ID * 16 (16 bytes per tile)
-                                    | (7 - (self.scanline as u16 - self.sprite_scanline[i as usize].y as u16) & 0x07);
+                                    | (7 - ((self.scanline as u16 - self.sprite_scanline[i as usize].y as u16) & 0x07));
                                 // Which Row in cell? (0->7)
                             }
                         }
@@ -398,8 +389,8 @@ impl Ppu2C02 {
                     // }
 
                     // Now we have the address of the sprite patterns, we can read them
-                    sprite_pattern_bits_lo = self.ppu_read(sprite_pattern_addr_lo);
-                    sprite_pattern_bits_hi = self.ppu_read(sprite_pattern_addr_hi);
+                    sprite_pattern_bits_lo = self.pattern_read(sprite_pattern_addr_lo);
+                    sprite_pattern_bits_hi = self.pattern_read(sprite_pattern_addr_hi);
 
                     // If the sprite is flipped horizontally, we need to flip the
                     // pattern bytes.
@@ -433,14 +424,14 @@ impl Ppu2C02 {
                 // produce visible artefacts
                 if self.control.get_enable_nmi() > 0 {
                     self.nmi = true;
+                    self.log_event(PpuEventKind::Nmi, 0, 0);
                 }
             }
         }
 
         let (pixel, palette) = self.get_cycle_pixel();
-        let pixel = self.get_colour_from_palette_ram(palette, pixel);
-        self.sprite_screen
-            .set_pixel((self.cycle - 1) as usize, self.scanline as usize, pixel);
+        let colour_index = self.get_colour_index(palette, pixel);
+        self.set_screen_pixel(self.cycle - 1, self.scanline, colour_index);
 
         self.cycle += 1;
 
@@ -451,6 +442,9 @@ impl Ppu2C02 {
                 self.scanline = -1;
                 self.frame_complete = true;
                 self.odd_frame = !self.odd_frame;
+                self.frame_count = self.frame_count.wrapping_add(1);
+                self.events.end_frame();
+                self.decay_io_latch();
             }
         }
     }
@@ -1,6 +1,6 @@
 use crate::video::Pixel;
 
-use super::Ppu2C02;
+use super::{Ppu2C02, PpuEventKind};
 
 impl Ppu2C02 {
     // Increment the background tile "pointer" one tile/column horizontally
@@ -249,6 +249,15 @@ impl Ppu2C02 {
         return (0, 0, false);
     }
 
+    // Só a primeira colisão do frame é registrada como evento, depois disso o
+    // flag continua ligado até o pre-render
+    fn set_sprite_zero_hit(&mut self) {
+        if self.status.get_sprite_zero_hit() == 0 {
+            self.status.set_sprite_zero_hit(1);
+            self.log_event(PpuEventKind::SpriteZeroHit, 0, 0);
+        }
+    }
+
     pub fn get_cycle_pixel(&mut self) -> (u8, u8) {
         let (bg_pixel, bg_palette) = self.get_backgroud_pixel();
         let (fg_pixel, fg_palette, fg_priority) = self.get_foreground_pixel();
@@ -287,11 +296,11 @@ impl Ppu2C02 {
                         || self.mask.get_render_sprites_left())
                     {
                         if self.cycle >= 9 && self.cycle < 258 {
-                            self.status.set_sprite_zero_hit(1);
+                            self.set_sprite_zero_hit();
                         }
                     } else {
                         if self.cycle >= 1 && self.cycle < 258 {
-                            self.status.set_sprite_zero_hit(1);
+                            self.set_sprite_zero_hit();
                         }
                     }
                 }
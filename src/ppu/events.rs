@@ -0,0 +1,81 @@
+use super::Ppu2C02;
+
+// Eventos da PPU ===============================================
+// Cada acesso da CPU aos registradores da PPU, o NMI e o sprite 0 hit
+// são guardados com o scanline e o ciclo em que aconteceram. Assim dá para ver exatamente quando um jogo troca o scroll
+// no meio da tela ou quando o NMI e o sprite 0 hit acontecem.
+//
+// confira: https://www.nesdev.org/wiki/PPU_frame_timing
+
+// Limite de eventos por frame, sem a PPU rodando (testes da CPU) o frame nunca termina
+const MAX_EVENTS: usize = 16384;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PpuEventKind {
+    /** Leitura de $2000-$2007 */
+    RegisterRead,
+    /** Escrita em $2000-$2007 ou no DMA da OAM ($4014) */
+    RegisterWrite,
+    Nmi,
+    SpriteZeroHit,
+}
+
+impl PpuEventKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PpuEventKind::RegisterRead => "READ",
+            PpuEventKind::RegisterWrite => "WRITE",
+            PpuEventKind::Nmi => "NMI",
+            PpuEventKind::SpriteZeroHit => "SPRITE 0",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PpuEvent {
+    pub kind: PpuEventKind,
+    pub scanline: i16,
+    pub cycle: i16,
+    /** Endereço do registrador acessado (0 nas interrupções) */
+    pub addres: u16,
+    pub data: u8,
+    /** Endereço da VRAM (loopy v) no momento do evento */
+    pub vram_addr: u16,
+}
+
+pub struct PpuEvents {
+    /** Eventos do frame atual */
+    pub current: Vec<PpuEvent>,
+    /** Eventos do último frame completo, mostrados pelo visualizador */
+    pub last_frame: Vec<PpuEvent>,
+}
+
+impl PpuEvents {
+    pub fn new() -> PpuEvents {
+        PpuEvents {
+            current: vec![],
+            last_frame: vec![],
+        }
+    }
+
+    /** Fecha o frame: os eventos atuais passam a ser os do último frame */
+    pub fn end_frame(&mut self) {
+        self.last_frame = std::mem::take(&mut self.current);
+    }
+}
+
+impl Ppu2C02 {
+    pub fn log_event(&mut self, kind: PpuEventKind, addres: u16, data: u8) {
+        if self.events.current.len() >= MAX_EVENTS {
+            return;
+        }
+        self.events.current.push(PpuEvent {
+            kind,
+            scanline: self.scanline,
+            cycle: self.cycle,
+            addres,
+            data,
+            vram_addr: self.vram_addr.reg,
+        });
+    }
+}
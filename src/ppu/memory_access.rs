@@ -1,7 +1,12 @@
 use crate::cartridge::Mirror;
+use crate::cpu::{BusActivity, BusOperation};
+use crate::debugger::CDL_CHR_READ;
 
 use super::ppu2C02::Ppu2C02;
 
+// Um bit do latch de I/O da PPU leva ~600ms para descarregar (36 frames a 60Hz)
+const IO_LATCH_DECAY_FRAMES: u8 = 36;
+
 impl Ppu2C02 {
     pub fn cpu_read(&mut self, addr: u16, read_only: bool) -> u8 {
         if read_only {
@@ -33,22 +38,18 @@ impl Ppu2C02 {
             // These are the live PPU registers that repsond
             // to being read from in various ways. Note that not
             // all the registers are capable of being read from
-            // so they just return 0x00
+            // so they just return the value left on the PPU I/O latch
             match addr {
-                // Control - Not readable
-                0x0000 => 0,
-                // Mask - Not Readable
-                0x0001 => 0,
                 // Status
                 0x0002 => {
                     // Reading from the status register has the effect of resetting
                     // different parts of the circuit. Only the top three bits
-                    // contain status information, however it is possible that
-                    // some "noise" gets picked up on the bottom 5 bits which
-                    // represent the last PPU bus transaction. Some games "may"
-                    // use this noise as valid data (even though they probably
-                    // shouldn't)
-                    let data = (self.status.reg & 0xE0) | (self.ppu_data_buffer & 0x1F);
+                    // contain status information, the bottom 5 bits are whatever
+                    // is left on the I/O latch from the last PPU bus transaction.
+                    // Some games "may" use this noise as valid data (even though
+                    // they probably shouldn't)
+                    self.refresh_io_latch(self.status.reg, 0xE0);
+                    let data = self.io_latch;
                     // Clear the vertical blanking flag
                     self.status.set_vertical_blank(0);
 
@@ -57,23 +58,35 @@ impl Ppu2C02 {
 
                     data
                 }
-                // OAM Address - Not Readable
-                0x0003 => 0,
                 // OAM Data
-                0x0004 => self.oam_read(self.oam_addr),
-                // Scroll - Not Readable
-                0x0005 => 0,
-                // PPU Address - Not Readable
-                0x0006 => 0,
+                0x0004 => {
+                    let data = self.oam_read(self.oam_addr);
+                    self.refresh_io_latch(data, 0xFF);
+                    data
+                }
                 // PPU Data
                 0x0007 => {
+                    let addres = self.vram_addr.reg & 0x3FFF;
+
                     // Reads from the NameTable ram get delayed one cycle,
                     // so output buffer which contains the data from the
                     // previous read request
-                    let data = self.ppu_data_buffer;
+                    let mut data = self.ppu_data_buffer;
 
                     // then update the buffer for next time
                     self.ppu_data_buffer = self.ppu_read(self.vram_addr.reg);
+                    self.log_data_access(addres, self.ppu_data_buffer, BusOperation::Read);
+                    self.log_chr(addres, CDL_CHR_READ);
+
+                    // However, if the address was in the palette range, the
+                    // data is not delayed, so it returns immediately. The palette
+                    // only has 6 bits, the top 2 come from the I/O latch
+                    if addres >= 0x3F00 {
+                        data = (self.ppu_data_buffer & 0x3F) | (self.io_latch & 0xC0);
+                        self.refresh_io_latch(data, 0x3F);
+                    } else {
+                        self.refresh_io_latch(data, 0xFF);
+                    }
 
                     // All reads from PPU data automatically increment the nametable
                     // address depending upon the mode set in the control register.
@@ -81,26 +94,58 @@ impl Ppu2C02 {
                     // one whole nametable row; in horizontal mode it just increments
                     // by 1, moving to the next column
                     self.vram_addr.reg = self.vram_addr.reg
-                        + if self.control.get_increment_mode() > 1 {
+                        + if self.control.get_increment_mode() > 0 {
                             32
                         } else {
                             1
                         };
 
-                    // However, if the address was in the palette range, the
-                    // data is not delayed, so it returns immediately
-                    if self.vram_addr.reg >= 0x3F00 {
-                        self.ppu_data_buffer
-                    } else {
-                        data
-                    }
+                    data
+                }
+                // Control, Mask, OAM Address, Scroll, PPU Address - Not Readable
+                _ => self.io_latch,
+            }
+        }
+    }
+
+    fn log_data_access(&mut self, addres: u16, data: u8, operation: BusOperation) {
+        if let Some(log) = &mut self.data_log {
+            log.push(BusActivity {
+                addres,
+                data,
+                operation,
+            });
+        }
+    }
+
+    /** Atualiza os bits do latch de I/O indicados pela máscara e reinicia o tempo
+    de descarga dos bits que ficaram em 1 */
+    pub fn refresh_io_latch(&mut self, data: u8, mask: u8) {
+        self.io_latch = (self.io_latch & !mask) | (data & mask);
+
+        for bit in 0..8 {
+            if mask & (1 << bit) != 0 {
+                self.io_latch_decay[bit] = IO_LATCH_DECAY_FRAMES;
+            }
+        }
+    }
+
+    /** Chamado uma vez por frame, zera os bits que não foram atualizados a tempo */
+    pub fn decay_io_latch(&mut self) {
+        for bit in 0..8 {
+            if self.io_latch_decay[bit] > 0 {
+                self.io_latch_decay[bit] -= 1;
+                if self.io_latch_decay[bit] == 0 {
+                    self.io_latch &= !(1 << bit);
                 }
-                _ => 0,
             }
         }
     }
 
     pub fn cpu_write(&mut self, addr: u16, data: u8) {
+        // Toda escrita, inclusive no status, carrega o latch de I/O
+        self.refresh_io_latch(data, 0xFF);
+
         match addr {
             // Control
             0x0000 => {
@@ -162,6 +207,7 @@ impl Ppu2C02 {
             }
             // PPU Data
             0x0007 => {
+                self.log_data_access(self.vram_addr.reg & 0x3FFF, data, BusOperation::Write);
                 self.ppu_write(self.vram_addr.reg, data);
                 // All writes from PPU data automatically increment the nametable
                 // address depending upon the mode set in the control register.
@@ -1,9 +1,13 @@
 mod clock;
 mod clock_helpers;
+mod events;
 mod memory_access;
 mod output;
 mod ppu2C02;
 mod registers;
 mod screen;
 
+pub use events::*;
+pub use output::*;
 pub use ppu2C02::*;
+pub use screen::*;
@@ -1,5 +1,18 @@
-use super::Ppu2C02;
-use crate::video::{get_color, Frame, Pixel};
+use super::{Ppu2C02, ScreenBuffer};
+use crate::video::{Frame, Pixel, BLACK_PIXEL};
+
+/** Informações de um tile das nametables, usadas pelo visualizador */
+pub struct NameTableTile {
+    /** Nametable lógica (0-3, $2000/$2400/$2800/$2C00) */
+    pub table: u8,
+    pub tile_x: u8,
+    pub tile_y: u8,
+    /** Endereço do tile na nametable */
+    pub addres: u16,
+    pub tile_id: u8,
+    pub attribute_addres: u16,
+    pub palette: u8,
+}
 
 impl Ppu2C02 {
     /* Essa função retorna a cor de um pixel em uma paleta especifica de cores */
@@ -14,11 +27,25 @@ impl Ppu2C02 {
         // "palette << 2" - Cada paleta tem 4 bytes de tamanho
         // "pixel"        - Cada pixel tem o index entre 0, 1, 2 or 3
         let color = self.ppu_read(0x3F00 + ((palette as u16) << 2) + pixel as u16);
-        get_color(color)
+        self.palette.get((color & 0x3F) as u16)
+    }
+
+    /* Índice de 9 bits que a PPU envia para a TV: bits 0-5 cor da paleta, bits 6-8 ênfase
+    (vermelho, verde e azul). No modo greyscale só a coluna cinza (0x00, 0x10, 0x20, 0x30)
+    da paleta é usada */
+    pub fn get_colour_index(&mut self, palette: u8, pixel: u8) -> u16 {
+        let mut color = self.ppu_read(0x3F00 + ((palette as u16) << 2) + pixel as u16) & 0x3F;
+
+        if self.mask.get_grayscale() {
+            color &= 0x30;
+        }
+
+        let emphasis = (self.mask.reg >> 5) as u16;
+        (emphasis << 6) | color as u16
     }
 
-    pub fn get_screen<'a>(&'a self) -> &'a Frame {
-        &self.sprite_screen
+    pub fn get_screen(&self) -> &ScreenBuffer {
+        &self.screen
     }
 
     // This function draw the CHR ROM for a given pattern table into
@@ -102,4 +129,151 @@ impl Ppu2C02 {
 
         &mut self.sprite_pattern_table[i as usize]
     }
+
+    /** Informações do tile na posição (x, y) das 4 nametables lógicas (512x480 pixels) */
+    pub fn get_name_table_tile(&mut self, x: usize, y: usize) -> NameTableTile {
+        let table = ((y / 240) * 2 + (x / 256)) as u8;
+        let tile_x = ((x % 256) / 8) as u8;
+        let tile_y = ((y % 240) / 8) as u8;
+
+        // Cada nametable tem 32x30 tiles (960 bytes) seguidos de 64 bytes de atributos,
+        // cada byte de atributo cobre 4x4 tiles e cada 2 bits dele um bloco de 2x2 tiles
+        let base = 0x2000 + table as u16 * 0x0400;
+        let addres = base + tile_y as u16 * 32 + tile_x as u16;
+        let attribute_addres = base + 0x03C0 + (tile_y as u16 / 4) * 8 + tile_x as u16 / 4;
+        let shift = ((tile_y & 0x02) << 1) | (tile_x & 0x02);
+
+        NameTableTile {
+            table,
+            tile_x,
+            tile_y,
+            addres,
+            tile_id: self.ppu_read(addres),
+            attribute_addres,
+            palette: (self.ppu_read(attribute_addres) >> shift) & 0x03,
+        }
+    }
+
+    // Desenha as 4 nametables lógicas ($2000, $2400, $2800 e $2C00) lado a lado como
+    // a PPU enxerga: a leitura passa pelo espelhamento do cartucho, então duas delas
+    // sempre repetem as outras (exceto em cartuchos com 4 telas)
+    pub fn get_name_table<'a>(&'a mut self) -> &'a mut Frame {
+        let pattern = self.control.get_pattern_background() as u16 * 0x1000;
+
+        // As 16 cores das paletas de background
+        let mut colors = [[Pixel::new(0, 0, 0); 4]; 4];
+        for palette in 0..4 {
+            for pixel in 0..4 {
+                colors[palette][pixel] =
+                    self.get_colour_from_palette_ram(palette as u8, pixel as u8);
+            }
+        }
+
+        for y in (0..480).step_by(8) {
+            for x in (0..512).step_by(8) {
+                let tile = self.get_name_table_tile(x, y);
+                let tile_offset = pattern + tile.tile_id as u16 * 16;
+
+                for row in 0..8 {
+                    let mut tile_lsb = self.ppu_read(tile_offset + row as u16);
+                    let mut tile_msb = self.ppu_read(tile_offset + row as u16 + 0x0008);
+
+                    for col in 0..8 {
+                        let pixel = (tile_msb & 0x01) << 1 | (tile_lsb & 0x01);
+                        tile_lsb >>= 1;
+                        tile_msb >>= 1;
+
+                        self.sprite_name_table.set_pixel(
+                            x + (7 - col),
+                            y + row,
+                            colors[tile.palette as usize][pixel as usize],
+                        );
+                    }
+                }
+            }
+        }
+
+        &mut self.sprite_name_table
+    }
+
+    /** Altura dos sprites em pixels (8 ou 16), definida pelo registrador de controle */
+    pub fn sprite_height(&self) -> i16 {
+        if self.control.get_sprite_size() > 0 {
+            16
+        } else {
+            8
+        }
+    }
+
+    /** Índices da OAM que a PPU copia para o sprite_scanline para serem desenhados na linha
+    "line" da tela. A avaliação acontece na linha anterior, por isso o sprite com Y = n
+    aparece a partir da linha n + 1. Só os 8 primeiros são desenhados, os outros causam o
+    sprite overflow */
+    pub fn sprites_on_scanline(&self, line: i16) -> Vec<usize> {
+        let height = self.sprite_height();
+        (0..64)
+            .filter(|&i| {
+                let diff = line - 1 - self.oam[i].y as i16;
+                diff >= 0 && diff < height
+            })
+            .collect()
+    }
+
+    // Desenha os 64 sprites da OAM em uma grade de 8x8 células de 8x16 pixels, com a
+    // paleta e o espelhamento de cada um. No modo 8x8 só a metade de cima da célula é
+    // usada, no modo 8x16 o bit 0 do tile escolhe a pattern table
+    pub fn get_oam_table(&mut self) -> &mut Frame {
+        let tall = self.sprite_height() == 16;
+        let background = self.get_colour_from_palette_ram(0, 0);
+
+        for i in 0..64 {
+            let sprite = self.oam[i];
+            let (cell_x, cell_y) = ((i % 8) * 8, (i / 8) * 16);
+            let palette = 4 + (sprite.attribute & 0x03);
+            let (flip_h, flip_v) = (sprite.attribute & 0x40 > 0, sprite.attribute & 0x80 > 0);
+
+            let (table, tile) = if tall {
+                ((sprite.id as u16 & 0x01) * 0x1000, sprite.id as u16 & 0xFE)
+            } else {
+                (
+                    self.control.get_pattern_sprite() as u16 * 0x1000,
+                    sprite.id as u16,
+                )
+            };
+            let rows = if tall { 16 } else { 8 };
+
+            for row in 0..16 {
+                if row >= rows {
+                    for col in 0..8 {
+                        self.sprite_oam_table
+                            .set_pixel(cell_x + col, cell_y + row, BLACK_PIXEL);
+                    }
+                    continue;
+                }
+
+                // No modo 8x16 o espelhamento vertical também troca os dois tiles
+                let source = if flip_v { rows - 1 - row } else { row };
+                let tile_offset = table + (tile + source as u16 / 8) * 16 + (source % 8) as u16;
+                let mut tile_lsb = self.ppu_read(tile_offset);
+                let mut tile_msb = self.ppu_read(tile_offset + 0x0008);
+
+                for col in 0..8 {
+                    let pixel = (tile_msb & 0x01) << 1 | (tile_lsb & 0x01);
+                    tile_lsb >>= 1;
+                    tile_msb >>= 1;
+
+                    let color = if pixel == 0 {
+                        background
+                    } else {
+                        self.get_colour_from_palette_ram(palette, pixel)
+                    };
+                    let x = if flip_h { col } else { 7 - col };
+                    self.sprite_oam_table
+                        .set_pixel(cell_x + x, cell_y + row, color);
+                }
+            }
+        }
+
+        &mut self.sprite_oam_table
+    }
 }
@@ -1,6 +1,9 @@
+use super::events::PpuEvents;
 use super::registers::{LoopyRegister, Mask, ObjectAttributeEntry, PpuControl, Status};
+use super::screen::{ScreenBuffer, SCREEN_HEIGHT, SCREEN_WIDTH};
 use crate::cartridge::ChrRom;
-use crate::video::Frame;
+use crate::cpu::BusActivity;
+use crate::video::{Frame, Palette};
 
 pub struct Ppu2C02 {
     // controla os tiles/sprites que serão exibidos na tela
@@ -11,11 +14,14 @@ pub struct Ppu2C02 {
     // paletas/cores
     pub table_palette: [u8; 32],
     pub chr_rom: ChrRom,
+    // cores das 512 combinações de índice + ênfase que a PPU pode gerar
+    pub palette: Palette,
 
     // auxiliares
-    pub sprite_screen: Frame,             // Tela final 256x240
-    pub sprite_name_table: [Frame; 2], // (não usado) visualização da nametables (tela final na memória) as duas são 256x240
+    pub screen: Box<ScreenBuffer>, // Tela final 256x240 (índices da paleta + ênfase)
+    pub sprite_name_table: Frame, // Visualização das 4 nametables lógicas (512x480) com o espelhamento atual
     pub sprite_pattern_table: [Frame; 2], // Visualização da tabela de sprites (background e foregrounds)
+    pub sprite_oam_table: Frame, // Visualização dos 64 sprites da OAM (8x8 células de 8x16)
 
     pub frame_complete: bool,
     pub status: Status,
@@ -31,10 +37,19 @@ pub struct Ppu2C02 {
     pub address_latch: u8,
     pub ppu_data_buffer: u8,
 
+    // Latch do barramento entre a CPU e a PPU ("open bus" da PPU). Todo acesso
+    // aos registradores atualiza o latch e os registradores que não podem ser
+    // lidos devolvem o valor dele. Cada bit em 1 descarrega sozinho depois de
+    // alguns frames sem ser atualizado
+    pub io_latch: u8,
+    pub io_latch_decay: [u8; 8],
+
     // Pixel "dot" position information
     pub scanline: i16,
     pub cycle: i16,
     pub odd_frame: bool,
+    /** Frames completos desde que o console ligou, usado na fase do filtro NTSC */
+    pub frame_count: u32,
 
     // Background rendering =========================================
     pub bg_next_tile_id: u8,
@@ -69,6 +84,13 @@ pub struct Ppu2C02 {
 
     // Interface
     pub nmi: bool,
+
+    // Acessos aos registradores e interrupções por scanline/ciclo (visualizador de eventos)
+    pub events: PpuEvents,
+    // Acessos da CPU à memória da PPU pelo $2007, só é preenchido quando habilitado (watchpoints)
+    pub data_log: Option<Vec<BusActivity>>,
+    // Flags do Code/Data Logger de cada byte da CHR, só existe enquanto está gravando
+    pub chr_log: Option<Vec<u8>>,
 }
 
 impl Ppu2C02 {
@@ -88,9 +110,11 @@ impl Ppu2C02 {
             // table_pattern: [vec![], vec![]],
             table_palette: [0; 32],
             chr_rom,
-            sprite_screen: Frame::new(256, 240),
-            sprite_name_table: [Frame::new(256, 240), Frame::new(256, 240)], // unused
+            palette: Palette::new(),
+            screen: Box::new([0; SCREEN_WIDTH * SCREEN_HEIGHT]),
+            sprite_name_table: Frame::new(512, 480),
             sprite_pattern_table: [Frame::new(128, 128), Frame::new(128, 128)],
+            sprite_oam_table: Frame::new(64, 128),
 
             frame_complete: false,
 
@@ -103,9 +127,12 @@ impl Ppu2C02 {
             fine_x: 0,
             address_latch: 0,
             ppu_data_buffer: 0,
+            io_latch: 0,
+            io_latch_decay: [0; 8],
             scanline: 0,
             cycle: 0,
             odd_frame: false,
+            frame_count: 0,
 
             bg_next_tile_id: 0,
             bg_next_tile_attrib: 0,
@@ -127,6 +154,10 @@ impl Ppu2C02 {
             sprite_zero_being_rendered: false,
 
             nmi: false,
+
+            events: PpuEvents::new(),
+            data_log: None,
+            chr_log: None,
         }
     }
 
@@ -134,6 +165,8 @@ impl Ppu2C02 {
         self.fine_x = 0x00;
         self.address_latch = 0x00;
         self.ppu_data_buffer = 0x00;
+        self.io_latch = 0x00;
+        self.io_latch_decay = [0; 8];
         self.scanline = 0;
         self.cycle = 0;
         self.bg_next_tile_id = 0x00;
@@ -1 +1,18 @@
+use super::ppu2C02::Ppu2C02;
 
+// Tamanho da imagem visível que a PPU gera
+pub const SCREEN_WIDTH: usize = 256;
+pub const SCREEN_HEIGHT: usize = 240;
+
+// A tela é guardada como os índices de 9 bits que a PPU envia para a TV
+// (6 bits da cor + 3 bits de ênfase), a conversão para RGB fica com quem
+// vai exibir a imagem (ver video::Palette)
+pub type ScreenBuffer = [u16; SCREEN_WIDTH * SCREEN_HEIGHT];
+
+impl Ppu2C02 {
+    pub fn set_screen_pixel(&mut self, x: i16, y: i16, colour_index: u16) {
+        if x >= 0 && (x as usize) < SCREEN_WIDTH && y >= 0 && (y as usize) < SCREEN_HEIGHT {
+            self.screen[(y as usize) * SCREEN_WIDTH + x as usize] = colour_index;
+        }
+    }
+}
@@ -0,0 +1,325 @@
+use std::io::{self, Read, Write};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::pad::PadButton;
+use crate::video::{Image, Rgba, Video};
+
+// Jogando no terminal ==========================================
+// Outra saída do trait Video, sem janela nem OpenGL: cada caractere "▀" mostra
+// dois pixels, o de cima com a cor do texto e o de baixo com a cor do
+// fundo (truecolor, "\x1b[38;2;r;g;bm"). A imagem é reduzida pela média
+// dos pixels para caber no terminal, mantendo a proporção. O teclado fica
+// em modo raw, sem eco e sem esperar o enter.
+//
+// O terminal só avisa quando uma tecla é pressionada (e repete enquanto
+// ela está apertada), nunca quando ela é solta. Por isso cada tecla deixa o
+// botão apertado por hold_frames frames, renovados a cada repetição. A
+// primeira repetição só chega depois do atraso do teclado (~500 ms, 660 ms
+// no X11), então o padrão HOLD_FRAMES passa disso para o botão não soltar
+// no meio. Um toque rápido também fica apertado esse tempo todo; quem
+// diminuir o atraso do teclado pode usar --hold-frames menor.
+//
+// confira: https://en.wikipedia.org/wiki/ANSI_escape_code#24-bit
+
+const UPPER_HALF_BLOCK: char = '▀';
+pub const HOLD_FRAMES: u8 = 45;
+const FRAME_TIME: Duration = Duration::from_nanos(1_000_000_000 / 60);
+// Uma linha no fim para o status
+const STATUS_LINES: usize = 1;
+
+const BUTTONS: [PadButton; 8] = [
+    PadButton::A,
+    PadButton::B,
+    PadButton::Select,
+    PadButton::Start,
+    PadButton::Up,
+    PadButton::Down,
+    PadButton::Left,
+    PadButton::Right,
+];
+
+enum TerminalKey {
+    Button(PadButton),
+    Pause,
+    Reset,
+    Quit,
+}
+
+// Guarda o modo do terminal e volta para ele quando sai do escopo (inclusive em um panic)
+struct RawMode {
+    original: libc::termios,
+}
+
+impl RawMode {
+    fn enable() -> Result<RawMode, String> {
+        unsafe {
+            let mut termios: libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut termios) != 0 {
+                return Err("stdin is not a terminal".to_string());
+            }
+            let original = termios;
+            libc::cfmakeraw(&mut termios);
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &termios) != 0 {
+                return Err("could not set the terminal to raw mode".to_string());
+            }
+            Ok(RawMode { original })
+        }
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original);
+        }
+        // Cursor de volta e sai da tela alternativa
+        print!("\x1b[0m\x1b[?25h\x1b[?1049l");
+        io::stdout().flush().ok();
+    }
+}
+
+/** Tamanho do terminal em (colunas, linhas) */
+fn terminal_size() -> (usize, usize) {
+    unsafe {
+        let mut size: libc::winsize = std::mem::zeroed();
+        if libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) == 0 && size.ws_col > 0 {
+            (size.ws_col as usize, size.ws_row as usize)
+        } else {
+            (80, 24)
+        }
+    }
+}
+
+/** Reduz a imagem para caber em width x height (nunca aumenta), mantendo a proporção.
+Cada pixel novo é a média dos pixels da imagem que ele cobre */
+pub fn fit_image(image: &Image, width: usize, height: usize) -> Image {
+    let scale = (width as f64 / image.width as f64)
+        .min(height as f64 / image.height as f64)
+        .min(1.0);
+    let new_width = ((image.width as f64 * scale) as usize).max(1);
+    let new_height = ((image.height as f64 * scale) as usize).max(1);
+
+    let mut fitted = Image::new(new_width, new_height);
+    for y in 0..new_height {
+        let (top, bottom) = (
+            y * image.height / new_height,
+            (y + 1) * image.height / new_height,
+        );
+        for x in 0..new_width {
+            let (left, right) = (
+                x * image.width / new_width,
+                (x + 1) * image.width / new_width,
+            );
+
+            let mut sum = [0usize; 3];
+            for source_y in top..bottom.max(top + 1) {
+                for source_x in left..right.max(left + 1) {
+                    let pixel = image.pixels[source_y * image.width + source_x];
+                    for c in 0..3 {
+                        sum[c] += pixel[c] as usize;
+                    }
+                }
+            }
+            let count = (bottom.max(top + 1) - top) * (right.max(left + 1) - left);
+            fitted.pixels[y * new_width + x] = [
+                (sum[0] / count) as u8,
+                (sum[1] / count) as u8,
+                (sum[2] / count) as u8,
+                255,
+            ];
+        }
+    }
+    fitted
+}
+
+/** A imagem em caracteres "▀", duas linhas de pixels por linha de texto. As cores só
+são escritas quando mudam, para diminuir a saída (importante por SSH) */
+pub fn render_half_blocks(image: &Image) -> String {
+    let mut text = String::new();
+    let black: Rgba = [0, 0, 0, 255];
+
+    for row in 0..image.height.div_ceil(2) {
+        let mut last: Option<(Rgba, Rgba)> = None;
+        for x in 0..image.width {
+            let top = image.pixels[row * 2 * image.width + x];
+            let bottom = if row * 2 + 1 < image.height {
+                image.pixels[(row * 2 + 1) * image.width + x]
+            } else {
+                black
+            };
+
+            if last.is_none_or(|(last_top, _)| last_top != top) {
+                text += &format!("\x1b[38;2;{};{};{}m", top[0], top[1], top[2]);
+            }
+            if last.is_none_or(|(_, last_bottom)| last_bottom != bottom) {
+                text += &format!("\x1b[48;2;{};{};{}m", bottom[0], bottom[1], bottom[2]);
+            }
+            last = Some((top, bottom));
+            text.push(UPPER_HALF_BLOCK);
+        }
+        text += "\x1b[0m\x1b[K\r\n";
+    }
+    text
+}
+
+// O stdin é lido em outra thread, assim o loop dos frames nunca fica esperando uma tecla
+fn spawn_input() -> Receiver<u8> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut stdin = io::stdin();
+        let mut buffer = [0; 64];
+        while let Ok(size @ 1..) = stdin.read(&mut buffer) {
+            if buffer[..size]
+                .iter()
+                .any(|byte| sender.send(*byte).is_err())
+            {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+// Traduz os bytes recebidos, as setas chegam como "ESC [ A" ... "ESC [ D"
+fn parse_keys(bytes: &[u8]) -> Vec<TerminalKey> {
+    let mut keys = vec![];
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == 0x1b && bytes.get(i + 1) == Some(&b'[') && i + 2 < bytes.len() {
+            match bytes[i + 2] {
+                b'A' => keys.push(TerminalKey::Button(PadButton::Up)),
+                b'B' => keys.push(TerminalKey::Button(PadButton::Down)),
+                b'C' => keys.push(TerminalKey::Button(PadButton::Right)),
+                b'D' => keys.push(TerminalKey::Button(PadButton::Left)),
+                _ => {}
+            }
+            i += 3;
+            continue;
+        }
+
+        match bytes[i].to_ascii_lowercase() {
+            b'z' => keys.push(TerminalKey::Button(PadButton::B)),
+            b'x' => keys.push(TerminalKey::Button(PadButton::A)),
+            b' ' | b'\r' => keys.push(TerminalKey::Button(PadButton::Start)),
+            b'c' => keys.push(TerminalKey::Button(PadButton::Select)),
+            b'p' => keys.push(TerminalKey::Pause),
+            b'r' => keys.push(TerminalKey::Reset),
+            // ESC não sai: por SSH uma seta pode chegar dividida em duas leituras
+            b'q' | 0x03 => keys.push(TerminalKey::Quit),
+            _ => {}
+        }
+        i += 1;
+    }
+    keys
+}
+
+pub struct Terminal<V: Video> {
+    pub video: V,
+    /** Frames que um botão fica apertado depois de cada tecla */
+    pub hold_frames: u8,
+    // Frames que faltam para soltar cada botão, na ordem de BUTTONS
+    held: [u8; 8],
+}
+
+impl<V: Video> Terminal<V> {
+    pub fn new(video: V) -> Terminal<V> {
+        Terminal {
+            video,
+            hold_frames: HOLD_FRAMES,
+            held: [0; 8],
+        }
+    }
+
+    pub fn start(&mut self) -> Result<(), String> {
+        let _raw_mode = RawMode::enable()?;
+        let input = spawn_input();
+        let mut stdout = io::stdout();
+
+        // Tela alternativa, sem cursor
+        print!("\x1b[?1049h\x1b[?25l\x1b[2J");
+        self.video.reset();
+        if !self.video.is_running() {
+            self.video.toggle_pause();
+        }
+
+        let mut frames = 0;
+        let mut fps_time = Instant::now();
+        let mut fps = 0.0;
+
+        loop {
+            let frame_start = Instant::now();
+
+            let bytes: Vec<u8> = input.try_iter().collect();
+            for key in parse_keys(&bytes) {
+                match key {
+                    TerminalKey::Button(button) => {
+                        let index = BUTTONS.iter().position(|b| *b == button).unwrap();
+                        if self.held[index] == 0 {
+                            self.video.on_pad_press(button);
+                        }
+                        self.held[index] = self.hold_frames.max(1);
+                    }
+                    TerminalKey::Pause => self.video.toggle_pause(),
+                    TerminalKey::Reset => self.video.reset(),
+                    TerminalKey::Quit => return Ok(()),
+                }
+            }
+
+            if self.video.is_running() {
+                self.video.main_loop();
+                self.release_buttons();
+                frames += 1;
+            }
+
+            if fps_time.elapsed() >= Duration::from_secs(1) {
+                fps = frames as f64 / fps_time.elapsed().as_secs_f64();
+                frames = 0;
+                fps_time = Instant::now();
+            }
+
+            let (columns, lines) = terminal_size();
+            let image = fit_image(
+                &self.video.screen_image(),
+                columns,
+                lines.saturating_sub(STATUS_LINES).max(1) * 2,
+            );
+            let status = format!(
+                "{:.0} fps{}  setas Z=B X=A espaço=start C=select  P pausa  R reset  Q sai",
+                fps,
+                if self.video.is_running() {
+                    ""
+                } else {
+                    " (pausado)"
+                }
+            );
+            let text = format!(
+                "\x1b[H{}{}\x1b[K",
+                render_half_blocks(&image),
+                status.chars().take(columns).collect::<String>()
+            );
+            stdout
+                .write_all(text.as_bytes())
+                .and_then(|_| stdout.flush())
+                .map_err(|e| e.to_string())?;
+
+            if let Some(wait) = FRAME_TIME.checked_sub(frame_start.elapsed()) {
+                thread::sleep(wait);
+            }
+        }
+    }
+
+    // Conta um frame emulado e solta os botões que acabaram os frames
+    fn release_buttons(&mut self) {
+        for (index, held) in self.held.iter_mut().enumerate() {
+            if *held > 0 {
+                *held -= 1;
+                if *held == 0 {
+                    self.video.on_pad_release(BUTTONS[index]);
+                }
+            }
+        }
+    }
+}
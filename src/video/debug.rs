@@ -1,8 +1,15 @@
 use graphics::{types::Color, Context};
 use piston_window::*;
 
-use super::utils::{DrawText, GREEN, RED, WHITE};
+use super::ppu_debug::YELLOW;
+use super::utils::{draw_text, DrawText, GREEN, RED, WHITE};
 use crate::cpu::{Cpu6502, Flags6502};
+use crate::debugger::{HexEditor, HexInput, HEX_EDITOR_COLUMNS};
+
+// Editor hexadecimal: espaço entre as colunas e as linhas
+const MEMORY_FONT_SIZE: usize = 14;
+const MEMORY_COLUMN_WIDTH: usize = 24;
+const MEMORY_LINE_HEIGHT: usize = 16;
 
 fn active_color(active: u8) -> Color {
     if active == 1 {
@@ -44,6 +51,11 @@ pub fn draw_cpu(
     text.draw("I", active_color(cpu.get_flag(Flags6502::I)), gl, glyphs);
     text.draw("Z", active_color(cpu.get_flag(Flags6502::Z)), gl, glyphs);
     text.draw("C", active_color(cpu.get_flag(Flags6502::C)), gl, glyphs);
+
+    if cpu.halted {
+        text.break_line();
+        text.draw_line(&format!("JAM: {:#04x}", cpu.opcode), RED, gl, glyphs);
+    }
 }
 
 pub fn draw_code(
@@ -85,3 +97,60 @@ pub fn draw_ram(
         );
     }
 }
+
+/** Desenha o editor hexadecimal: o cursor em amarelo e os bytes que o jogo mudou em
+vermelho, apagando com o tempo */
+pub fn draw_memory(
+    x: usize,
+    y: usize,
+    editor: &HexEditor,
+    cpu: &mut Cpu6502,
+    c: Context,
+    gl: &mut G2d,
+    glyphs: &mut Glyphs,
+) {
+    let mut text = DrawText::with_font_size(MEMORY_FONT_SIZE, x, y, c);
+    text.draw_line(
+        &format!("MEMORY {}", editor.space.title()),
+        WHITE,
+        gl,
+        glyphs,
+    );
+
+    let rows_y = y + MEMORY_LINE_HEIGHT * 3;
+    let rows = editor.visible_rows(cpu);
+    for (row, start) in rows.iter().enumerate() {
+        let line_y = rows_y + row * MEMORY_LINE_HEIGHT;
+        let label = format!("{:04X}", start);
+        draw_text(x, line_y, &label, WHITE, c, gl, glyphs);
+
+        let end = (start + HEX_EDITOR_COLUMNS).min(cpu.space_size(editor.space));
+        for addres in *start..end {
+            let data = cpu.peek_space(editor.space, addres);
+            let (value, color) = if addres == editor.cursor {
+                match editor.nibble {
+                    Some(high) => (format!("{:X}_", high), YELLOW),
+                    None => (format!("{:02X}", data), YELLOW),
+                }
+            } else {
+                let fade = editor.highlight(addres);
+                (format!("{:02X}", data), [1.0, 1.0 - fade, 1.0 - fade, 1.0])
+            };
+            let column_x = x + 60 + (addres - start) * MEMORY_COLUMN_WIDTH;
+            draw_text(column_x, line_y, &value, color, c, gl, glyphs);
+        }
+    }
+
+    let mut text = DrawText::with_font_size(
+        MEMORY_FONT_SIZE,
+        x,
+        rows_y + rows.len() * MEMORY_LINE_HEIGHT,
+        c,
+    );
+    match &editor.input {
+        HexInput::Goto(input) => text.draw_line(&format!("GOTO: {}_", input), YELLOW, gl, glyphs),
+        HexInput::Search(input) => text.draw_line(&format!("FIND: {}_", input), YELLOW, gl, glyphs),
+        HexInput::None => text.draw_line(&editor.message, YELLOW, gl, glyphs),
+    }
+    text.draw_line("TAB space  G goto  / find  0-F edit", WHITE, gl, glyphs);
+}
@@ -0,0 +1,234 @@
+use super::scaler::Image;
+use crate::ppu::{SCREEN_HEIGHT, SCREEN_WIDTH};
+
+// Overscan e proporção da imagem ===============================
+// A PPU gera 240 linhas, mas as TVs de tubo escondiam algumas linhas
+// e colunas de cada borda (overscan) e muitos jogos deixam lixo ali.
+// Os pixels do NES também não são quadrados: em uma TV NTSC cada
+// pixel é um pouco mais largo que alto (proporção 8:7).
+// confira: https://www.nesdev.org/wiki/Overscan
+
+/** Linhas/colunas (em pixels da PPU) escondidas em cada borda */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Overscan {
+    pub top: usize,
+    pub bottom: usize,
+    pub left: usize,
+    pub right: usize,
+}
+
+impl Overscan {
+    /** Sem corte, mostra as 240 linhas */
+    pub fn new() -> Overscan {
+        Overscan {
+            top: 0,
+            bottom: 0,
+            left: 0,
+            right: 0,
+        }
+    }
+
+    /** Lê "N" (todas as bordas) ou "topo,baixo,esquerda,direita". Pelo menos uma linha e
+    uma coluna precisam continuar visíveis */
+    pub fn from(value: &str) -> Result<Overscan, String> {
+        let edges = value
+            .split(',')
+            .map(|edge| edge.trim().parse::<usize>())
+            .collect::<Result<Vec<usize>, _>>()
+            .map_err(|_| format!("invalid overscan: {}", value))?;
+
+        let overscan = match edges[..] {
+            [all] => Overscan {
+                top: all,
+                bottom: all,
+                left: all,
+                right: all,
+            },
+            [top, bottom, left, right] => Overscan {
+                top,
+                bottom,
+                left,
+                right,
+            },
+            _ => {
+                return Err(format!(
+                    "invalid overscan: {} (expected N or top,bottom,left,right)",
+                    value
+                ))
+            }
+        };
+
+        if overscan.left + overscan.right >= SCREEN_WIDTH
+            || overscan.top + overscan.bottom >= SCREEN_HEIGHT
+        {
+            return Err(format!(
+                "invalid overscan: {} (left + right must be under {} and top + bottom under {})",
+                value, SCREEN_WIDTH, SCREEN_HEIGHT
+            ));
+        }
+        Ok(overscan)
+    }
+
+    /** Largura visível em pixels da PPU, com a mesma regra do crop */
+    pub fn visible_width(&self) -> usize {
+        let left = self.left.min(SCREEN_WIDTH - 1);
+        SCREEN_WIDTH - left - self.right.min(SCREEN_WIDTH - 1 - left)
+    }
+
+    /** Altura visível em pixels da PPU, com a mesma regra do crop */
+    pub fn visible_height(&self) -> usize {
+        let top = self.top.min(SCREEN_HEIGHT - 1);
+        SCREEN_HEIGHT - top - self.bottom.min(SCREEN_HEIGHT - 1 - top)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Overscan::new()
+    }
+
+    /** Corta as bordas de uma imagem da tela. A imagem pode ser mais larga que a PPU
+    (ex: saída do filtro NTSC), então as colunas são convertidas para a largura dela.
+    Como no from, sempre sobra pelo menos uma linha e uma coluna */
+    pub fn crop(&self, image: &Image) -> Image {
+        let x_scale = (image.width / SCREEN_WIDTH).max(1);
+        let left = (self.left.min(SCREEN_WIDTH - 1) * x_scale).min(image.width - 1);
+        let right = (self.right * x_scale).min(image.width - 1 - left);
+        let top = self.top.min(image.height - 1);
+        let bottom = self.bottom.min(image.height - 1 - top);
+
+        let mut out = Image::new(image.width - left - right, image.height - top - bottom);
+        for y in 0..out.height {
+            let start = (y + top) * image.width + left;
+            let end = start + out.width;
+            out.pixels[y * out.width..(y + 1) * out.width]
+                .copy_from_slice(&image.pixels[start..end]);
+        }
+
+        out
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AspectMode {
+    /** Pixels quadrados, como a PPU gera */
+    Square,
+    /** Pixels 8:7, como em uma TV NTSC */
+    Par8x7,
+    /** Imagem inteira esticada para 4:3 */
+    Display4x3,
+}
+
+impl AspectMode {
+    pub fn from(name: &str) -> Option<AspectMode> {
+        match name {
+            "square" | "1:1" => Some(AspectMode::Square),
+            "8:7" | "ntsc" => Some(AspectMode::Par8x7),
+            "4:3" => Some(AspectMode::Display4x3),
+            _ => None,
+        }
+    }
+
+    pub fn next(&self) -> AspectMode {
+        match self {
+            AspectMode::Square => AspectMode::Par8x7,
+            AspectMode::Par8x7 => AspectMode::Display4x3,
+            AspectMode::Display4x3 => AspectMode::Square,
+        }
+    }
+
+    /** Largura em que uma imagem de width x height deve ser mostrada. "ppu_width" é a
+    largura dela em pixels da PPU, já que a imagem pode ter sido escalada */
+    pub fn display_width(&self, ppu_width: usize, height: usize) -> f64 {
+        match self {
+            AspectMode::Square => ppu_width as f64,
+            AspectMode::Par8x7 => ppu_width as f64 * 8.0 / 7.0,
+            AspectMode::Display4x3 => height as f64 * 4.0 / 3.0,
+        }
+    }
+
+    /** Estica a imagem na horizontal para a proporção, usado ao exportar imagens */
+    pub fn apply(&self, image: &Image, ppu_width: usize, ppu_height: usize) -> Image {
+        let y_scale = image.height as f64 / ppu_height as f64;
+        let width = (self.display_width(ppu_width, ppu_height) * y_scale).round() as usize;
+        if width == image.width {
+            return image.clone();
+        }
+
+        // Interpolação linear entre as colunas vizinhas
+        let mut out = Image::new(width, image.height);
+        let step = image.width as f64 / width as f64;
+        for x in 0..width {
+            let source = ((x as f64 + 0.5) * step - 0.5).max(0.0);
+            let left = (source as usize).min(image.width - 1);
+            let right = (left + 1).min(image.width - 1);
+            let alpha = source - left as f64;
+
+            for y in 0..image.height {
+                let a = image.pixels[y * image.width + left];
+                let b = image.pixels[y * image.width + right];
+                let mut pixel = [0; 4];
+                for channel in 0..4 {
+                    pixel[channel] = (a[channel] as f64 * (1.0 - alpha) + b[channel] as f64 * alpha)
+                        .round() as u8;
+                }
+                out.set(x, y, pixel);
+            }
+        }
+
+        out
+    }
+}
+
+/** Área da janela ocupada pela tela do jogo (a partir do canto 0,0), usada para
+converter entre pixels da PPU e pixels da janela */
+pub struct ScreenArea {
+    pub width: f64,
+    pub height: f64,
+    /** Primeiro pixel da PPU visível depois do overscan */
+    pub left: usize,
+    pub top: usize,
+    pub scale_x: f64,
+    pub scale_y: f64,
+}
+
+impl ScreenArea {
+    /** Encaixa a imagem cortada e esticada para a proporção em max_width x max_height */
+    pub fn new(
+        overscan: &Overscan,
+        aspect: AspectMode,
+        max_width: f64,
+        max_height: f64,
+    ) -> ScreenArea {
+        let height = overscan.visible_height();
+        let width = overscan.visible_width();
+        let display_width = aspect.display_width(width, height);
+        let scale = (max_width / display_width).min(max_height / height as f64);
+
+        ScreenArea {
+            width: display_width * scale,
+            height: height as f64 * scale,
+            left: overscan.left.min(SCREEN_WIDTH - 1),
+            top: overscan.top.min(SCREEN_HEIGHT - 1),
+            scale_x: display_width * scale / width as f64,
+            scale_y: scale,
+        }
+    }
+
+    /** Posição na janela de um pixel da PPU */
+    pub fn to_window(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            (x - self.left as f64) * self.scale_x,
+            (y - self.top as f64) * self.scale_y,
+        )
+    }
+
+    /** Pixel da PPU embaixo de uma posição da janela, se estiver dentro da tela */
+    pub fn to_ppu(&self, x: f64, y: f64) -> Option<(usize, usize)> {
+        if x < 0.0 || y < 0.0 || x >= self.width || y >= self.height {
+            return None;
+        }
+        Some((
+            self.left + (x / self.scale_x) as usize,
+            self.top + (y / self.scale_y) as usize,
+        ))
+    }
+}
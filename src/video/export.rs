@@ -0,0 +1,104 @@
+use super::frame::Frame;
+use super::scaler::Image;
+
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Exportação de imagens ========================================
+// Salva a tela e as visualizações de debug em PNG, com nomes
+// automáticos no formato <rom>_<data>_<hora><sufixo>.<extensão>
+
+// Pasta padrão dos arquivos gerados pelas teclas de atalho
+pub const EXPORT_FOLDER: &str = "screenshots";
+
+impl Image {
+    pub fn save_png(&self, path: &str) -> Result<(), String> {
+        create_parent_folder(path)?;
+        let mut rgba = vec![0; self.width * self.height * 4];
+        self.write_rgba(&mut rgba);
+
+        image::save_buffer(
+            path,
+            &rgba,
+            self.width as u32,
+            self.height as u32,
+            image::ColorType::Rgba8,
+        )
+        .map_err(|e| format!("{}: {}", path, e))
+    }
+}
+
+impl Frame {
+    /** Salva os pixels do Frame (set_pixel) em PNG */
+    pub fn save_png(&mut self, path: &str) -> Result<(), String> {
+        create_parent_folder(path)?;
+        self.update_canvas();
+        self.canvas
+            .save(path)
+            .map_err(|e| format!("{}: {}", path, e))
+    }
+}
+
+fn create_parent_folder(path: &str) -> Result<(), String> {
+    match Path::new(path).parent() {
+        Some(folder) if !folder.as_os_str().is_empty() => {
+            fs::create_dir_all(folder).map_err(|e| format!("{}: {}", folder.display(), e))
+        }
+        _ => Ok(()),
+    }
+}
+
+/** Nome de arquivo automático dentro de EXPORT_FOLDER, usando o nome da ROM e a data/hora
+(UTC). Se o arquivo já existir um contador é adicionado no final */
+pub fn export_file_name(rom: &str, suffix: &str, extension: &str) -> String {
+    let rom_name = Path::new(rom)
+        .file_stem()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "nes".to_string());
+    let base = format!("{}/{}_{}{}", EXPORT_FOLDER, rom_name, timestamp(), suffix);
+
+    let mut file_name = format!("{}.{}", base, extension);
+    let mut count = 1;
+    while Path::new(&file_name).exists() {
+        count += 1;
+        file_name = format!("{}_{}.{}", base, count, extension);
+    }
+    file_name
+}
+
+// Data e hora atuais no formato AAAA-MM-DD_HH-MM-SS
+fn timestamp() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or(0);
+    let (year, month, day) = civil_from_days((seconds / 86400) as i64);
+    let time = seconds % 86400;
+
+    format!(
+        "{:04}-{:02}-{:02}_{:02}-{:02}-{:02}",
+        year,
+        month,
+        day,
+        time / 3600,
+        (time / 60) % 60,
+        time % 60
+    )
+}
+
+// Converte dias desde 1970-01-01 para (ano, mês, dia)
+// confira: http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
@@ -1,13 +1,17 @@
-extern crate graphics;
 extern crate image;
-extern crate opengl_graphics;
 
+#[cfg(feature = "window")]
 use graphics::{Context, Transformed};
 use image::{ImageBuffer, Rgba};
+#[cfg(feature = "window")]
 use opengl_graphics::TextureSettings;
+#[cfg(feature = "window")]
 use piston_window::{G2d, G2dTexture, G2dTextureContext, Texture};
 
+#[cfg(feature = "window")]
+use super::palette::Palette;
 use super::pixel::{Pixel, BLACK_PIXEL};
+use super::scaler::Image;
 
 const PIXEL_SIZE: f64 = 2.3;
 
@@ -16,6 +20,7 @@ pub struct Frame {
     pub width: usize,
     pub height: usize,
     pub canvas: ImageBuffer<Rgba<u8>, Vec<u8>>,
+    #[cfg(feature = "window")]
     pub texture: Option<G2dTexture>,
     data: Vec<Pixel>,
 } // Matrix de pixels linhasxcolunas
@@ -27,6 +32,7 @@ impl Frame {
             height,
             data: vec![BLACK_PIXEL; width * height],
             canvas: ImageBuffer::new(width as u32, height as u32),
+            #[cfg(feature = "window")]
             texture: None,
         }
     }
@@ -50,6 +56,7 @@ impl Frame {
         }
     }
 
+    #[cfg(feature = "window")]
     pub fn render(&self, x: usize, y: usize, pixel_size: f64, context: Context, gl: &mut G2d) {
         for pixel_y in 0..self.height {
             for pixel_x in 0..self.width {
@@ -68,22 +75,38 @@ impl Frame {
     }
 
     pub fn update_canvas(&mut self) {
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let position: usize = (y * self.width) + x;
-                if position < self.data.len() {
-                    let color = self.data[position];
-
-                    self.canvas
-                        .put_pixel(x as u32, y as u32, Rgba(color.get_color_u8()))
-                }
-            }
+        let canvas: &mut [u8] = &mut self.canvas;
+        for (color, out) in self.data.iter().zip(canvas.chunks_exact_mut(4)) {
+            out.copy_from_slice(&color.get_color_u8());
         }
     }
 
+    /** Os pixels do Frame (set_pixel) como uma Image */
+    pub fn canvas_image(&mut self) -> Image {
+        self.update_canvas();
+        Image::from_rgba(self.width, self.height, &self.canvas)
+    }
+
+    #[cfg(feature = "window")]
     pub fn update_texture(&mut self, context: &mut G2dTextureContext) {
         self.update_canvas();
+        self.upload_texture(context);
+    }
 
+    /** Atualiza a textura direto de uma tela de índices da PPU, sem passar pelos pixels do Frame */
+    #[cfg(feature = "window")]
+    pub fn update_texture_indexed(
+        &mut self,
+        context: &mut G2dTextureContext,
+        indices: &[u16],
+        palette: &Palette,
+    ) {
+        palette.to_rgba(indices, &mut self.canvas);
+        self.upload_texture(context);
+    }
+
+    #[cfg(feature = "window")]
+    pub fn upload_texture(&mut self, context: &mut G2dTextureContext) {
         if let Some(texture) = &mut self.texture {
             texture.update(context, &self.canvas).unwrap();
         } else {
@@ -93,6 +116,27 @@ impl Frame {
         }
     }
 
+    /** Desenha a textura esticada para ocupar width x height na tela */
+    #[cfg(feature = "window")]
+    pub fn render_image_size(
+        &mut self,
+        x: usize,
+        y: usize,
+        width: f64,
+        height: f64,
+        context: Context,
+        gl: &mut G2d,
+    ) {
+        if let Some(texture) = &mut self.texture {
+            let transform = context
+                .transform
+                .trans(x as f64, y as f64)
+                .scale(width / self.width as f64, height / self.height as f64);
+            piston_window::image(texture, transform, gl);
+        }
+    }
+
+    #[cfg(feature = "window")]
     pub fn render_image(&mut self, x: usize, y: usize, scale: f64, context: Context, gl: &mut G2d) {
         if let Some(texture) = &mut self.texture {
             let transform = context
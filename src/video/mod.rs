@@ -1,11 +1,35 @@
+#[cfg(feature = "window")]
 mod debug;
+mod display;
+mod export;
 mod frame;
+mod ntsc_filter;
+mod palette;
 mod pixel;
+#[cfg(feature = "window")]
+mod ppu_debug;
+mod recorder;
+mod scaler;
+#[cfg(feature = "window")]
 mod utils;
 mod video;
+#[cfg(feature = "window")]
+mod window;
 
+#[cfg(feature = "window")]
 pub use debug::*;
+pub use display::*;
+pub use export::*;
 pub use frame::*;
+pub use ntsc_filter::*;
+pub use palette::*;
 pub use pixel::*;
+#[cfg(feature = "window")]
+pub use ppu_debug::*;
+pub use recorder::*;
+pub use scaler::*;
+#[cfg(feature = "window")]
 pub use utils::*;
 pub use video::*;
+#[cfg(feature = "window")]
+pub use window::*;
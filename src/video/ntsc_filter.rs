@@ -0,0 +1,211 @@
+use super::palette::{ntsc_angle, ntsc_signal, yiq_to_pixel, NtscParams, Palette, PALETTE_SIZE};
+
+// Filtro NTSC ==================================================
+// Simula em software o caminho do sinal entre a PPU e a TV: cada
+// pixel vira 8 amostras do sinal composto (a PPU gera o sinal no
+// clock mestre, 8 clocks por pixel e 12 clocks por ciclo da
+// subportadora de cor) e a "TV" separa luminância e crominância com
+// filtros passa-baixa. Como os filtros não são perfeitos aparecem os
+// artefatos que muitos jogos usam de propósito: cores misturadas no
+// dithering, franjas coloridas nas bordas e o "dot crawl".
+//
+// A fase da subportadora avança 4 amostras por scanline (341 * 8 = 2728)
+// e também muda de um frame para o outro, por isso o padrão dos
+// artefatos se move na tela.
+//
+// Baseado na ideia do nes_ntsc do Blargg, mas sem os kernels
+// pré-calculados: a separação é feita com médias móveis (somas de
+// prefixo) sobre o sinal de cada linha.
+// confira: https://www.nesdev.org/wiki/NTSC_video
+
+const SAMPLES_PER_PIXEL: usize = 8;
+const INPUT_WIDTH: usize = 256;
+const LINE_SAMPLES: usize = INPUT_WIDTH * SAMPLES_PER_PIXEL;
+
+// A saída tem 2 pixels para cada pixel da PPU, o suficiente para mostrar os artefatos
+pub const NTSC_OUTPUT_WIDTH: usize = INPUT_WIDTH * 2;
+const OUTPUT_STEP: usize = LINE_SAMPLES / NTSC_OUTPUT_WIDTH;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NtscPreset {
+    /** Cabo RCA: luminância e crominância no mesmo sinal, com todos os artefatos */
+    Composite,
+    /** Luminância e crominância em fios separados, sem dot crawl */
+    SVideo,
+    /** Sem NTSC, cada pixel vira a cor exata da paleta */
+    Rgb,
+    /** TV preto e branco: só a luminância do sinal composto, sem artefatos */
+    Monochrome,
+}
+
+impl NtscPreset {
+    pub fn from(name: &str) -> Option<NtscPreset> {
+        match name {
+            "composite" => Some(NtscPreset::Composite),
+            "svideo" | "s-video" => Some(NtscPreset::SVideo),
+            "rgb" => Some(NtscPreset::Rgb),
+            "mono" | "monochrome" => Some(NtscPreset::Monochrome),
+            _ => None,
+        }
+    }
+
+    /** Próximo preset, usado pela tecla que troca o filtro */
+    pub fn next(&self) -> Option<NtscPreset> {
+        match self {
+            NtscPreset::Composite => Some(NtscPreset::SVideo),
+            NtscPreset::SVideo => Some(NtscPreset::Rgb),
+            NtscPreset::Rgb => Some(NtscPreset::Monochrome),
+            NtscPreset::Monochrome => None,
+        }
+    }
+
+    // Largura (em amostras) dos filtros de luminância e crominância. Uma janela
+    // de 12 amostras cobre um ciclo inteiro da subportadora e separa os sinais
+    // perfeitamente, janelas menores deixam a cor vazar para o brilho
+    fn luma_window(&self) -> usize {
+        match self {
+            NtscPreset::Composite => 10,
+            NtscPreset::SVideo => 4,
+            NtscPreset::Rgb => SAMPLES_PER_PIXEL,
+            NtscPreset::Monochrome => 12,
+        }
+    }
+
+    fn chroma_window(&self) -> usize {
+        match self {
+            NtscPreset::Composite => 24,
+            _ => 12,
+        }
+    }
+}
+
+pub struct NtscFilter {
+    pub preset: NtscPreset,
+    pub params: NtscParams,
+    // sinal normalizado de cada índice de 9 bits nas 12 fases da subportadora
+    signal: Vec<[f32; 12]>,
+    // luminância de cada índice (média das 12 fases), usada no S-Video
+    luma: Vec<f32>,
+    // cosseno e seno da subportadora em cada fase, recalculados a cada frame (hue)
+    carrier: [(f32, f32); 12],
+    // somas de prefixo da linha atual
+    luma_sums: Vec<f32>,
+    i_sums: Vec<f32>,
+    q_sums: Vec<f32>,
+}
+
+impl NtscFilter {
+    pub fn new(preset: NtscPreset, params: NtscParams) -> NtscFilter {
+        let mut signal = vec![[0.0; 12]; PALETTE_SIZE];
+        let mut luma = vec![0.0; PALETTE_SIZE];
+
+        for (index, phases) in signal.iter_mut().enumerate() {
+            for (phase, value) in phases.iter_mut().enumerate() {
+                *value = ntsc_signal(index as u16, phase as u16);
+            }
+            luma[index] = phases.iter().sum::<f32>() / 12.0;
+        }
+
+        NtscFilter {
+            preset,
+            params,
+            signal,
+            luma,
+            carrier: [(0.0, 0.0); 12],
+            luma_sums: vec![0.0; LINE_SAMPLES + 1],
+            i_sums: vec![0.0; LINE_SAMPLES + 1],
+            q_sums: vec![0.0; LINE_SAMPLES + 1],
+        }
+    }
+
+    /** Largura da imagem gerada pelo filtro */
+    pub fn output_width(&self) -> usize {
+        NTSC_OUTPUT_WIDTH
+    }
+
+    /** Aplica o filtro em uma tela de índices da PPU (256 x altura) e escreve a imagem em
+    RGBA (NTSC_OUTPUT_WIDTH x altura, 4 bytes por pixel). "frame" é o número do frame
+    emulado, que define a fase do dot crawl. A paleta só é usada pelo preset RGB */
+    pub fn apply(&mut self, indices: &[u16], palette: &Palette, frame: u32, rgba: &mut [u8]) {
+        let height = indices.len() / INPUT_WIDTH;
+
+        // Dot crawl: a fase inicial muda a cada frame
+        let frame_phase = (frame % 3) as usize * 4;
+
+        for (phase, carrier) in self.carrier.iter_mut().enumerate() {
+            let angle = ntsc_angle(phase as u16, &self.params);
+            *carrier = (angle.cos(), angle.sin());
+        }
+
+        for y in 0..height {
+            let line = &indices[y * INPUT_WIDTH..(y + 1) * INPUT_WIDTH];
+            let out = &mut rgba[y * NTSC_OUTPUT_WIDTH * 4..(y + 1) * NTSC_OUTPUT_WIDTH * 4];
+
+            if self.preset == NtscPreset::Rgb {
+                for (x, pixel) in out.chunks_exact_mut(4).enumerate() {
+                    pixel.copy_from_slice(&palette.get(line[x / 2]).get_color_u8());
+                }
+                continue;
+            }
+
+            let line_phase = (frame_phase + y * 4) % 12;
+            self.modulate_line(line, line_phase);
+            self.demodulate_line(out);
+        }
+    }
+
+    // Gera o sinal da linha e guarda as somas de prefixo da luminância e das
+    // componentes I e Q (sinal multiplicado pelo cosseno/seno da subportadora)
+    fn modulate_line(&mut self, line: &[u16], line_phase: usize) {
+        let (mut luma, mut i, mut q) = (0.0, 0.0, 0.0);
+
+        for sample in 0..LINE_SAMPLES {
+            let index = line[sample / SAMPLES_PER_PIXEL] as usize & (PALETTE_SIZE - 1);
+            let phase = (line_phase + sample) % 12;
+            let signal = self.signal[index][phase];
+
+            let (luma_signal, chroma_signal) = match self.preset {
+                // Fios separados: a luminância não tem a subportadora e vice-versa
+                NtscPreset::SVideo => (self.luma[index], signal - self.luma[index]),
+                _ => (signal, signal),
+            };
+
+            let (cos, sin) = self.carrier[phase];
+            luma += luma_signal;
+            i += chroma_signal * cos;
+            q += chroma_signal * sin;
+
+            self.luma_sums[sample + 1] = luma;
+            self.i_sums[sample + 1] = i;
+            self.q_sums[sample + 1] = q;
+        }
+    }
+
+    fn demodulate_line(&self, out: &mut [u8]) {
+        let luma_window = self.preset.luma_window();
+        let chroma_window = self.preset.chroma_window();
+
+        for (x, pixel) in out.chunks_exact_mut(4).enumerate() {
+            let center = x * OUTPUT_STEP + OUTPUT_STEP / 2;
+
+            let y = average(&self.luma_sums, center, luma_window);
+            let (i, q) = if self.preset == NtscPreset::Monochrome {
+                (0.0, 0.0)
+            } else {
+                (
+                    average(&self.i_sums, center, chroma_window),
+                    average(&self.q_sums, center, chroma_window),
+                )
+            };
+
+            pixel.copy_from_slice(&yiq_to_pixel(y, i, q, &self.params).get_color_u8());
+        }
+    }
+}
+
+// Média móvel de uma janela centrada na amostra, usando as somas de prefixo
+fn average(sums: &[f32], center: usize, window: usize) -> f32 {
+    let start = center.saturating_sub(window / 2);
+    let end = (start + window).min(sums.len() - 1);
+    (sums[end] - sums[start]) / (end - start) as f32
+}
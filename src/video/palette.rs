@@ -0,0 +1,235 @@
+use super::pixel::{Pixel, PALETTE_SCREEN};
+
+use std::f32::consts::PI;
+use std::fs;
+
+// A PPU gera um índice de 9 bits por pixel: 6 bits da cor na paleta
+// (0x00-0x3F) e 3 bits de ênfase (vermelho, verde e azul, na mesma
+// ordem dos bits 5-7 do registrador Mask). Cada bit de ênfase escurece
+// os outros dois canais, então a paleta final tem 8 * 64 = 512 cores
+// confira: https://www.nesdev.org/wiki/Colour_emphasis
+pub const PALETTE_SIZE: usize = 0x200;
+
+// Atenuação aplicada aos canais que não estão em ênfase
+const EMPHASIS_ATTENUATION: f32 = 0.816328;
+
+// Tensões do sinal composto da PPU, normalizadas pelo nível do sync.
+// 4 níveis para a parte baixa da onda e 4 para a parte alta
+// confira: https://www.nesdev.org/wiki/NTSC_video
+const SIGNAL_LEVELS: [f32; 8] = [0.350, 0.518, 0.962, 1.550, 1.094, 1.506, 1.962, 1.962];
+const SIGNAL_BLACK: f32 = 0.518;
+const SIGNAL_WHITE: f32 = 1.962;
+// A ênfase atenua o sinal durante a fase da cor correspondente
+const SIGNAL_EMPHASIS: f32 = 0.746;
+// Fase do color burst em relação ao início do pixel (em ciclos de 1/12 da subportadora)
+const BURST_PHASE: f32 = 3.9;
+
+/** Parâmetros do gerador de paleta NTSC, os valores padrão geram uma paleta
+parecida com a de um NES ligado em uma TV de tubo */
+#[derive(Debug, Clone, Copy)]
+pub struct NtscParams {
+    /** Rotação do matiz em graus */
+    pub hue: f32,
+    /** Multiplicador da crominância */
+    pub saturation: f32,
+    /** Multiplicador da luminância */
+    pub contrast: f32,
+    /** Soma na luminância */
+    pub brightness: f32,
+    /** Gamma da TV, a saída é corrigida para 2.2 */
+    pub gamma: f32,
+}
+
+impl NtscParams {
+    pub fn new() -> NtscParams {
+        NtscParams {
+            hue: 0.0,
+            saturation: 1.0,
+            contrast: 1.0,
+            brightness: 0.0,
+            gamma: 1.8,
+        }
+    }
+}
+
+pub struct Palette {
+    colors: [Pixel; PALETTE_SIZE],
+}
+
+impl Palette {
+    /** Paleta padrão, as 64 cores de PALETTE_SCREEN com as 7 combinações de ênfase */
+    pub fn new() -> Palette {
+        Palette::from_base(&PALETTE_SCREEN)
+    }
+
+    /** Monta as 512 cores a partir das 64 cores sem ênfase */
+    pub fn from_base(base: &[Pixel; 0x40]) -> Palette {
+        let mut colors = [Pixel::new(0, 0, 0); PALETTE_SIZE];
+
+        for emphasis in 0..8 {
+            for color in 0..0x40 {
+                colors[(emphasis << 6) | color] = apply_emphasis(base[color], emphasis as u8);
+            }
+        }
+
+        Palette { colors }
+    }
+
+    /** Lê um arquivo .pal com 64 cores (192 bytes) ou 512 cores (1536 bytes, com ênfase) */
+    pub fn from_file(file_name: &str) -> Result<Palette, String> {
+        let data = fs::read(file_name).map_err(|e| format!("{}: {}", file_name, e))?;
+        Palette::from_bytes(&data).map_err(|e| format!("{}: {}", file_name, e))
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Palette, String> {
+        let rgb = |i: usize| Pixel::new(data[i * 3], data[i * 3 + 1], data[i * 3 + 2]);
+
+        match data.len() {
+            192 => {
+                let mut base = [Pixel::new(0, 0, 0); 0x40];
+                for (i, color) in base.iter_mut().enumerate() {
+                    *color = rgb(i);
+                }
+                Ok(Palette::from_base(&base))
+            }
+            1536 => {
+                let mut colors = [Pixel::new(0, 0, 0); PALETTE_SIZE];
+                for (i, color) in colors.iter_mut().enumerate() {
+                    *color = rgb(i);
+                }
+                Ok(Palette { colors })
+            }
+            size => Err(format!(
+                "invalid palette size {} (expected 192 or 1536 bytes)",
+                size
+            )),
+        }
+    }
+
+    /** Calcula as 512 cores simulando o sinal composto que a PPU gera para cada índice
+    e decodificando como uma TV NTSC (YIQ -> RGB) */
+    pub fn generate(params: &NtscParams) -> Palette {
+        let mut colors = [Pixel::new(0, 0, 0); PALETTE_SIZE];
+
+        for (index, color) in colors.iter_mut().enumerate() {
+            *color = ntsc_color(index as u16, params);
+        }
+
+        Palette { colors }
+    }
+
+    /** Cor de um índice de 9 bits (bits 0-5 cor, bits 6-8 ênfase) */
+    pub fn get(&self, index: u16) -> Pixel {
+        self.colors[index as usize & (PALETTE_SIZE - 1)]
+    }
+
+    /** Converte uma tela de índices para RGBA (4 bytes por pixel) */
+    pub fn to_rgba(&self, indices: &[u16], rgba: &mut [u8]) {
+        for (index, out) in indices.iter().zip(rgba.chunks_exact_mut(4)) {
+            out.copy_from_slice(&self.get(*index).get_color_u8());
+        }
+    }
+}
+
+fn apply_emphasis(pixel: Pixel, emphasis: u8) -> Pixel {
+    let [r, g, b, _] = pixel.get_color_u8();
+    let mut channels = [r as f32, g as f32, b as f32];
+
+    // bit 0 = vermelho, bit 1 = verde, bit 2 = azul
+    for bit in 0..3 {
+        if emphasis & (1 << bit) == 0 {
+            continue;
+        }
+        for (channel, value) in channels.iter_mut().enumerate() {
+            if channel != bit {
+                *value *= EMPHASIS_ATTENUATION;
+            }
+        }
+    }
+
+    Pixel::new(
+        channels[0].round() as u8,
+        channels[1].round() as u8,
+        channels[2].round() as u8,
+    )
+}
+
+// A cor (bits 0-3) é a fase da onda quadrada que a PPU gera: durante 6 dos 12
+// ciclos da subportadora o sinal fica no nível alto e nos outros 6 no baixo
+fn in_color_phase(color: u16, phase: u16) -> bool {
+    (color + phase) % 12 < 6
+}
+
+/** Tensão do sinal composto de um índice de 9 bits em uma das 12 fases da
+subportadora, já normalizada (0.0 = preto, 1.0 = branco) */
+pub(super) fn ntsc_signal(index: u16, phase: u16) -> f32 {
+    let color = index & 0x0F;
+    let level = ((index >> 4) & 0x03) as usize;
+    let emphasis = index >> 6;
+
+    // As cores 0x0 só usam o nível alto, 0xD só o nível baixo e 0xE/0xF são pretas
+    let (low, high) = if color > 0xD {
+        (SIGNAL_LEVELS[1], SIGNAL_LEVELS[1])
+    } else {
+        (
+            SIGNAL_LEVELS[level + 4 * (color == 0x0) as usize],
+            SIGNAL_LEVELS[level + 4 * (color < 0xD) as usize],
+        )
+    };
+
+    let mut signal = if in_color_phase(color, phase) {
+        high
+    } else {
+        low
+    };
+
+    let emphasized = (emphasis & 0x01 != 0 && in_color_phase(0, phase))
+        || (emphasis & 0x02 != 0 && in_color_phase(4, phase))
+        || (emphasis & 0x04 != 0 && in_color_phase(8, phase));
+    if emphasized && color < 0xE {
+        signal *= SIGNAL_EMPHASIS;
+    }
+
+    (signal - SIGNAL_BLACK) / (SIGNAL_WHITE - SIGNAL_BLACK)
+}
+
+/** Ângulo da subportadora usado para demodular a crominância em uma fase */
+pub(super) fn ntsc_angle(phase: u16, params: &NtscParams) -> f32 {
+    PI / 6.0 * (phase as f32 + BURST_PHASE) + params.hue.to_radians()
+}
+
+/** Decodifica YIQ para RGB aplicando os ajustes da TV */
+pub(super) fn yiq_to_pixel(y: f32, i: f32, q: f32, params: &NtscParams) -> Pixel {
+    let y = y * params.contrast + params.brightness;
+    let i = i * params.saturation;
+    let q = q * params.saturation;
+
+    let gamma = |value: f32| {
+        let value = if value <= 0.0 {
+            0.0
+        } else {
+            value.powf(2.2 / params.gamma)
+        };
+        (value * 255.0).round().clamp(0.0, 255.0) as u8
+    };
+
+    Pixel::new(
+        gamma(y + 0.946882 * i + 0.623557 * q),
+        gamma(y - 0.274788 * i - 0.635691 * q),
+        gamma(y - 1.108545 * i + 1.709007 * q),
+    )
+}
+
+fn ntsc_color(index: u16, params: &NtscParams) -> Pixel {
+    // A TV tira a média de um ciclo inteiro da subportadora
+    let (mut y, mut i, mut q) = (0.0, 0.0, 0.0);
+    for phase in 0..12 {
+        let signal = ntsc_signal(index, phase) / 12.0;
+        let angle = ntsc_angle(phase, params);
+        y += signal;
+        i += signal * angle.cos();
+        q += signal * angle.sin();
+    }
+
+    yiq_to_pixel(y, i, q, params)
+}
@@ -110,7 +110,3 @@ pub const PALETTE_SCREEN: [Pixel; 0x40] = [
     Pixel(0, 0, 0),       // 0x3E
     Pixel(0, 0, 0),       // 0x3F
 ];
-
-pub fn get_color(color: u8) -> Pixel {
-    PALETTE_SCREEN[(color & 0x3F) as usize]
-}
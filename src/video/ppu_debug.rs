@@ -0,0 +1,510 @@
+use graphics::{types::Color, Context, Rectangle};
+use piston_window::*;
+
+use super::display::ScreenArea;
+use super::utils::{DrawText, RED, WHITE};
+use crate::ppu::{Ppu2C02, PpuEvent, PpuEventKind};
+
+// Visualizações de debug da PPU ================================
+
+pub const YELLOW: Color = [1.0, 1.0, 0.0, 1.0];
+const GRID: Color = [1.0, 1.0, 1.0, 0.25];
+const ATTRIBUTE_GRID: Color = [0.0, 1.0, 1.0, 0.5];
+const HIDDEN: Color = [1.0, 1.0, 1.0, 0.4];
+
+// Tamanho das 4 nametables lado a lado
+pub const NAME_TABLES_WIDTH: usize = 512;
+pub const NAME_TABLES_HEIGHT: usize = 480;
+
+// Grade dos 64 sprites da OAM (8x8 células de 8x16) desenhada com escala 3
+const OAM_SCALE: f64 = 3.0;
+pub const OAM_TABLE_WIDTH: usize = 64 * 3;
+pub const OAM_TABLE_HEIGHT: usize = 128 * 3;
+const OAM_LIST_FONT_SIZE: usize = 12;
+
+// Mapa de eventos: 341 ciclos x 262 scanlines com escala 1.5
+const EVENT_SCALE: f64 = 1.5;
+pub const EVENT_MAP_WIDTH: usize = 512;
+pub const EVENT_MAP_HEIGHT: usize = 393;
+const EVENT_BACKGROUND: Color = [0.15, 0.15, 0.15, 1.0];
+const EVENT_VISIBLE: Color = [0.3, 0.3, 0.3, 1.0];
+const EVENT_KINDS: [PpuEventKind; 4] = [
+    PpuEventKind::RegisterRead,
+    PpuEventKind::RegisterWrite,
+    PpuEventKind::Nmi,
+    PpuEventKind::SpriteZeroHit,
+];
+
+pub struct NameTableOptions {
+    pub tile_grid: bool,
+    pub attribute_grid: bool,
+    /** Posição do mouse relativa ao canto das nametables */
+    pub hover: Option<(usize, usize)>,
+}
+
+pub struct SpriteOptions {
+    /** Linha da tela usada para destacar os sprites selecionados */
+    pub line: i16,
+    /** Posição do mouse relativa ao canto da grade de sprites */
+    pub hover: Option<(usize, usize)>,
+}
+
+fn draw_border(color: Color, x: f64, y: f64, width: f64, height: f64, c: Context, gl: &mut G2d) {
+    Rectangle::new_border(color, 0.5).draw([x, y, width, height], &c.draw_state, c.transform, gl);
+}
+
+fn draw_grid(color: Color, x: f64, y: f64, step: f64, c: Context, gl: &mut G2d) {
+    let line = graphics::Line::new(color, 0.5);
+    let (width, height) = (NAME_TABLES_WIDTH as f64, NAME_TABLES_HEIGHT as f64);
+
+    let mut offset = 0.0;
+    while offset <= width {
+        line.draw(
+            [x + offset, y, x + offset, y + height],
+            &c.draw_state,
+            c.transform,
+            gl,
+        );
+        offset += step;
+    }
+
+    // As linhas horizontais recomeçam em cada nametable (240 não é múltiplo de 32)
+    for table_y in [0.0, 240.0] {
+        let mut offset = 0.0;
+        while offset <= 240.0 {
+            let line_y = y + table_y + offset;
+            line.draw(
+                [x, line_y, x + width, line_y],
+                &c.draw_state,
+                c.transform,
+                gl,
+            );
+            offset += step;
+        }
+    }
+}
+
+/** Desenha as 4 nametables com a área visível do scroll (tram_addr + fine_x), as grades
+opcionais e as informações do tile embaixo do mouse */
+pub fn draw_name_tables(
+    x: usize,
+    y: usize,
+    ppu: &mut Ppu2C02,
+    options: &NameTableOptions,
+    c: Context,
+    gl: &mut G2d,
+    glyphs: &mut Glyphs,
+) {
+    ppu.sprite_name_table.render_image(x, y, 1.0, c, gl);
+    let (x0, y0) = (x as f64, y as f64);
+
+    if options.tile_grid {
+        draw_grid(GRID, x0, y0, 8.0, c, gl);
+    }
+    if options.attribute_grid {
+        draw_grid(ATTRIBUTE_GRID, x0, y0, 32.0, c, gl);
+    }
+
+    // Scroll: o canto da tela dentro das 4 nametables. A área de 256x240 dá a volta
+    // nas bordas, então pode ser desenhada em até 4 pedaços
+    let scroll = &ppu.tram_addr;
+    let scroll_x = scroll.get_nametable_x() as usize * 256
+        + scroll.get_coarse_x() as usize * 8
+        + ppu.fine_x as usize;
+    let scroll_y = scroll.get_nametable_y() as usize * 240
+        + (scroll.get_coarse_y() as usize * 8 + scroll.get_fine_y() as usize) % 240;
+
+    for (start_x, width) in wrap(scroll_x, 256, NAME_TABLES_WIDTH) {
+        for (start_y, height) in wrap(scroll_y, 240, NAME_TABLES_HEIGHT) {
+            draw_border(
+                YELLOW,
+                x0 + start_x as f64,
+                y0 + start_y as f64,
+                width as f64,
+                height as f64,
+                c,
+                gl,
+            );
+        }
+    }
+
+    let mut text = DrawText::new(x, y + NAME_TABLES_HEIGHT, c);
+    text.draw_line(
+        &format!("SCROLL: X {} Y {}", scroll_x, scroll_y),
+        WHITE,
+        gl,
+        glyphs,
+    );
+
+    if let Some((hover_x, hover_y)) = options.hover {
+        let tile = ppu.get_name_table_tile(hover_x, hover_y);
+        draw_border(
+            WHITE,
+            x0 + (hover_x - hover_x % 8) as f64,
+            y0 + (hover_y - hover_y % 8) as f64,
+            8.0,
+            8.0,
+            c,
+            gl,
+        );
+
+        text.draw_line(
+            &format!(
+                "TILE: ${:02X} ({}, {}) NT {}  ADDR: ${:04X}",
+                tile.tile_id, tile.tile_x, tile.tile_y, tile.table, tile.addres
+            ),
+            WHITE,
+            gl,
+            glyphs,
+        );
+        text.draw_line(
+            &format!(
+                "ATTR: ${:04X}  PALETTE: {}",
+                tile.attribute_addres, tile.palette
+            ),
+            WHITE,
+            gl,
+            glyphs,
+        );
+    }
+}
+
+// Sprites com Y entre $EF e $FF ficam abaixo da tela, é assim que os jogos escondem sprites
+fn is_hidden(y: u8) -> bool {
+    y >= 0xEF
+}
+
+// Prioridade atrás do background (B) e espelhamento horizontal (H) e vertical (V)
+fn sprite_flags(attribute: u8) -> String {
+    [(0x20, 'B'), (0x40, 'H'), (0x80, 'V')]
+        .iter()
+        .map(|&(bit, flag)| if attribute & bit > 0 { flag } else { '-' })
+        .collect()
+}
+
+// Cor do sprite i na grade e na lista: amarelo se é desenhado na linha escolhida,
+// vermelho se está na linha mas passou do limite de 8 sprites
+fn sprite_color(i: usize, selected: &[usize]) -> Option<Color> {
+    match selected.iter().position(|&sprite| sprite == i) {
+        Some(order) if order < 8 => Some(YELLOW),
+        Some(_) => Some(RED),
+        None => None,
+    }
+}
+
+/** Desenha os 64 sprites da OAM com a lista de posições, tiles, paletas e flags.
+Os sprites que a PPU seleciona para a linha escolhida ficam destacados */
+pub fn draw_sprites(
+    x: usize,
+    y: usize,
+    ppu: &mut Ppu2C02,
+    options: &SpriteOptions,
+    c: Context,
+    gl: &mut G2d,
+    glyphs: &mut Glyphs,
+) {
+    ppu.sprite_oam_table.render_image(x, y, OAM_SCALE, c, gl);
+    let (x0, y0) = (x as f64, y as f64);
+    let height = ppu.sprite_height();
+    let selected = ppu.sprites_on_scanline(options.line);
+
+    let cell_width = 8.0 * OAM_SCALE;
+    let cell_height = 16.0 * OAM_SCALE;
+    for (i, sprite) in ppu.oam.iter().enumerate() {
+        let cell_x = x0 + (i % 8) as f64 * cell_width;
+        let cell_y = y0 + (i / 8) as f64 * cell_height;
+        if let Some(color) = sprite_color(i, &selected) {
+            let sprite_height = height as f64 * OAM_SCALE;
+            draw_border(color, cell_x, cell_y, cell_width, sprite_height, c, gl);
+        }
+
+        // Lista em 2 colunas de 32 sprites ao lado da grade
+        let mut text = DrawText::with_font_size(
+            OAM_LIST_FONT_SIZE,
+            x + OAM_TABLE_WIDTH + 20 + (i / 32) * 180,
+            y + (i % 32) * OAM_LIST_FONT_SIZE,
+            c,
+        );
+        let color = match sprite_color(i, &selected) {
+            Some(color) => color,
+            None if is_hidden(sprite.y) => HIDDEN,
+            None => WHITE,
+        };
+        text.draw_line(
+            &format!(
+                "{:02} {:3} {:3} ${:02X} {} {}",
+                i,
+                sprite.x,
+                sprite.y,
+                sprite.id,
+                sprite.attribute & 0x03,
+                sprite_flags(sprite.attribute)
+            ),
+            color,
+            gl,
+            glyphs,
+        );
+    }
+
+    let mut text = DrawText::new(x, y + OAM_TABLE_HEIGHT + 10, c);
+    text.draw_line(
+        &format!(
+            "LINE: {}  SPRITES: {}{}",
+            options.line,
+            selected.len().min(8),
+            if selected.len() > 8 {
+                " (OVERFLOW)"
+            } else {
+                ""
+            }
+        ),
+        WHITE,
+        gl,
+        glyphs,
+    );
+    text.draw_line(
+        &format!("SIZE: 8x{}  FLAGS: B=BEHIND H/V=FLIP", height),
+        WHITE,
+        gl,
+        glyphs,
+    );
+
+    if let Some((hover_x, hover_y)) = options.hover {
+        let i = (hover_y / cell_height as usize) * 8 + hover_x / cell_width as usize;
+        let sprite = ppu.oam[i];
+        draw_border(
+            WHITE,
+            x0 + (i % 8) as f64 * cell_width,
+            y0 + (i / 8) as f64 * cell_height,
+            cell_width,
+            cell_height,
+            c,
+            gl,
+        );
+
+        text.draw_line(
+            &format!(
+                "SPRITE {:02}  X: {}  Y: {}  TILE: ${:02X}",
+                i, sprite.x, sprite.y, sprite.id
+            ),
+            WHITE,
+            gl,
+            glyphs,
+        );
+        text.draw_line(
+            &format!(
+                "PALETTE: {}  PRIORITY: {}  FLIP: {}",
+                4 + (sprite.attribute & 0x03),
+                if sprite.attribute & 0x20 > 0 {
+                    "BEHIND"
+                } else {
+                    "FRONT"
+                },
+                &sprite_flags(sprite.attribute)[1..]
+            ),
+            WHITE,
+            gl,
+            glyphs,
+        );
+    }
+}
+
+/** Desenha a caixa de cada sprite visível sobre a tela do jogo e a linha escolhida */
+pub fn draw_sprite_boxes(ppu: &Ppu2C02, line: i16, area: &ScreenArea, c: Context, gl: &mut G2d) {
+    let selected = ppu.sprites_on_scanline(line);
+    let height = ppu.sprite_height() as f64;
+
+    // Recorta no espaço da tela para as caixas não invadirem o painel de debug
+    let draw_state = c
+        .draw_state
+        .scissor([0, 0, area.width as u32, area.height as u32]);
+    let c = Context { draw_state, ..c };
+
+    for (i, sprite) in ppu.oam.iter().enumerate() {
+        if is_hidden(sprite.y) {
+            continue;
+        }
+
+        // O sprite aparece uma linha abaixo do Y da OAM
+        let (left, top) = area.to_window(sprite.x as f64, sprite.y as f64 + 1.0);
+        let color = sprite_color(i, &selected).unwrap_or(GRID);
+        draw_border(
+            color,
+            left,
+            top,
+            8.0 * area.scale_x,
+            height * area.scale_y,
+            c,
+            gl,
+        );
+    }
+
+    let (_, line_y) = area.to_window(0.0, line as f64 + 0.5);
+    graphics::Line::new(HIDDEN, 0.5).draw(
+        [0.0, line_y, area.width, line_y],
+        &c.draw_state,
+        c.transform,
+        gl,
+    );
+}
+
+fn event_color(kind: PpuEventKind) -> Color {
+    match kind {
+        PpuEventKind::RegisterRead => [0.3, 0.6, 1.0, 1.0],
+        PpuEventKind::RegisterWrite => [0.2, 1.0, 0.2, 1.0],
+        PpuEventKind::Nmi => RED,
+        PpuEventKind::SpriteZeroHit => YELLOW,
+    }
+}
+
+fn register_name(addres: u16) -> &'static str {
+    if addres == 0x4014 {
+        return "OAMDMA";
+    }
+    if !(0x2000..=0x3FFF).contains(&addres) {
+        return "";
+    }
+    match addres & 0x0007 {
+        0 => "PPUCTRL",
+        1 => "PPUMASK",
+        2 => "PPUSTATUS",
+        3 => "OAMADDR",
+        4 => "OAMDATA",
+        5 => "PPUSCROLL",
+        6 => "PPUADDR",
+        _ => "PPUDATA",
+    }
+}
+
+// Posição do evento no mapa, o pre-render (-1) fica na última linha
+fn event_position(event: &PpuEvent) -> (f64, f64) {
+    let line = if event.scanline < 0 {
+        261
+    } else {
+        event.scanline
+    };
+    (
+        (event.cycle as f64 + 0.5) * EVENT_SCALE,
+        (line as f64 + 0.5) * EVENT_SCALE,
+    )
+}
+
+/** Evento mais perto da posição (x, y) do mapa, até 4 pixels de distância */
+pub fn find_event(events: &[PpuEvent], x: usize, y: usize) -> Option<PpuEvent> {
+    let distance = |event: &PpuEvent| {
+        let (event_x, event_y) = event_position(event);
+        (event_x - x as f64).powi(2) + (event_y - y as f64).powi(2)
+    };
+    events
+        .iter()
+        .filter(|event| distance(event) <= 16.0)
+        .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+        .copied()
+}
+
+/** Desenha os eventos do último frame em um mapa de 341 ciclos x 262 scanlines, com a
+área visível da tela mais clara, e os detalhes do evento selecionado */
+pub fn draw_events(
+    x: usize,
+    y: usize,
+    ppu: &Ppu2C02,
+    selected: Option<&PpuEvent>,
+    c: Context,
+    gl: &mut G2d,
+    glyphs: &mut Glyphs,
+) {
+    let (x0, y0) = (x as f64, y as f64);
+    Rectangle::new(EVENT_BACKGROUND).draw(
+        [x0, y0, 341.0 * EVENT_SCALE, 262.0 * EVENT_SCALE],
+        &c.draw_state,
+        c.transform,
+        gl,
+    );
+    // Os pixels visíveis são gerados nos ciclos 1-256 das scanlines 0-239
+    Rectangle::new(EVENT_VISIBLE).draw(
+        [
+            x0 + EVENT_SCALE,
+            y0,
+            256.0 * EVENT_SCALE,
+            240.0 * EVENT_SCALE,
+        ],
+        &c.draw_state,
+        c.transform,
+        gl,
+    );
+
+    let events = &ppu.events.last_frame;
+    for event in events {
+        let (event_x, event_y) = event_position(event);
+        Rectangle::new(event_color(event.kind)).draw(
+            [x0 + event_x - 1.5, y0 + event_y - 1.5, 3.0, 3.0],
+            &c.draw_state,
+            c.transform,
+            gl,
+        );
+    }
+
+    let mut text = DrawText::with_font_size(14, x, y + EVENT_MAP_HEIGHT + 6, c);
+    text.break_line();
+    for kind in EVENT_KINDS.iter() {
+        text.draw(&format!("{} ", kind.name()), event_color(*kind), gl, glyphs);
+    }
+
+    let mut text = DrawText::new(x, y + EVENT_MAP_HEIGHT + 30, c);
+    text.draw_line(&format!("EVENTS: {}", events.len()), WHITE, gl, glyphs);
+
+    if let Some(event) = selected {
+        let (event_x, event_y) = event_position(event);
+        draw_border(
+            WHITE,
+            x0 + event_x - 3.5,
+            y0 + event_y - 3.5,
+            7.0,
+            7.0,
+            c,
+            gl,
+        );
+
+        text.draw_line(
+            &format!(
+                "SCANLINE: {}  CYCLE: {}  {}",
+                event.scanline,
+                event.cycle,
+                event.kind.name()
+            ),
+            event_color(event.kind),
+            gl,
+            glyphs,
+        );
+        match event.kind {
+            PpuEventKind::RegisterRead | PpuEventKind::RegisterWrite => text.draw_line(
+                &format!(
+                    "${:04X} {} = ${:02X}",
+                    event.addres,
+                    register_name(event.addres),
+                    event.data
+                ),
+                WHITE,
+                gl,
+                glyphs,
+            ),
+            _ => {}
+        }
+        text.draw_line(
+            &format!("VRAM ADDR: ${:04X}", event.vram_addr),
+            WHITE,
+            gl,
+            glyphs,
+        );
+    }
+}
+
+// Divide um intervalo [start, start + size) que dá a volta em "limit" em até 2 pedaços
+fn wrap(start: usize, size: usize, limit: usize) -> Vec<(usize, usize)> {
+    let start = start % limit;
+    if start + size <= limit {
+        vec![(start, size)]
+    } else {
+        vec![(start, limit - start), (0, start + size - limit)]
+    }
+}
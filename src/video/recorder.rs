@@ -0,0 +1,498 @@
+use super::scaler::Image;
+
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, RgbaImage};
+use std::fs::File;
+use std::io::{BufWriter, Error, Seek, SeekFrom, Write};
+
+// Gravação de vídeo ============================================
+// Grava cada frame emulado, sem pular nenhum, em um arquivo de vídeo
+// sem compressão (Y4M ou AVI RGB) e um WAV com o mesmo nome. O tempo
+// do vídeo segue o tempo emulado e não o relógio: o NES NTSC roda a
+// 60.0988 frames por segundo (21.477272 MHz / 4 / 341 / 262 com o
+// ciclo pulado nos frames ímpares), então a taxa é a fração exata.
+//
+// O emulador ainda não tem APU, então o WAV é silêncio, mas com o
+// número exato de amostras de cada frame para ficar sincronizado.
+//
+// confira:
+// - Y4M: https://wiki.multimedia.cx/index.php/YUV4MPEG2
+// - AVI: https://learn.microsoft.com/en-us/windows/win32/directshow/avi-riff-file-reference
+
+// Frames por segundo = FRAME_RATE_NUMERATOR / FRAME_RATE_DENOMINATOR
+pub const FRAME_RATE_NUMERATOR: u64 = 39375000;
+pub const FRAME_RATE_DENOMINATOR: u64 = 655171;
+
+const AUDIO_SAMPLE_RATE: u64 = 44100;
+
+// O GIF só tem delays em centésimos de segundo, então grava 1 a cada 2 frames (~30 fps)
+const GIF_FRAME_SKIP: u64 = 2;
+
+// Os tamanhos do RIFF (AVI e WAV) são de 32 bits, a gravação para antes de passar disso
+const RIFF_LIMIT: u64 = u32::MAX as u64;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecordFormat {
+    Y4m,
+    Avi,
+    Gif,
+}
+
+impl RecordFormat {
+    pub fn from(name: &str) -> Option<RecordFormat> {
+        match name {
+            "y4m" => Some(RecordFormat::Y4m),
+            "avi" => Some(RecordFormat::Avi),
+            "gif" => Some(RecordFormat::Gif),
+            _ => None,
+        }
+    }
+
+    /** Formato a partir da extensão do arquivo */
+    pub fn from_file_name(file_name: &str) -> Option<RecordFormat> {
+        let extension = file_name.rsplit('.').next()?.to_lowercase();
+        RecordFormat::from(&extension)
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            RecordFormat::Y4m => "y4m",
+            RecordFormat::Avi => "avi",
+            RecordFormat::Gif => "gif",
+        }
+    }
+}
+
+enum VideoStream {
+    Y4m(BufWriter<File>),
+    Avi(AviWriter),
+    Gif(Box<GifEncoder<BufWriter<File>>>),
+}
+
+pub struct Recorder {
+    pub format: RecordFormat,
+    pub path: String,
+    pub width: usize,
+    pub height: usize,
+    frames: u64,
+    video: VideoStream,
+    audio: Option<WavWriter>,
+    // tempo já gravado no GIF, em centésimos de segundo
+    gif_time: u64,
+}
+
+impl Recorder {
+    /** Começa a gravação. No Y4M e no AVI o áudio vai para um .wav com o mesmo nome */
+    pub fn start(
+        path: &str,
+        format: RecordFormat,
+        width: usize,
+        height: usize,
+    ) -> Result<Recorder, String> {
+        let error = |e: std::io::Error| format!("{}: {}", path, e);
+        let file = BufWriter::new(File::create(path).map_err(error)?);
+
+        let (video, audio) = match format {
+            RecordFormat::Y4m => {
+                let mut file = file;
+                writeln!(
+                    file,
+                    "YUV4MPEG2 W{} H{} F{}:{} Ip A1:1 C444",
+                    width, height, FRAME_RATE_NUMERATOR, FRAME_RATE_DENOMINATOR
+                )
+                .map_err(error)?;
+                (
+                    VideoStream::Y4m(file),
+                    Some(WavWriter::create(&wav_path(path))?),
+                )
+            }
+            RecordFormat::Avi => {
+                let avi = AviWriter::new(file, width, height).map_err(error)?;
+                (
+                    VideoStream::Avi(avi),
+                    Some(WavWriter::create(&wav_path(path))?),
+                )
+            }
+            RecordFormat::Gif => {
+                let mut encoder = GifEncoder::new_with_speed(file, 10);
+                encoder
+                    .set_repeat(Repeat::Infinite)
+                    .map_err(|e| format!("{}: {}", path, e))?;
+                (VideoStream::Gif(Box::new(encoder)), None)
+            }
+        };
+
+        Ok(Recorder {
+            format,
+            path: path.to_string(),
+            width,
+            height,
+            frames: 0,
+            video,
+            audio,
+            gif_time: 0,
+        })
+    }
+
+    /** Número de frames emulados desde o início da gravação */
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /** Grava o próximo frame emulado, a imagem precisa ter o tamanho do início da gravação */
+    pub fn push_frame(&mut self, image: &Image) -> Result<(), String> {
+        if image.width != self.width || image.height != self.height {
+            return Err(format!(
+                "{}: frame size changed from {}x{} to {}x{}",
+                self.path, self.width, self.height, image.width, image.height
+            ));
+        }
+
+        let path = &self.path;
+        let error = |e: std::io::Error| format!("{}: {}", path, e);
+        match &mut self.video {
+            VideoStream::Y4m(file) => write_y4m_frame(file, image).map_err(error)?,
+            VideoStream::Avi(avi) => avi.write_frame(image).map_err(error)?,
+            VideoStream::Gif(encoder) => {
+                if self.frames.is_multiple_of(GIF_FRAME_SKIP) {
+                    // Delay até o próximo frame gravado, sem acumular erro de arredondamento
+                    let end = frames_to_time(self.frames + GIF_FRAME_SKIP, 100);
+                    let delay = (end - self.gif_time) as u32;
+                    self.gif_time = end;
+
+                    let mut rgba = vec![0; image.width * image.height * 4];
+                    image.write_rgba(&mut rgba);
+                    let buffer =
+                        RgbaImage::from_raw(image.width as u32, image.height as u32, rgba).unwrap();
+                    let frame = image::Frame::from_parts(
+                        buffer,
+                        0,
+                        0,
+                        Delay::from_numer_denom_ms(delay * 10, 1),
+                    );
+                    encoder
+                        .encode_frame(frame)
+                        .map_err(|e| format!("{}: {}", path, e))?;
+                }
+            }
+        }
+
+        self.frames += 1;
+
+        // Amostras de áudio que cabem até o fim deste frame
+        if let Some(audio) = &mut self.audio {
+            let samples = frames_to_time(self.frames, AUDIO_SAMPLE_RATE) - audio.samples;
+            audio
+                .write_silence(samples)
+                .map_err(|e| format!("{}: {}", audio.path, e))?;
+        }
+
+        Ok(())
+    }
+
+    /** Termina a gravação e completa os cabeçalhos com os tamanhos finais */
+    pub fn finish(self) -> Result<(), String> {
+        let path = self.path;
+        let error = |e: std::io::Error| format!("{}: {}", path, e);
+
+        match self.video {
+            VideoStream::Y4m(mut file) => file.flush().map_err(error)?,
+            VideoStream::Avi(avi) => avi.finish().map_err(error)?,
+            // O trailer do GIF é escrito quando o encoder é descartado
+            VideoStream::Gif(encoder) => drop(encoder),
+        }
+
+        if let Some(audio) = self.audio {
+            let audio_path = audio.path.clone();
+            audio
+                .finish()
+                .map_err(|e| format!("{}: {}", audio_path, e))?;
+        }
+
+        Ok(())
+    }
+}
+
+// Tempo emulado no fim de "frames" frames, em unidades de 1/units segundo
+fn frames_to_time(frames: u64, units: u64) -> u64 {
+    frames * units * FRAME_RATE_DENOMINATOR / FRAME_RATE_NUMERATOR
+}
+
+fn wav_path(path: &str) -> String {
+    match path.rfind('.') {
+        Some(dot) => format!("{}.wav", &path[..dot]),
+        None => format!("{}.wav", path),
+    }
+}
+
+// RGB -> YCbCr (BT.601, faixa limitada), um plano inteiro de cada componente
+fn write_y4m_frame(file: &mut BufWriter<File>, image: &Image) -> std::io::Result<()> {
+    let size = image.width * image.height;
+    let mut planes = vec![0; size * 3];
+
+    for (i, pixel) in image.pixels.iter().enumerate() {
+        let (r, g, b) = (pixel[0] as f32, pixel[1] as f32, pixel[2] as f32);
+        planes[i] = (16.0 + 0.257 * r + 0.504 * g + 0.098 * b).round() as u8;
+        planes[size + i] = (128.0 - 0.148 * r - 0.291 * g + 0.439 * b).round() as u8;
+        planes[size * 2 + i] = (128.0 + 0.439 * r - 0.368 * g - 0.071 * b).round() as u8;
+    }
+
+    file.write_all(b"FRAME\n")?;
+    file.write_all(&planes)
+}
+
+fn write_u16(file: &mut BufWriter<File>, value: u16) -> std::io::Result<()> {
+    file.write_all(&value.to_le_bytes())
+}
+
+fn write_u32(file: &mut BufWriter<File>, value: u32) -> std::io::Result<()> {
+    file.write_all(&value.to_le_bytes())
+}
+
+// Volta no arquivo para completar um campo do cabeçalho
+fn patch_u32(file: &mut BufWriter<File>, position: u64, value: u32) -> std::io::Result<()> {
+    let end = file.stream_position()?;
+    file.seek(SeekFrom::Start(position))?;
+    write_u32(file, value)?;
+    file.seek(SeekFrom::Start(end))?;
+    Ok(())
+}
+
+// AVI com um stream de vídeo RGB de 24 bits sem compressão. Os tamanhos e o número de
+// frames só são conhecidos no final, então as posições desses campos são guardadas
+struct AviWriter {
+    file: BufWriter<File>,
+    width: usize,
+    height: usize,
+    frames: u32,
+    // posição e tamanho de cada frame dentro do LIST movi, para o índice idx1
+    index: Vec<(u32, u32)>,
+    total_frames_position: u64,
+    length_position: u64,
+    movi_position: u64,
+}
+
+impl AviWriter {
+    fn new(mut file: BufWriter<File>, width: usize, height: usize) -> std::io::Result<AviWriter> {
+        let frame_size = (row_size(width) * height) as u32;
+        let micro_seconds = (1_000_000 * FRAME_RATE_DENOMINATOR / FRAME_RATE_NUMERATOR) as u32;
+
+        file.write_all(b"RIFF")?;
+        write_u32(&mut file, 0)?;
+        file.write_all(b"AVI ")?;
+
+        // hdrl: avih (56 bytes) + LIST strl (strh 56 bytes + strf 40 bytes)
+        file.write_all(b"LIST")?;
+        write_u32(&mut file, 4 + (8 + 56) + (12 + (8 + 56) + (8 + 40)))?;
+        file.write_all(b"hdrl")?;
+
+        file.write_all(b"avih")?;
+        write_u32(&mut file, 56)?;
+        write_u32(&mut file, micro_seconds)?;
+        write_u32(&mut file, frame_size * 61)?; // bytes por segundo
+        write_u32(&mut file, 0)?; // padding
+        write_u32(&mut file, 0x10)?; // AVIF_HASINDEX
+        let total_frames_position = file.stream_position()?;
+        write_u32(&mut file, 0)?; // total de frames
+        write_u32(&mut file, 0)?; // frames iniciais
+        write_u32(&mut file, 1)?; // streams
+        write_u32(&mut file, frame_size)?;
+        write_u32(&mut file, width as u32)?;
+        write_u32(&mut file, height as u32)?;
+        file.write_all(&[0; 16])?;
+
+        file.write_all(b"LIST")?;
+        write_u32(&mut file, 4 + (8 + 56) + (8 + 40))?;
+        file.write_all(b"strl")?;
+
+        file.write_all(b"strh")?;
+        write_u32(&mut file, 56)?;
+        file.write_all(b"vids")?;
+        file.write_all(b"DIB ")?;
+        write_u32(&mut file, 0)?; // flags
+        write_u16(&mut file, 0)?; // prioridade
+        write_u16(&mut file, 0)?; // idioma
+        write_u32(&mut file, 0)?; // frames iniciais
+        write_u32(&mut file, FRAME_RATE_DENOMINATOR as u32)?; // escala
+        write_u32(&mut file, FRAME_RATE_NUMERATOR as u32)?; // taxa
+        write_u32(&mut file, 0)?; // início
+        let length_position = file.stream_position()?;
+        write_u32(&mut file, 0)?; // duração em frames
+        write_u32(&mut file, frame_size)?;
+        write_u32(&mut file, 0xFFFFFFFF)?; // qualidade
+        write_u32(&mut file, 0)?; // tamanho da amostra
+        write_u16(&mut file, 0)?;
+        write_u16(&mut file, 0)?;
+        write_u16(&mut file, width as u16)?;
+        write_u16(&mut file, height as u16)?;
+
+        // BITMAPINFOHEADER, altura positiva = linhas de baixo para cima
+        file.write_all(b"strf")?;
+        write_u32(&mut file, 40)?;
+        write_u32(&mut file, 40)?;
+        write_u32(&mut file, width as u32)?;
+        write_u32(&mut file, height as u32)?;
+        write_u16(&mut file, 1)?; // planos
+        write_u16(&mut file, 24)?; // bits por pixel
+        write_u32(&mut file, 0)?; // BI_RGB
+        write_u32(&mut file, frame_size)?;
+        file.write_all(&[0; 16])?;
+
+        let movi_position = file.stream_position()?;
+        file.write_all(b"LIST")?;
+        write_u32(&mut file, 0)?;
+        file.write_all(b"movi")?;
+
+        Ok(AviWriter {
+            file,
+            width,
+            height,
+            frames: 0,
+            index: vec![],
+            total_frames_position,
+            length_position,
+            movi_position,
+        })
+    }
+
+    fn write_frame(&mut self, image: &Image) -> std::io::Result<()> {
+        let row_size = row_size(self.width);
+        let mut data = vec![0; row_size * self.height];
+
+        // BGR, de baixo para cima, cada linha alinhada em 4 bytes
+        for y in 0..self.height {
+            let row = &mut data[(self.height - 1 - y) * row_size..];
+            for x in 0..self.width {
+                let pixel = image.pixels[y * self.width + x];
+                row[x * 3] = pixel[2];
+                row[x * 3 + 1] = pixel[1];
+                row[x * 3 + 2] = pixel[0];
+            }
+        }
+
+        // Tamanho final do arquivo com este frame e o idx1, sem OpenDML o RIFF não
+        // passa de 4GB e é melhor parar com um erro do que gerar um arquivo corrompido
+        let position = self.file.stream_position()?;
+        let end = position + 8 + data.len() as u64 + 8 + 16 * (self.index.len() as u64 + 1);
+        if end - 8 > RIFF_LIMIT {
+            return Err(Error::other("AVI reached the 4GB limit"));
+        }
+
+        // O offset do índice é relativo ao "movi"
+        let offset = position - (self.movi_position + 8);
+        self.index.push((offset as u32, data.len() as u32));
+
+        self.file.write_all(b"00db")?;
+        write_u32(&mut self.file, data.len() as u32)?;
+        self.file.write_all(&data)?;
+        self.frames += 1;
+        Ok(())
+    }
+
+    fn finish(mut self) -> std::io::Result<()> {
+        let movi_end = self.file.stream_position()?;
+
+        self.file.write_all(b"idx1")?;
+        write_u32(&mut self.file, self.index.len() as u32 * 16)?;
+        for (offset, size) in &self.index {
+            self.file.write_all(b"00db")?;
+            write_u32(&mut self.file, 0x10)?; // AVIIF_KEYFRAME
+            write_u32(&mut self.file, *offset)?;
+            write_u32(&mut self.file, *size)?;
+        }
+        let end = self.file.stream_position()?;
+
+        patch_u32(&mut self.file, 4, (end - 8) as u32)?;
+        patch_u32(&mut self.file, self.total_frames_position, self.frames)?;
+        patch_u32(&mut self.file, self.length_position, self.frames)?;
+        patch_u32(
+            &mut self.file,
+            self.movi_position + 4,
+            (movi_end - self.movi_position - 8) as u32,
+        )?;
+        self.file.flush()
+    }
+}
+
+fn row_size(width: usize) -> usize {
+    (width * 3 + 3) & !3
+}
+
+// WAV PCM 16 bits mono
+struct WavWriter {
+    path: String,
+    file: BufWriter<File>,
+    samples: u64,
+}
+
+impl WavWriter {
+    fn create(path: &str) -> Result<WavWriter, String> {
+        let error = |e: std::io::Error| format!("{}: {}", path, e);
+        let mut file = BufWriter::new(File::create(path).map_err(error)?);
+
+        write_wav_header(&mut file).map_err(error)?;
+
+        Ok(WavWriter {
+            path: path.to_string(),
+            file,
+            samples: 0,
+        })
+    }
+
+    fn write_silence(&mut self, samples: u64) -> std::io::Result<()> {
+        if 36 + (self.samples + samples) * 2 > RIFF_LIMIT {
+            return Err(Error::other("WAV reached the 4GB limit"));
+        }
+        self.file.write_all(&vec![0; samples as usize * 2])?;
+        self.samples += samples;
+        Ok(())
+    }
+
+    fn finish(mut self) -> std::io::Result<()> {
+        let data_size = (self.samples * 2) as u32;
+        patch_u32(&mut self.file, 4, 36 + data_size)?;
+        patch_u32(&mut self.file, 40, data_size)?;
+        self.file.flush()
+    }
+}
+
+fn write_wav_header(file: &mut BufWriter<File>) -> std::io::Result<()> {
+    file.write_all(b"RIFF")?;
+    write_u32(file, 0)?;
+    file.write_all(b"WAVEfmt ")?;
+    write_u32(file, 16)?;
+    write_u16(file, 1)?; // PCM
+    write_u16(file, 1)?; // canais
+    write_u32(file, AUDIO_SAMPLE_RATE as u32)?;
+    write_u32(file, AUDIO_SAMPLE_RATE as u32 * 2)?; // bytes por segundo
+    write_u16(file, 2)?; // bytes por amostra
+    write_u16(file, 16)?; // bits por amostra
+    file.write_all(b"data")?;
+    write_u32(file, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn avi_stops_before_4gb() {
+        let path = env::temp_dir().join("nes_avi_limit.avi");
+        let file = BufWriter::new(File::create(&path).unwrap());
+        let mut avi = AviWriter::new(file, 256, 240).unwrap();
+        let image = Image::new(256, 240);
+        assert!(avi.write_frame(&image).is_ok());
+
+        // Pula para perto do limite sem escrever nada, o próximo frame não cabe mais
+        let frame_size = (row_size(256) * 240) as u64;
+        avi.file
+            .seek(SeekFrom::Start(RIFF_LIMIT - frame_size))
+            .unwrap();
+        assert!(avi.write_frame(&image).is_err());
+        assert_eq!(avi.frames, 1);
+
+        drop(avi);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,666 @@
+// Filtros de pós-processamento =================================
+// Todos os filtros rodam na CPU e trabalham em uma imagem RGBA
+// simples (Image), sem depender da janela ou de texturas. Cada um
+// recebe uma imagem e devolve outra, maior, então eles podem ser
+// encadeados (ex: smooth2x seguido do efeito de CRT).
+//
+// confira:
+// - Scale2x/3x: https://www.scale2x.it/algorithm
+// - smoothNx:   regras simplificadas a partir do hqx, não é a tabela do hqNx
+//               (https://en.wikipedia.org/wiki/Hqx)
+// - xBR:        https://forums.libretro.com/t/xbr-algorithm-tutorial/123
+
+pub type Rgba = [u8; 4];
+
+#[derive(Clone)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Rgba>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize) -> Image {
+        Image {
+            width,
+            height,
+            pixels: vec![[0, 0, 0, 255]; width * height],
+        }
+    }
+
+    /** Cria a imagem a partir de um buffer RGBA (4 bytes por pixel) */
+    pub fn from_rgba(width: usize, height: usize, data: &[u8]) -> Image {
+        let pixels = data
+            .chunks_exact(4)
+            .take(width * height)
+            .map(|p| [p[0], p[1], p[2], p[3]])
+            .collect();
+        Image {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn write_rgba(&self, out: &mut [u8]) {
+        for (pixel, out) in self.pixels.iter().zip(out.chunks_exact_mut(4)) {
+            out.copy_from_slice(pixel);
+        }
+    }
+
+    /** Pixel na posição, as coordenadas fora da imagem repetem a borda */
+    pub fn get(&self, x: isize, y: isize) -> Rgba {
+        let x = x.clamp(0, self.width as isize - 1) as usize;
+        let y = y.clamp(0, self.height as isize - 1) as usize;
+        self.pixels[y * self.width + x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, pixel: Rgba) {
+        self.pixels[y * self.width + x] = pixel;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scaler {
+    /** Repete cada pixel N vezes */
+    Nearest(usize),
+    Scale2x,
+    Scale3x,
+    Smooth2x,
+    Smooth3x,
+    /** Escala N com a detecção de bordas do xBR (nível 1) e mistura em rampa */
+    Xbr(usize),
+    /** Escala 3x com scanlines e máscara de aperture grille */
+    Crt,
+}
+
+impl Scaler {
+    pub fn from(name: &str) -> Option<Scaler> {
+        match name {
+            "nearest2x" => Some(Scaler::Nearest(2)),
+            "nearest3x" => Some(Scaler::Nearest(3)),
+            "nearest4x" => Some(Scaler::Nearest(4)),
+            "scale2x" => Some(Scaler::Scale2x),
+            "scale3x" => Some(Scaler::Scale3x),
+            "smooth2x" => Some(Scaler::Smooth2x),
+            "smooth3x" => Some(Scaler::Smooth3x),
+            "xbr2x" => Some(Scaler::Xbr(2)),
+            "xbr3x" => Some(Scaler::Xbr(3)),
+            "xbr4x" => Some(Scaler::Xbr(4)),
+            "crt" => Some(Scaler::Crt),
+            _ => None,
+        }
+    }
+
+    /** Escalas trocadas pela tecla de atalho, o CRT tem uma tecla própria */
+    pub fn next(scaler: Option<Scaler>) -> Option<Scaler> {
+        match scaler {
+            None => Some(Scaler::Nearest(3)),
+            Some(Scaler::Nearest(_)) => Some(Scaler::Scale2x),
+            Some(Scaler::Scale2x) => Some(Scaler::Scale3x),
+            Some(Scaler::Scale3x) => Some(Scaler::Smooth2x),
+            Some(Scaler::Smooth2x) => Some(Scaler::Smooth3x),
+            Some(Scaler::Smooth3x) => Some(Scaler::Xbr(3)),
+            Some(Scaler::Xbr(_)) | Some(Scaler::Crt) => None,
+        }
+    }
+
+    pub fn apply(&self, image: &Image) -> Image {
+        match self {
+            Scaler::Nearest(scale) => nearest(image, *scale),
+            Scaler::Scale2x => scale2x(image),
+            Scaler::Scale3x => scale3x(image),
+            Scaler::Smooth2x => smooth(image, 2),
+            Scaler::Smooth3x => smooth(image, 3),
+            Scaler::Xbr(scale) => xbr(image, *scale),
+            Scaler::Crt => crt(image),
+        }
+    }
+}
+
+/** Sequência de filtros aplicados um depois do outro */
+pub struct PostProcess {
+    pub chain: Vec<Scaler>,
+}
+
+impl PostProcess {
+    pub fn new() -> PostProcess {
+        PostProcess { chain: vec![] }
+    }
+
+    pub fn apply(&self, image: Image) -> Image {
+        self.chain
+            .iter()
+            .fold(image, |image, scaler| scaler.apply(&image))
+    }
+}
+
+pub fn nearest(image: &Image, scale: usize) -> Image {
+    let mut out = Image::new(image.width * scale, image.height * scale);
+
+    for y in 0..out.height {
+        for x in 0..out.width {
+            out.set(x, y, image.get((x / scale) as isize, (y / scale) as isize));
+        }
+    }
+
+    out
+}
+
+// Vizinhança 3x3 de um pixel:
+// A B C
+// D E F
+// G H I
+fn neighbours(image: &Image, x: usize, y: usize) -> [Rgba; 9] {
+    let (x, y) = (x as isize, y as isize);
+    [
+        image.get(x - 1, y - 1),
+        image.get(x, y - 1),
+        image.get(x + 1, y - 1),
+        image.get(x - 1, y),
+        image.get(x, y),
+        image.get(x + 1, y),
+        image.get(x - 1, y + 1),
+        image.get(x, y + 1),
+        image.get(x + 1, y + 1),
+    ]
+}
+
+pub fn scale2x(image: &Image) -> Image {
+    let mut out = Image::new(image.width * 2, image.height * 2);
+
+    for y in 0..image.height {
+        for x in 0..image.width {
+            let [_, b, _, d, e, f, _, h, _] = neighbours(image, x, y);
+
+            let mut e0 = e;
+            let mut e1 = e;
+            let mut e2 = e;
+            let mut e3 = e;
+            if b != h && d != f {
+                if d == b {
+                    e0 = d;
+                }
+                if b == f {
+                    e1 = f;
+                }
+                if d == h {
+                    e2 = d;
+                }
+                if h == f {
+                    e3 = f;
+                }
+            }
+
+            out.set(x * 2, y * 2, e0);
+            out.set(x * 2 + 1, y * 2, e1);
+            out.set(x * 2, y * 2 + 1, e2);
+            out.set(x * 2 + 1, y * 2 + 1, e3);
+        }
+    }
+
+    out
+}
+
+pub fn scale3x(image: &Image) -> Image {
+    let mut out = Image::new(image.width * 3, image.height * 3);
+
+    for y in 0..image.height {
+        for x in 0..image.width {
+            let [a, b, c, d, e, f, g, h, i] = neighbours(image, x, y);
+
+            let mut block = [e; 9];
+            if b != h && d != f {
+                if d == b {
+                    block[0] = d;
+                }
+                if (d == b && e != c) || (b == f && e != a) {
+                    block[1] = b;
+                }
+                if b == f {
+                    block[2] = f;
+                }
+                if (d == b && e != g) || (d == h && e != a) {
+                    block[3] = d;
+                }
+                if (b == f && e != i) || (h == f && e != c) {
+                    block[5] = f;
+                }
+                if d == h {
+                    block[6] = d;
+                }
+                if (d == h && e != i) || (h == f && e != g) {
+                    block[7] = h;
+                }
+                if h == f {
+                    block[8] = f;
+                }
+            }
+
+            for (n, pixel) in block.iter().enumerate() {
+                out.set(x * 3 + n % 3, y * 3 + n / 3, *pixel);
+            }
+        }
+    }
+
+    out
+}
+
+// Converte para YUV, usado para comparar cores como o olho enxerga
+fn yuv(pixel: Rgba) -> (f32, f32, f32) {
+    let (r, g, b) = (pixel[0] as f32, pixel[1] as f32, pixel[2] as f32);
+    (
+        0.299 * r + 0.587 * g + 0.114 * b,
+        -0.169 * r - 0.331 * g + 0.5 * b,
+        0.5 * r - 0.419 * g - 0.081 * b,
+    )
+}
+
+// As duas cores são diferentes o suficiente para formar uma borda (limites do hqx)
+fn color_diff(a: Rgba, b: Rgba) -> bool {
+    let (ya, ua, va) = yuv(a);
+    let (yb, ub, vb) = yuv(b);
+    (ya - yb).abs() > 48.0 || (ua - ub).abs() > 7.0 || (va - vb).abs() > 6.0
+}
+
+// Mistura de cores com pesos
+fn blend(colors: &[(Rgba, u32)]) -> Rgba {
+    let total: u32 = colors.iter().map(|(_, weight)| weight).sum();
+    let mut out = [0; 4];
+
+    for (channel, value) in out.iter_mut().enumerate() {
+        let sum: u32 = colors
+            .iter()
+            .map(|(color, weight)| color[channel] as u32 * weight)
+            .sum();
+        *value = ((sum + total / 2) / total) as u8;
+    }
+
+    out
+}
+
+// Canto de um pixel no smoothNx. "a" é o vizinho na diagonal do canto, "b" e
+// "d" os vizinhos na vertical e na horizontal. Em vez das 256 combinações da
+// tabela do hqx o canto é decidido só pelas bordas entre o centro e os três
+// vizinhos do canto, por isso o resultado é parecido mas não igual ao hq2x
+fn smooth_corner(e: Rgba, a: Rgba, b: Rgba, d: Rgba) -> Rgba {
+    let edge_b = color_diff(e, b);
+    let edge_d = color_diff(e, d);
+
+    if edge_b && edge_d {
+        if !color_diff(b, d) {
+            // Borda diagonal cortando o canto
+            if color_diff(e, a) {
+                blend(&[(e, 2), (b, 1), (d, 1)])
+            } else {
+                blend(&[(e, 2), (b, 3), (d, 3)])
+            }
+        } else {
+            blend(&[(e, 6), (b, 1), (d, 1)])
+        }
+    } else if edge_b {
+        blend(&[(e, 3), (b, 1)])
+    } else if edge_d {
+        blend(&[(e, 3), (d, 1)])
+    } else if color_diff(e, a) {
+        blend(&[(e, 3), (a, 1)])
+    } else {
+        e
+    }
+}
+
+// Meio da borda de um pixel no smooth3x, "b" é o vizinho do lado e "d"/"f" os
+// vizinhos que formam os dois cantos daquele lado
+fn smooth_edge(e: Rgba, b: Rgba, d: Rgba, f: Rgba) -> Rgba {
+    let diagonal = color_diff(e, b) && (!color_diff(b, d) || !color_diff(b, f));
+    if diagonal {
+        blend(&[(e, 3), (b, 1)])
+    } else {
+        e
+    }
+}
+
+pub fn smooth(image: &Image, scale: usize) -> Image {
+    let mut out = Image::new(image.width * scale, image.height * scale);
+
+    for y in 0..image.height {
+        for x in 0..image.width {
+            let [a, b, c, d, e, f, g, h, i] = neighbours(image, x, y);
+
+            let top_left = smooth_corner(e, a, b, d);
+            let top_right = smooth_corner(e, c, b, f);
+            let bottom_left = smooth_corner(e, g, h, d);
+            let bottom_right = smooth_corner(e, i, h, f);
+
+            let block: Vec<Rgba> = if scale == 2 {
+                vec![top_left, top_right, bottom_left, bottom_right]
+            } else {
+                vec![
+                    top_left,
+                    smooth_edge(e, b, d, f),
+                    top_right,
+                    smooth_edge(e, d, b, h),
+                    e,
+                    smooth_edge(e, f, b, h),
+                    bottom_left,
+                    smooth_edge(e, h, d, f),
+                    bottom_right,
+                ]
+            };
+
+            for (n, pixel) in block.iter().enumerate() {
+                out.set(x * scale + n % scale, y * scale + n / scale, *pixel);
+            }
+        }
+    }
+
+    out
+}
+
+// Distância entre duas cores, com mais peso no brilho
+fn distance(a: Rgba, b: Rgba) -> f32 {
+    let (ya, ua, va) = yuv(a);
+    let (yb, ub, vb) = yuv(b);
+    48.0 * (ya - yb).abs() + 7.0 * (ua - ub).abs() + 6.0 * (va - vb).abs()
+}
+
+fn mix(a: Rgba, b: Rgba, alpha: f32) -> Rgba {
+    let mut out = [0; 4];
+    for channel in 0..4 {
+        out[channel] =
+            (a[channel] as f32 * (1.0 - alpha) + b[channel] as f32 * alpha).round() as u8;
+    }
+    out
+}
+
+// Detecta a borda em um canto do pixel com a regra do xBR (nível 1). O canto é
+// escolhido espelhando as coordenadas (sx, sy), os nomes são do canto inferior direito:
+//      A1 B1 C1
+//   A0 A  B  C  C4
+//   D0 D  E  F  F4
+//   G0 G  H  I  I4
+//      G5 H5 I5
+// Retorna a cor que deve invadir o canto, se existir uma borda
+fn xbr_corner(image: &Image, x: usize, y: usize, sx: isize, sy: isize) -> Option<Rgba> {
+    let p = |i: isize, j: isize| image.get(x as isize + i * sx, y as isize + j * sy);
+
+    let e = p(0, 0);
+    let (b, c, d, f) = (p(0, -1), p(1, -1), p(-1, 0), p(1, 0));
+    let (g, h, i) = (p(-1, 1), p(0, 1), p(1, 1));
+    let (f4, i4, h5, i5) = (p(2, 0), p(2, 1), p(0, 2), p(1, 2));
+
+    if e == f || e == h {
+        return None;
+    }
+
+    let wd1 =
+        distance(e, c) + distance(e, g) + distance(i, f4) + distance(i, h5) + 4.0 * distance(h, f);
+    let wd2 =
+        distance(h, d) + distance(h, i5) + distance(f, i4) + distance(f, b) + 4.0 * distance(e, i);
+
+    if wd1 < wd2 {
+        if distance(e, f) <= distance(e, h) {
+            Some(f)
+        } else {
+            Some(h)
+        }
+    } else {
+        None
+    }
+}
+
+pub fn xbr(image: &Image, scale: usize) -> Image {
+    let mut out = Image::new(image.width * scale, image.height * scale);
+    let corners = [(-1, -1), (1, -1), (-1, 1), (1, 1)];
+
+    for y in 0..image.height {
+        for x in 0..image.width {
+            let e = image.get(x as isize, y as isize);
+            let edges: Vec<(isize, isize, Rgba)> = corners
+                .iter()
+                .filter_map(|&(sx, sy)| {
+                    xbr_corner(image, x, y, sx, sy).map(|color| (sx, sy, color))
+                })
+                .collect();
+
+            for oy in 0..scale {
+                for ox in 0..scale {
+                    // Posição do centro do sub-pixel dentro do pixel (0.0 a 1.0)
+                    let u = (ox as f32 + 0.5) / scale as f32;
+                    let v = (oy as f32 + 0.5) / scale as f32;
+
+                    // A borda é uma linha diagonal cortando o canto, quanto mais
+                    // perto do canto mais a cor do vizinho domina
+                    let mut pixel = e;
+                    for &(sx, sy, color) in &edges {
+                        let u = if sx > 0 { u } else { 1.0 - u };
+                        let v = if sy > 0 { v } else { 1.0 - v };
+                        let alpha = (u + v - 1.0).clamp(0.0, 1.0);
+                        if alpha > 0.0 {
+                            pixel = mix(pixel, color, alpha);
+                        }
+                    }
+
+                    out.set(x * scale + ox, y * scale + oy, pixel);
+                }
+            }
+        }
+    }
+
+    out
+}
+
+// Intensidade de cada canal (R, G, B) nas 3 colunas da máscara de aperture grille
+const APERTURE_GRILLE: [[f32; 3]; 3] = [[1.0, 0.7, 0.7], [0.7, 1.0, 0.7], [0.7, 0.7, 1.0]];
+// Brilho da última linha de cada pixel, o espaço entre as scanlines
+const SCANLINE_GAP: f32 = 0.55;
+// Compensa a perda de brilho da máscara
+const CRT_GAIN: f32 = 1.2;
+
+pub fn crt(image: &Image) -> Image {
+    let mut out = Image::new(image.width * 3, image.height * 3);
+
+    for y in 0..out.height {
+        for x in 0..out.width {
+            let pixel = image.get((x / 3) as isize, (y / 3) as isize);
+            let mask = APERTURE_GRILLE[x % 3];
+            let scanline = if y % 3 == 2 { SCANLINE_GAP } else { 1.0 };
+
+            let mut color = [0, 0, 0, pixel[3]];
+            for channel in 0..3 {
+                let value = pixel[channel] as f32 * mask[channel] * scanline * CRT_GAIN;
+                color[channel] = value.round().clamp(0.0, 255.0) as u8;
+            }
+            out.set(x, y, color);
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHITE: Rgba = [255, 255, 255, 255];
+    const BLACK: Rgba = [0, 0, 0, 255];
+
+    // Imagem desenhada com texto: '#' é preto e '.' é branco
+    fn image(rows: &[&str]) -> Image {
+        let mut image = Image::new(rows[0].len(), rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                image.set(x, y, if c == '#' { BLACK } else { WHITE });
+            }
+        }
+        image
+    }
+
+    // Imagem com cores diferentes em cada pixel
+    fn gradient(width: usize, height: usize) -> Image {
+        let mut image = Image::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                image.set(x, y, [(x * 40) as u8, (y * 40) as u8, 128, 255]);
+            }
+        }
+        image
+    }
+
+    #[test]
+    fn output_sizes() {
+        let input = gradient(5, 4);
+        let cases = [
+            (Scaler::Nearest(2), 2),
+            (Scaler::Nearest(4), 4),
+            (Scaler::Scale2x, 2),
+            (Scaler::Scale3x, 3),
+            (Scaler::Smooth2x, 2),
+            (Scaler::Smooth3x, 3),
+            (Scaler::Xbr(2), 2),
+            (Scaler::Xbr(3), 3),
+            (Scaler::Xbr(4), 4),
+            (Scaler::Crt, 3),
+        ];
+        for (scaler, scale) in cases.iter() {
+            let out = scaler.apply(&input);
+            assert_eq!(
+                (out.width, out.height),
+                (5 * scale, 4 * scale),
+                "{:?}",
+                scaler
+            );
+            assert_eq!(out.pixels.len(), out.width * out.height, "{:?}", scaler);
+        }
+    }
+
+    #[test]
+    fn nearest_replicates_pixels() {
+        let input = gradient(4, 3);
+        let out = nearest(&input, 3);
+        for y in 0..out.height {
+            for x in 0..out.width {
+                assert_eq!(
+                    out.pixels[y * out.width + x],
+                    input.pixels[(y / 3) * input.width + x / 3]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn scale2x_diagonal_edge() {
+        // A borda diagonal é suavizada, o nearest deixaria degraus de 2 pixels
+        let input = image(&["..#", ".##", "###"]);
+        let expected = image(&[
+            "....##", //
+            "...###", //
+            "...###", //
+            ".#####", //
+            "######", //
+            "######",
+        ]);
+        assert_eq!(scale2x(&input).pixels, expected.pixels);
+    }
+
+    #[test]
+    fn scale2x_keeps_flat_areas() {
+        // Linhas retas (b == h ou d == f) não mudam
+        let input = image(&["...", "###", "..."]);
+        assert_eq!(scale2x(&input).pixels, nearest(&input, 2).pixels);
+    }
+
+    #[test]
+    fn scale3x_diagonal_edge() {
+        let input = image(&["..#", ".##", "###"]);
+        let expected = image(&[
+            "......###", //
+            ".....####", //
+            ".....####", //
+            "....#####", //
+            "...######", //
+            ".########", //
+            "#########", //
+            "#########", //
+            "#########",
+        ]);
+        assert_eq!(scale3x(&input).pixels, expected.pixels);
+    }
+
+    #[test]
+    fn scale3x_keeps_flat_areas() {
+        let input = image(&[".#.", ".#.", ".#."]);
+        assert_eq!(scale3x(&input).pixels, nearest(&input, 3).pixels);
+    }
+
+    #[test]
+    fn smooth_keeps_flat_areas() {
+        let input = image(&["...", "...", "..."]);
+        assert_eq!(smooth(&input, 2).pixels, nearest(&input, 2).pixels);
+        assert_eq!(smooth(&input, 3).pixels, nearest(&input, 3).pixels);
+    }
+
+    #[test]
+    fn smooth_blends_diagonal_edge() {
+        // O pixel branco (1, 0) tem a borda diagonal no canto inferior direito:
+        // o canto vira a mistura 2:1:1 do centro com os dois vizinhos pretos
+        let input = image(&["..#", ".##", "###"]);
+        let out = smooth(&input, 2);
+        assert_eq!(out.get(2, 0), WHITE);
+        assert_eq!(out.get(3, 1), [128, 128, 128, 255]);
+
+        // No 3x o centro de cada bloco é sempre o pixel original
+        let input = gradient(4, 3);
+        let out = smooth(&input, 3);
+        for y in 0..input.height {
+            for x in 0..input.width {
+                let center = out.get(x as isize * 3 + 1, y as isize * 3 + 1);
+                assert_eq!(center, input.get(x as isize, y as isize));
+            }
+        }
+    }
+
+    #[test]
+    fn xbr_keeps_straight_lines() {
+        let input = image(&["...", "###", "..."]);
+        assert_eq!(xbr(&input, 2).pixels, nearest(&input, 2).pixels);
+        assert_eq!(xbr(&input, 4).pixels, nearest(&input, 4).pixels);
+    }
+
+    #[test]
+    fn xbr_blends_diagonal_edge() {
+        // A borda diagonal invade o canto inferior direito do pixel branco (1, 0)
+        // em rampa: o sub-pixel do canto fica 2/3 preto e o canto oposto não muda
+        let input = image(&["..#", ".##", "###"]);
+        let out = xbr(&input, 3);
+        assert_eq!(out.get(3, 0), WHITE);
+        assert_eq!(out.get(5, 2), [85, 85, 85, 255]);
+        assert_eq!(out.get(0, 0), WHITE);
+        assert_eq!(out.get(8, 8), BLACK);
+    }
+
+    #[test]
+    fn crt_darkens_scanlines() {
+        let input = image(&["..", ".."]);
+        let out = crt(&input);
+        assert_eq!((out.width, out.height), (6, 6));
+
+        let brightness = |y: usize| -> u32 {
+            (0..out.width)
+                .map(|x| {
+                    out.get(x as isize, y as isize)[..3]
+                        .iter()
+                        .map(|c| *c as u32)
+                        .sum::<u32>()
+                })
+                .sum()
+        };
+        for y in 0..out.height {
+            if y % 3 == 2 {
+                assert!(brightness(y) < brightness(y - 1), "row {}", y);
+            } else {
+                assert_eq!(brightness(y), brightness(0), "row {}", y);
+            }
+        }
+    }
+}
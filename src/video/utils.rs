@@ -64,29 +64,23 @@ impl DrawText {
     pub fn draw_line(&mut self, text: &str, color: Color, gl: &mut G2d, glyphs: &mut Glyphs) {
         self.i += 1;
         self.j = 0;
-        draw_text(
-            self.x + self.j * self.font_size,
-            self.y + self.font_size * self.i,
-            text,
-            color,
-            self.c,
-            gl,
-            glyphs,
-        );
+        self.draw_at_cursor(text, color, gl, glyphs);
 
         self.j += text.len();
     }
 
+    // Desenha o texto na posição atual com o tamanho de fonte do DrawText
+    fn draw_at_cursor(&self, text: &str, color: Color, gl: &mut G2d, glyphs: &mut Glyphs) {
+        let x = self.x + self.j * self.font_size;
+        let y = self.y + self.font_size * self.i;
+        let transform = self.c.transform.trans(x as f64, y as f64);
+        Text::new_color(color, self.font_size as u32)
+            .draw(text, glyphs, &self.c.draw_state, transform, gl)
+            .unwrap();
+    }
+
     pub fn draw(&mut self, text: &str, color: Color, gl: &mut G2d, glyphs: &mut Glyphs) {
-        draw_text(
-            self.x + self.j * self.font_size,
-            self.y + self.font_size * self.i,
-            text,
-            color,
-            self.c,
-            gl,
-            glyphs,
-        );
+        self.draw_at_cursor(text, color, gl, glyphs);
 
         self.j += text.len();
     }
@@ -0,0 +1,251 @@
+; ROM de teste da PPU ==========================================
+; Escrita para os testes de regressão deste repositório (golden
+; hashes do modo headless), pode ser distribuída junto com ele.
+; NROM-128 com CHR ROM e mirroring vertical. A cada frame mexe em
+; quase tudo que a PPU faz:
+;   - fundo com scroll horizontal pelas duas nametables e split no meio
+;     da tela esperando o sprite 0 hit
+;   - sprites com flip, paletas, prioridade atrás do fundo, 9 sprites na
+;     mesma linha (overflow) e 8x16 nos frames 64-127
+;   - ênfase de cores e greyscale no PPUMASK
+;   - escritas e leituras do $2007 com incremento de 32 e leitura da paleta,
+;     guardadas na RAM ($10-$14)
+;
+; Montagem com o ca65:
+;   ca65 ppu_test.s -o ppu_test.o && ld65 -C ppu_test.cfg ppu_test.o -o ppu_test.nes
+;
+; Página zero:
+;   $00 contador de frames    $01 scroll X    $02 temporário
+;   $10-$13 coluna lida com incremento de 32    $14 cor lida da paleta
+
+; Header iNES
+    .byte $4E,$45,$53,$1A,$01,$01,$01,$00,$00,$00,$00,$00,$00,$00,$00,$00
+
+.org $C000
+reset:
+    SEI
+    CLD
+    LDX #$FF
+    TXS
+    INX
+    STX $2000
+    STX $2001
+vblank1:
+    BIT $2002
+    BPL vblank1
+
+    ; RAM zerada e sprites escondidos
+clear:
+    LDA #$00
+    STA $00,X
+    STA $0300,X
+    LDA #$F8
+    STA $0200,X
+    INX
+    BNE clear
+vblank2:
+    BIT $2002
+    BPL vblank2
+
+    ; Paletas
+    LDA #$3F
+    STA $2006
+    LDA #$00
+    STA $2006
+    LDX #$00
+load_palette:
+    LDA palette,X
+    STA $2007
+    INX
+    CPX #$20
+    BNE load_palette
+
+    ; Nametable 0: tiles 0-7 em sequência, os atributos também recebem 0-7
+    LDA #$20
+    STA $2006
+    LDA #$00
+    STA $2006
+    LDY #$04
+    LDX #$00
+fill_nt0:
+    TXA
+    AND #$07
+    STA $2007
+    INX
+    BNE fill_nt0
+    DEY
+    BNE fill_nt0
+
+    ; Nametable 1: cada tile se repete 4 vezes
+    LDY #$04
+fill_nt1:
+    TXA
+    LSR A
+    LSR A
+    AND #$07
+    STA $2007
+    INX
+    BNE fill_nt1
+    DEY
+    BNE fill_nt1
+
+    ; Coluna 2 da nametable 0 com o tile 9, incremento de 32
+    LDA #$04
+    STA $2000
+    LDA #$20
+    STA $2006
+    LDA #$02
+    STA $2006
+    LDX #$1E
+    LDA #$09
+write_column:
+    STA $2007
+    DEX
+    BNE write_column
+
+    ; Lê de volta as 4 primeiras linhas da coluna (a primeira leitura só enche o buffer)
+    LDA #$20
+    STA $2006
+    LDA #$02
+    STA $2006
+    LDA $2007
+    LDX #$00
+read_column:
+    LDA $2007
+    STA $10,X
+    INX
+    CPX #$04
+    BNE read_column
+
+    ; A paleta é lida sem o buffer
+    LDA #$3F
+    STA $2006
+    LDA #$05
+    STA $2006
+    LDA $2007
+    STA $14
+
+    ; Sprites
+    LDX #$00
+load_sprites:
+    LDA sprites,X
+    STA $0200,X
+    INX
+    CPX #$44
+    BNE load_sprites
+
+    LDA #$00
+    STA $2005
+    STA $2005
+    LDA #$80
+    STA $2000
+    LDA #$1E
+    STA $2001
+
+    ; Split: depois do sprite 0 hit o resto da tela fica sem scroll
+main:
+    BIT $2002
+    BVS main
+wait_hit:
+    BIT $2002
+    BVC wait_hit
+    LDA #$00
+    STA $2005
+    STA $2005
+    JMP main
+
+nmi:
+    PHA
+    TXA
+    PHA
+    LDA #$02
+    STA $4014
+    INC $00
+
+    ; Scroll de 2 pixels por frame, o carry escolhe a nametable
+    LDA $00
+    ASL A
+    STA $01
+    INC $0207
+    DEC $020B
+    LDA #$80
+    ADC #$00
+    BIT $00
+    BVC set_control
+    ORA #$20
+set_control:
+    STA $2000
+
+    ; Greyscale a cada 8 frames e ênfase trocando a cada 16
+    LDA $00
+    AND #$08
+    LSR A
+    LSR A
+    LSR A
+    STA $02
+    LDA $00
+    ASL A
+    AND #$E0
+    ORA #$1E
+    ORA $02
+    STA $2001
+
+    BIT $2002
+    LDA $01
+    STA $2005
+    LDA #$00
+    STA $2005
+    PLA
+    TAX
+    PLA
+irq:
+    RTI
+
+palette:
+    .byte $0F,$01,$11,$21,$0F,$06,$16,$26,$0F,$09,$19,$29,$0F,$02,$12,$22
+    .byte $0F,$14,$24,$34,$0F,$17,$27,$37,$0F,$1A,$2A,$3A,$0F,$0C,$1C,$2C
+
+; Y, tile, atributos, X
+sprites:
+    .byte $20,$03,$00,$48
+    .byte $40,$08,$00,$10
+    .byte $50,$08,$41,$F0
+    .byte $60,$08,$82,$80
+    .byte $70,$08,$C3,$90
+    .byte $80,$05,$20,$A0
+    .byte $90,$04,$01,$A4
+    .byte $90,$06,$02,$A8
+    .byte $B0,$07,$00,$10
+    .byte $B0,$07,$01,$20
+    .byte $B0,$07,$02,$30
+    .byte $B0,$07,$03,$40
+    .byte $B0,$07,$00,$50
+    .byte $B0,$07,$01,$60
+    .byte $B0,$07,$02,$70
+    .byte $B0,$07,$03,$80
+    .byte $B0,$07,$00,$90
+
+    .res $FFFA - *, $00
+    .word nmi, reset, irq
+
+; CHR ROM
+.org $0000
+    ; 0: vazio
+    .byte $00,$00,$00,$00,$00,$00,$00,$00,$00,$00,$00,$00,$00,$00,$00,$00
+    ; 1-3: cores sólidas
+    .byte $FF,$FF,$FF,$FF,$FF,$FF,$FF,$FF,$00,$00,$00,$00,$00,$00,$00,$00
+    .byte $00,$00,$00,$00,$00,$00,$00,$00,$FF,$FF,$FF,$FF,$FF,$FF,$FF,$FF
+    .byte $FF,$FF,$FF,$FF,$FF,$FF,$FF,$FF,$FF,$FF,$FF,$FF,$FF,$FF,$FF,$FF
+    ; 4: xadrez
+    .byte $AA,$55,$AA,$55,$AA,$55,$AA,$55,$0F,$0F,$0F,$0F,$F0,$F0,$F0,$F0
+    ; 5: diagonais
+    .byte $80,$40,$20,$10,$08,$04,$02,$01,$01,$02,$04,$08,$10,$20,$40,$80
+    ; 6: moldura
+    .byte $FF,$81,$81,$81,$81,$81,$81,$FF,$00,$7E,$42,$42,$42,$42,$7E,$00
+    ; 7: listras
+    .byte $FF,$00,$FF,$00,$FF,$00,$FF,$00,$00,$FF,$00,$FF,$00,$FF,$00,$FF
+    ; 8: seta, diferente em cada eixo para mostrar o flip
+    .byte $F0,$F8,$FC,$FE,$FF,$0F,$07,$03,$01,$03,$07,$00,$00,$E0,$C0,$80
+    ; 9: marca da coluna
+    .byte $3C,$42,$81,$81,$81,$81,$42,$3C,$3C,$7E,$FF,$FF,$FF,$FF,$7E,$3C
+    .res $2000 - *, $00